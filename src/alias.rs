@@ -0,0 +1,57 @@
+//! Objective aliases applied when reporting, so inconsistently named
+//! sessions still add up: `[aliases]` maps names to the canonical one,
+//! `[alias_patterns]` maps regular expressions to a replacement.
+
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use regex::Regex;
+
+use crate::config::Config;
+
+#[derive(Debug, Clone, Default)]
+pub struct Aliases {
+    exact: HashMap<String, String>,
+    /// Tried in the order of their patterns
+    patterns: Vec<(Regex, String)>,
+}
+
+impl Aliases {
+    pub fn load(config: &Config) -> Result<Aliases> {
+        let exact = config
+            .section("aliases")
+            .into_iter()
+            .map(|(from, to)| (from.to_string(), to.to_string()))
+            .collect();
+        let patterns = config
+            .section("alias_patterns")
+            .into_iter()
+            .map(|(pattern, to)| {
+                // Anchored so a pattern has to match the whole objective
+                let regex = Regex::new(&format!("^(?:{})$", pattern))
+                    .with_context(|| format!("Invalid alias pattern: {}", pattern))?;
+                Ok((regex, to.to_string()))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Aliases { exact, patterns })
+    }
+
+    /// The canonical name of `objective`, `$1` etc. in a pattern's
+    /// replacement refer to its groups.
+    pub fn apply(&self, objective: &str) -> String {
+        if let Some(to) = self.exact.get(objective) {
+            return to.clone();
+        }
+        self.patterns
+            .iter()
+            .find(|(regex, _)| regex.is_match(objective))
+            .map_or_else(
+                || objective.to_string(),
+                |(regex, to)| regex.replace(objective, to.as_str()).into_owned(),
+            )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exact.is_empty() && self.patterns.is_empty()
+    }
+}
@@ -0,0 +1,168 @@
+//! Fast paths for `start` and `stop` that only touch the end of the storage
+//! file instead of parsing and rewriting all of it.
+//!
+//! Unlike `write`, these modify the file in place. The storage file is
+//! still backed up first and every change is journaled.
+
+use std::convert::TryFrom;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use anyhow::{Error, Result};
+use csv::{ReaderBuilder, StringRecord, WriterBuilder};
+
+use crate::{backup, chain, crypt, git, journal, lock, log, schema, Tracker, HEADER};
+
+/// The last record is looked for within this many bytes of the file end.
+const MAX_RECORD_LEN: u64 = 64 * 1024;
+
+/// The last entry of the storage file and the byte offset its row starts at.
+pub struct Last {
+    offset: u64,
+    pub entry: Tracker,
+    row: Vec<String>,
+}
+
+fn encode(row: &[String]) -> Result<Vec<u8>> {
+    let mut writer = WriterBuilder::new().from_writer(Vec::new());
+    writer.write_record(row)?;
+    writer.into_inner().map_err(|e| Error::msg(e.to_string()))
+}
+
+fn parse_row(bytes: &[u8]) -> Option<StringRecord> {
+    let mut rdr = ReaderBuilder::new().has_headers(false).from_reader(bytes);
+    let mut records = rdr.records();
+    let record = records.next()?.ok()?;
+    match records.next() {
+        None => Some(record),
+        Some(_) => None,
+    }
+}
+
+/// Whether the fast path may be used: encryption and the hash chain are
+/// off, it's no dry run, nothing is locked and the file exists and has the
+/// schema version and header this version writes. Otherwise callers fall
+/// back to a full rewrite.
+pub fn usable(path: &Path) -> bool {
+    if crypt::enabled() || chain::enabled() || crate::dry_run() || lock::enabled(path) {
+        return false;
+    }
+    let mut file = match File::open(path) {
+        Ok(file) => BufReader::new(file),
+        Err(_) => return false,
+    };
+    // The marker line and the header line
+    let mut head = Vec::new();
+    if file.read_until(b'\n', &mut head).is_err()
+        || file.read_until(b'\n', &mut head).is_err()
+        || schema::marked(&head) != Some(schema::VERSION)
+    {
+        return false;
+    }
+    let mut rdr = ReaderBuilder::new()
+        .has_headers(true)
+        .comment(Some(b'#'))
+        .from_reader(&head[..]);
+    match rdr.headers() {
+        Ok(headers) => headers.iter().eq(HEADER.iter().copied()),
+        Err(_) => false,
+    }
+}
+
+/// Finds the last row by parsing from each line start near the end of the
+/// file, so that objectives containing newlines don't confuse it.
+pub fn last(path: &Path) -> Result<Option<Last>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let chunk_start = len.saturating_sub(MAX_RECORD_LEN);
+    file.seek(SeekFrom::Start(chunk_start))?;
+    let mut chunk = Vec::new();
+    file.read_to_end(&mut chunk)?;
+    let content_end = chunk
+        .iter()
+        .rposition(|b| *b != b'\n' && *b != b'\r')
+        .map_or(0, |p| p + 1);
+    let candidates = chunk[..content_end]
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, b)| **b == b'\n')
+        .map(|(i, _)| i + 1);
+    for start in candidates {
+        let record = match parse_row(&chunk[start..content_end]) {
+            Some(record) if record.len() == HEADER.len() => record,
+            _ => continue,
+        };
+        let row = record.iter().map(String::from).collect::<Vec<_>>();
+        let entry = match Tracker::try_from(record) {
+            Ok(entry) => entry,
+            Err(_) => continue,
+        };
+        log::debug(|| format!("Last row starts at byte {}", chunk_start + start as u64));
+        return Ok(Some(Last {
+            offset: chunk_start + start as u64,
+            entry,
+            row,
+        }));
+    }
+    Ok(None)
+}
+
+/// Appends a single entry to the end of the storage file.
+pub fn append(path: &Path, entry: &Tracker) -> Result<()> {
+    backup::backup(path)?;
+    let row = entry.to_record();
+    let mut file = OpenOptions::new().read(true).append(true).open(path)?;
+    let len = file.metadata()?.len();
+    let mut bytes = Vec::new();
+    if len > 0 {
+        let mut last = [0u8; 1];
+        file.seek(SeekFrom::Start(len - 1))?;
+        file.read_exact(&mut last)?;
+        if last[0] != b'\n' {
+            bytes.push(b'\n');
+        }
+    }
+    bytes.extend(encode(&row)?);
+    file.write_all(&bytes)?;
+    file.sync_all()?;
+    if let Some(description) = journal::record_change(path, Vec::new(), vec![row])? {
+        git::commit(path, &description)?;
+    }
+    Ok(())
+}
+
+/// Replaces the last row of the storage file with the given entry.
+pub fn replace_last(path: &Path, last: Last, entry: &Tracker) -> Result<()> {
+    backup::backup(path)?;
+    let row = entry.to_record();
+    let bytes = encode(&row)?;
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    file.seek(SeekFrom::Start(last.offset))?;
+    file.write_all(&bytes)?;
+    file.set_len(last.offset + bytes.len() as u64)?;
+    file.sync_all()?;
+    if let Some(description) = journal::record_change(path, vec![last.row], vec![row])? {
+        git::commit(path, &description)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store;
+
+    #[test]
+    fn usable_on_a_freshly_written_file() {
+        let dir = std::env::temp_dir().join(format!("track-work-append-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("work.csv");
+        let entry = Tracker::new(time::OffsetDateTime::now_utc(), None, "review".into());
+        store(&path, &[entry]).unwrap();
+        let usable = usable(&path);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(usable);
+    }
+}
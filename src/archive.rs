@@ -0,0 +1,99 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use time::Date;
+
+use crate::{git, read, store, write_unlocked, Tracker};
+
+/// The archive for `year` of a storage file, e.g. `work-2022.csv` for `work.csv`.
+fn archive_path(path: &Path, year: i32) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{}-{}.{}", stem, year, ext.to_string_lossy()),
+        None => format!("{}-{}", stem, year),
+    };
+    path.with_file_name(name)
+}
+
+/// All yearly archives of the storage file, oldest first.
+pub fn archives(path: &Path) -> Result<Vec<PathBuf>> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let prefix = format!(
+        "{}-",
+        path.file_stem().unwrap_or_default().to_string_lossy()
+    );
+    let suffix = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    let mut archives = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|candidate| {
+            let year = candidate
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .strip_prefix(&prefix)
+                .and_then(|rest| rest.strip_suffix(&suffix))
+                .and_then(|year| year.parse::<i32>().ok());
+            match year {
+                Some(year) => archive_path(path, year).file_name() == candidate.file_name(),
+                None => false,
+            }
+        })
+        .collect::<Vec<_>>();
+    archives.sort();
+    Ok(archives)
+}
+
+/// The entries of all archives followed by those of the storage file.
+pub fn history(path: &PathBuf) -> Result<Vec<Tracker>> {
+    let mut entries = Vec::new();
+    for archive in archives(path)? {
+        entries.extend(read(&archive)?);
+    }
+    entries.extend(read(path)?);
+    Ok(entries)
+}
+
+/// Moves all finished entries that started before `cutoff` into per-year
+/// archive files next to the storage file.
+pub fn archive(path: &PathBuf, cutoff: Date) -> Result<()> {
+    let (old, current): (Vec<Tracker>, Vec<Tracker>) = read(path)?
+        .into_iter()
+        .partition(|entry| entry.start.date() < cutoff && entry.end.is_some());
+    if old.is_empty() {
+        println!("No entries before {} to archive", cutoff.format("%F"));
+        return Ok(());
+    }
+    let mut years: BTreeMap<i32, Vec<Tracker>> = BTreeMap::new();
+    for entry in old {
+        years.entry(entry.start.year()).or_default().push(entry);
+    }
+    for (year, entries) in years {
+        let target = archive_path(path, year);
+        if crate::dry_run() {
+            println!(
+                "Would archive {} entries to {}",
+                entries.len(),
+                target.display()
+            );
+            continue;
+        }
+        let mut archived = read(&target)?;
+        let count = entries.len();
+        archived.extend(entries);
+        archived.sort_by_key(|entry| entry.start);
+        store(&target, &archived)?;
+        git::stage(&target)?;
+        println!("Archived {} entries to {}", count, target.display());
+    }
+    // Locked entries are kept, only moved
+    write_unlocked(path, &current)
+}
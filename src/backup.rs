@@ -0,0 +1,113 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{Context, Error, Result};
+use time::OffsetDateTime;
+
+use crate::{git, lock, log, read, tmp_path};
+
+/// How many backups are kept per storage file, 0 disables backups.
+pub static RETENTION: AtomicUsize = AtomicUsize::new(10);
+
+fn backup_dir(path: &Path) -> PathBuf {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.join("backups"),
+        _ => PathBuf::from("backups"),
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// All backups of the storage file, oldest first.
+pub fn list(path: &Path) -> Result<Vec<PathBuf>> {
+    let dir = backup_dir(path);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let prefix = format!("{}.", file_name(path));
+    let mut backups = fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|p| {
+            let name = file_name(p);
+            name.starts_with(&prefix) && name.ends_with(".bak")
+        })
+        .collect::<Vec<_>>();
+    backups.sort();
+    Ok(backups)
+}
+
+/// Copies the current storage file into the backup directory and prunes
+/// backups beyond the retention count.
+pub fn backup(path: &Path) -> Result<()> {
+    let retention = RETENTION.load(Ordering::SeqCst);
+    if retention == 0 || !path.exists() {
+        return Ok(());
+    }
+    let dir = backup_dir(path);
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Could not create backup directory {}", dir.display()))?;
+    let now = OffsetDateTime::now_local();
+    let target = dir.join(format!(
+        "{}.{}-{:03}.bak",
+        file_name(path),
+        now.format("%Y%m%d-%H%M%S"),
+        now.millisecond()
+    ));
+    log::info(|| format!("Backing up to {}", target.display()));
+    fs::copy(path, &target)
+        .with_context(|| format!("Could not create backup {}", target.display()))?;
+    let backups = list(path)?;
+    if backups.len() > retention {
+        for old in &backups[..backups.len() - retention] {
+            fs::remove_file(old)?;
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the storage file with the given backup, either a path or the
+/// file name shown by `restore` without arguments.
+pub fn restore(path: &Path, name: Option<&str>) -> Result<()> {
+    let backups = list(path)?;
+    let name = match name {
+        Some(name) => name,
+        None => {
+            if backups.is_empty() {
+                println!("No backups found in {}", backup_dir(path).display());
+            }
+            for backup in &backups {
+                println!("{}", file_name(backup));
+            }
+            return Ok(());
+        }
+    };
+    let source = backups
+        .iter()
+        .find(|b| file_name(b) == name || b.as_os_str() == name)
+        .ok_or_else(|| Error::msg(format!("No such backup: {}", name)))?
+        .clone();
+    lock::check(path, &read(&path.to_path_buf())?, &read(&source)?)?;
+    if crate::dry_run() {
+        println!("Would restore {}", file_name(&source));
+        return Ok(());
+    }
+    crate::confirm(&format!(
+        "Replace {} with {}?",
+        path.display(),
+        file_name(&source)
+    ))?;
+    let tmp = tmp_path(path);
+    fs::copy(&source, &tmp)?;
+    backup(path)?;
+    fs::rename(&tmp, path)?;
+    git::commit(path, &format!("restore: {}", file_name(&source)))?;
+    println!("Restored {}", file_name(&source));
+    Ok(())
+}
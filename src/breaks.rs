@@ -0,0 +1,123 @@
+//! Checks the pauses between the sessions of each day against mandatory
+//! break rules, e.g. 30 minutes after 6 hours of work.
+
+use anyhow::{Context, Error, Result};
+use time::{Date, Duration, OffsetDateTime};
+
+use crate::config::Config;
+use crate::{format, human, Tracker};
+
+/// The German rules, used unless the `[breaks]` config section has its own.
+const DEFAULT_RULES: &str = "6h:30m, 9h:45m";
+const DEFAULT_MIN_BREAK: i64 = 15;
+
+#[derive(Debug)]
+pub struct Rules {
+    /// Work time after which the break time is required, ascending
+    rules: Vec<(Duration, Duration)>,
+    /// Shorter pauses don't count as breaks
+    min_break: Duration,
+}
+
+impl Rules {
+    /// Reads `rules = "6h:30m, 9h:45m"` and `min_break_minutes` from the
+    /// `[breaks]` config section.
+    pub fn load(config: &Config) -> Result<Rules> {
+        let mut rules = config
+            .get("breaks", "rules")
+            .unwrap_or(DEFAULT_RULES)
+            .split(',')
+            .map(|rule| {
+                let (after, length) = rule.trim().split_once(':').ok_or_else(|| {
+                    Error::msg(format!(
+                        "Invalid break rule {}, expected e.g. 6h:30m",
+                        rule.trim()
+                    ))
+                })?;
+                Ok((
+                    human::parse_duration(after)?,
+                    human::parse_duration(length)?,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()
+            .context("Invalid break rules")?;
+        rules.sort();
+        let min_break = match config.get("breaks", "min_break_minutes") {
+            Some(minutes) => minutes
+                .parse()
+                .with_context(|| format!("Invalid min_break_minutes: {}", minutes))?,
+            None => DEFAULT_MIN_BREAK,
+        };
+        Ok(Rules {
+            rules,
+            min_break: Duration::minutes(min_break),
+        })
+    }
+
+    /// The break time required after working `worked`.
+    fn required(&self, worked: Duration) -> Duration {
+        self.rules
+            .iter()
+            .rev()
+            .find(|(after, _)| worked > *after)
+            .map_or(Duration::zero(), |(_, length)| *length)
+    }
+}
+
+/// Prints the work and break time of each day and whether the breaks are
+/// long enough.
+pub fn report(mut entries: Vec<Tracker>, rules: &Rules) {
+    entries.sort_by_key(|entry| entry.start);
+    println!("Date, Worked, Breaks, Required, Status");
+    let mut violations = 0;
+    let mut day: Option<(Date, Duration, Duration)> = None;
+    let mut finish = |day: Option<(Date, Duration, Duration)>| {
+        if let Some((date, worked, breaks)) = day {
+            let required = rules.required(worked);
+            let status = if breaks >= required {
+                "ok".to_string()
+            } else {
+                violations += 1;
+                format!("missing {}", format::duration(required - breaks))
+            };
+            println!(
+                "{}, {}, {}, {}, {}",
+                date,
+                format::duration(worked),
+                format::duration(breaks),
+                format::duration(required),
+                status
+            );
+        }
+    };
+    // The latest end so far, an entry may be contained in an earlier one.
+    let mut latest: Option<OffsetDateTime> = None;
+    for entry in entries {
+        let end = entry.end.unwrap_or_else(OffsetDateTime::now_local);
+        let date = entry.start.date();
+        if day.is_some_and(|(current, _, _)| current != date) {
+            finish(day.take());
+            latest = None;
+        }
+        let (_, worked, breaks) = day.get_or_insert((date, Duration::zero(), Duration::zero()));
+        match latest {
+            // Overlapping sessions, e.g. of named timers, count once
+            Some(latest) if entry.start < latest => {
+                *worked += std::cmp::max(end, latest) - latest;
+            }
+            Some(latest) => {
+                let pause = entry.start - latest;
+                if pause >= rules.min_break {
+                    *breaks += pause;
+                }
+                *worked += end - entry.start;
+            }
+            None => *worked += end - entry.start,
+        }
+        latest = Some(latest.map_or(end, |latest| std::cmp::max(latest, end)));
+    }
+    finish(day);
+    if violations > 0 {
+        println!("{} day(s) without enough breaks", violations);
+    }
+}
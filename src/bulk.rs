@@ -0,0 +1,159 @@
+//! Changes all entries matching a filter at once, e.g. to add metadata to
+//! history after introducing projects or tags.
+
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+use anyhow::{Error, Result};
+
+use crate::filter::Filter;
+use crate::{read, write, Tracker};
+
+/// A change given via `--set`.
+#[derive(Debug)]
+pub enum Set {
+    /// `key=value`, an empty value removes a metadata key
+    Assign(String, String),
+    /// `key+=value` adds to a comma-separated list
+    Add(String, String),
+    /// `key-=value` removes from a comma-separated list
+    Remove(String, String),
+}
+
+impl std::str::FromStr for Set {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (key, value) = s
+            .split_once('=')
+            .ok_or_else(|| Error::msg(format!("Invalid change, expected key=value: {}", s)))?;
+        let value = value.trim().to_string();
+        let set = match key.trim() {
+            key if key.ends_with('+') => Set::Add(key.trim_end_matches('+').trim().into(), value),
+            key if key.ends_with('-') => {
+                Set::Remove(key.trim_end_matches('-').trim().into(), value)
+            }
+            key => Set::Assign(key.into(), value),
+        };
+        match &set {
+            Set::Assign(key, _) | Set::Add(key, _) | Set::Remove(key, _) if key.is_empty() => {
+                Err(Error::msg(format!("Invalid change, missing key: {}", s)))
+            }
+            Set::Add(key, _) | Set::Remove(key, _) if key == "objective" || key == "timer" => {
+                Err(Error::msg(format!("{} can only be set with {}=", key, key)))
+            }
+            _ => Ok(set),
+        }
+    }
+}
+
+fn list(value: &str) -> Vec<&str> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+impl Set {
+    fn apply(&self, entry: &mut Tracker) {
+        match self {
+            Set::Assign(key, value) if key == "objective" => entry.objective = value.clone(),
+            Set::Assign(key, value) if key == "timer" => entry.timer = value.clone(),
+            Set::Assign(key, value) if value.is_empty() => {
+                entry.meta.remove(key);
+            }
+            Set::Assign(key, value) => {
+                entry.meta.insert(key.clone(), value.clone());
+            }
+            Set::Add(key, value) => {
+                let current = entry.meta.get(key).map_or("", |v| v.as_str());
+                let mut items = list(current);
+                if !items.contains(&value.as_str()) {
+                    items.push(value);
+                }
+                let items = items.join(",");
+                entry.meta.insert(key.clone(), items);
+            }
+            Set::Remove(key, value) => {
+                let current = entry.meta.get(key).map_or("", |v| v.as_str());
+                let items = list(current)
+                    .into_iter()
+                    .filter(|item| item != value)
+                    .collect::<Vec<_>>()
+                    .join(",");
+                if items.is_empty() {
+                    entry.meta.remove(key);
+                } else {
+                    entry.meta.insert(key.clone(), items);
+                }
+            }
+        }
+    }
+}
+
+/// What changed between two versions of an entry, e.g. `project: -> acme`.
+fn changes(before: &Tracker, after: &Tracker) -> Vec<String> {
+    let mut changes = Vec::new();
+    if before.objective != after.objective {
+        changes.push(format!(
+            "objective: {} -> {}",
+            before.objective, after.objective
+        ));
+    }
+    if before.timer != after.timer {
+        changes.push(format!("timer: {} -> {}", before.timer, after.timer));
+    }
+    let keys = before
+        .meta
+        .keys()
+        .chain(after.meta.keys())
+        .collect::<BTreeSet<_>>();
+    for key in keys {
+        let (old, new) = (before.meta.get(key), after.meta.get(key));
+        if old != new {
+            changes.push(format!(
+                "{}: {} -> {}",
+                key,
+                old.map_or("", |v| v.as_str()),
+                new.map_or("", |v| v.as_str())
+            ));
+        }
+    }
+    changes
+}
+
+/// Applies the changes to every entry matching `filter`, printing each
+/// change.
+pub fn bulk_edit(path: &PathBuf, filter: &Filter, sets: &[Set]) -> Result<()> {
+    if sets.is_empty() {
+        return Err(Error::msg("Nothing to change, pass --set key=value"));
+    }
+    let mut data = read(path)?;
+    let mut changed = 0;
+    for entry in data.iter_mut().filter(|entry| filter.matches(entry)) {
+        let before = entry.clone();
+        for set in sets {
+            set.apply(entry);
+        }
+        let changes = changes(&before, entry);
+        if changes.is_empty() {
+            continue;
+        }
+        println!("{}", before);
+        for change in changes {
+            println!("    {}", change);
+        }
+        changed += 1;
+    }
+    if changed > 0 {
+        crate::confirm(&format!("Change {} entries?", changed))?;
+        write(path, &data)?;
+    }
+    if crate::dry_run() {
+        println!("Would change {} entries", changed);
+    } else {
+        println!("Changed {} entries", changed);
+    }
+    Ok(())
+}
@@ -0,0 +1,148 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use crate::config::Config;
+use crate::http::Request;
+use crate::{check_overlaps, ics, log, read, write, Tracker};
+
+const CALENDAR_QUERY: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop><c:calendar-data/></d:prop>
+  <c:filter>
+    <c:comp-filter name="VCALENDAR"><c:comp-filter name="VEVENT"/></c:comp-filter>
+  </c:filter>
+</c:calendar-query>"#;
+
+struct Calendar<'a> {
+    url: String,
+    user: Option<&'a str>,
+    password: &'a str,
+}
+
+impl<'a> Calendar<'a> {
+    fn from_config(config: &'a Config) -> Result<Self> {
+        let url = config.require("caldav", "url")?;
+        let url = if url.ends_with('/') {
+            url.to_string()
+        } else {
+            format!("{}/", url)
+        };
+        Ok(Calendar {
+            url,
+            user: config.get("caldav", "user"),
+            password: config.get("caldav", "password").unwrap_or(""),
+        })
+    }
+
+    fn request(&self, method: &'a str, url: String) -> Request<'a> {
+        let request = Request::new(method, url);
+        match self.user {
+            Some(user) => request.basic_auth(user, self.password),
+            None => request,
+        }
+    }
+
+    fn events(&self) -> Result<Vec<Tracker>> {
+        let response = self
+            .request("REPORT", self.url.clone())
+            .header("Depth", "1")
+            .header("Content-Type", "application/xml; charset=utf-8")
+            .body(CALENDAR_QUERY)
+            .send()?
+            .error_for_status()?;
+        Ok(ics::parse_events(&xml_unescape(&response.text())))
+    }
+
+    fn put(&self, entry: &Tracker) -> Result<()> {
+        let url = format!("{}{}.ics", self.url, ics::uid(entry));
+        self.request("PUT", url)
+            .header("Content-Type", "text/calendar; charset=utf-8")
+            .body(ics::calendar(std::iter::once(entry), true))
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+fn xml_unescape(text: &str) -> String {
+    text.replace("<![CDATA[", "")
+        .replace("]]>", "")
+        .replace("&#13;", "\r")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Synchronizes the storage file with a CalDAV calendar, matching entries
+/// and events by their UID. Events missing locally are added as entries
+/// unless they overlap, finished sessions missing or differing remotely are
+/// uploaded as VEVENTs.
+pub fn sync(path: &PathBuf, config: &Config, push: bool, pull: bool) -> Result<()> {
+    let calendar = Calendar::from_config(config)?;
+    let mut data = read(path)?;
+    let remote = calendar
+        .events()?
+        .into_iter()
+        .map(|event| (ics::uid(&event), event))
+        .collect::<HashMap<_, _>>();
+    log::info(|| format!("Found {} remote events", remote.len()));
+    let mut changed = false;
+
+    if push {
+        let mut pushed = 0;
+        for entry in data.iter_mut().filter(|entry| entry.end.is_some()) {
+            // Later changes of the start update the same event
+            if !entry.meta.contains_key("uid") {
+                entry.meta.insert("uid".to_string(), ics::uid(entry));
+                changed = true;
+            }
+            let unchanged = remote.get(&ics::uid(entry)).is_some_and(|event| {
+                event.start.timestamp() == entry.start.timestamp()
+                    && event.end.map(|e| e.timestamp()) == entry.end.map(|e| e.timestamp())
+                    && event.objective == entry.objective
+            });
+            if !unchanged {
+                if !crate::dry_run() {
+                    calendar.put(entry)?;
+                }
+                pushed += 1;
+            }
+        }
+        if crate::dry_run() {
+            println!("Would upload {} sessions", pushed);
+        } else {
+            println!("Uploaded {} sessions", pushed);
+        }
+    }
+
+    if pull {
+        let mut pulled = 0;
+        let known = data.iter().map(ics::uid).collect::<HashSet<_>>();
+        let mut events = remote
+            .into_iter()
+            .filter(|(uid, _)| !known.contains(uid))
+            .map(|(_, event)| event)
+            .collect::<Vec<_>>();
+        events.sort_by_key(|event| event.start);
+        for event in events {
+            if let Err(e) = check_overlaps(&data, &event, crate::force()) {
+                println!("Skipped {}: {}", event, e);
+                continue;
+            }
+            data.push(event);
+            pulled += 1;
+        }
+        changed |= pulled > 0;
+        println!("Added {} sessions from calendar", pulled);
+    }
+
+    if changed {
+        data.sort_by_key(|entry| entry.start);
+        write(path, &data)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,84 @@
+//! A tamper-evident hash chain over the entries, enabled by `hash_chain` of
+//! the `[audit]` config section: each entry keeps the SHA-256 of its row
+//! and the hash of the entry before it as the metadata `hash`.
+//!
+//! Every write by track-work seals the entries again, `verify` finds rows
+//! changed, added or removed by anything else.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{Error, Result};
+
+use crate::{read, schema, sha256, Tracker};
+
+/// Whether every write seals the entries.
+pub static ENABLED: AtomicBool = AtomicBool::new(false);
+
+const KEY: &str = "hash";
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// The hash of `entry`, without its own hash, following `previous`. The
+/// times are hashed in the format of schema 5, so chains sealed before
+/// schema 6 still verify.
+fn hash(previous: &str, entry: &Tracker) -> String {
+    let mut entry = entry.clone();
+    entry.meta.remove(KEY);
+    let mut record = entry.to_record();
+    record[0] = entry.start.format(schema::LEGACY_TIME_FORMAT);
+    record[1] = entry
+        .end
+        .map(|end| end.format(schema::LEGACY_TIME_FORMAT))
+        .unwrap_or_default();
+    let mut content = previous.to_string();
+    for field in record {
+        content.push('\x1f');
+        content += &field;
+    }
+    sha256::hex(content.as_bytes())
+}
+
+/// `data` with the hash chain, in the order given.
+pub fn seal(data: &[Tracker]) -> Vec<Tracker> {
+    let mut previous = String::new();
+    data.iter()
+        .map(|entry| {
+            let mut entry = entry.clone();
+            previous = hash(&previous, &entry);
+            entry.meta.insert(KEY.to_string(), previous.clone());
+            entry
+        })
+        .collect()
+}
+
+/// Checks the hash chain of the storage file, failing at the first entry
+/// that doesn't match.
+pub fn verify(path: &PathBuf) -> Result<()> {
+    let data = read(path)?;
+    let mut previous = String::new();
+    for (index, entry) in data.iter().enumerate() {
+        let expected = hash(&previous, entry);
+        match entry.meta.get(KEY) {
+            Some(stored) if *stored == expected => previous = expected,
+            Some(_) => {
+                return Err(Error::msg(format!(
+                    "Entry {} was changed, or one before it added or removed, outside track-work: {}",
+                    index + 1,
+                    entry
+                )))
+            }
+            None => {
+                return Err(Error::msg(format!(
+                    "Entry {} has no hash, it was added outside track-work or before hash_chain was enabled: {}",
+                    index + 1,
+                    entry
+                )))
+            }
+        }
+    }
+    println!("Verified {} entries", data.len());
+    Ok(())
+}
@@ -0,0 +1,68 @@
+//! Unicode charts of the tracked time for the terminal.
+
+use console::Term;
+use time::{Date, Duration};
+
+use crate::format;
+
+/// Eighths of a block, for the end of a bar.
+const PARTIAL: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+const FULL: char = '█';
+const TARGET: char = '│';
+
+/// A bar of `value` out of `max` that is `width` cells wide at most, with
+/// the target marked.
+pub fn bar(value: Duration, max: Duration, width: usize, target: Option<usize>) -> String {
+    let eighths = (value.whole_seconds() * 8 * width as i64 / max.whole_seconds().max(1)) as usize;
+    let mut cells = vec![FULL; eighths / 8];
+    if !eighths.is_multiple_of(8) {
+        cells.push(PARTIAL[eighths % 8]);
+    }
+    if let Some(target) = target {
+        if cells.len() <= target {
+            cells.resize(target + 1, ' ');
+        }
+        cells[target] = TARGET;
+    }
+    cells.into_iter().collect::<String>().trim_end().to_string()
+}
+
+/// Prints a horizontal bar per day scaled to the terminal width, the daily
+/// target is marked in every row.
+pub fn daily(days: &[(Date, Duration)], target: Option<Duration>) {
+    // `2022-03-01 08:30 ` before the bars
+    const LABEL: usize = 17;
+    let columns = Term::stdout()
+        .size_checked()
+        .map_or(80, |(_, columns)| columns as usize);
+    let width = columns.saturating_sub(LABEL + 1).max(10);
+    let max = days
+        .iter()
+        .map(|(_, duration)| *duration)
+        .chain(target)
+        .max()
+        .unwrap_or_else(Duration::zero);
+    let marker = target.map(|target| {
+        ((target.whole_seconds() * width as i64 / max.whole_seconds().max(1)) as usize)
+            .min(width - 1)
+    });
+    let mut total = Duration::zero();
+    for (date, duration) in days {
+        println!(
+            "{} {} {}",
+            date.format("%F"),
+            format::duration(*duration),
+            bar(*duration, max, width, marker)
+        );
+        total += *duration;
+    }
+    match target {
+        Some(target) => println!(
+            "Total: {}, {} target {}",
+            format::duration(total),
+            TARGET,
+            format::duration(target)
+        ),
+        None => println!("Total: {}", format::duration(total)),
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Error, Result};
+
+/// A minimal TOML-like configuration: `[section]` headers followed by
+/// `key = value` lines. Values may be quoted strings, numbers or booleans,
+/// they are all kept as strings and converted on access.
+#[derive(Debug, Default)]
+pub struct Config {
+    sections: HashMap<String, HashMap<String, String>>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Could not read config file: {}", path.display()))?;
+        Config::parse(&content)
+            .with_context(|| format!("Could not parse config file: {}", path.display()))
+    }
+
+    fn parse(content: &str) -> Result<Self> {
+        let mut sections: HashMap<String, HashMap<String, String>> = HashMap::new();
+        let mut current = String::new();
+        for (number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                current = line[1..line.len() - 1].trim().to_string();
+                sections.entry(current.clone()).or_default();
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| Error::msg(format!("Invalid line {}: {}", number + 1, line)))?;
+            let key = unquote(key.trim());
+            sections
+                .entry(current.clone())
+                .or_default()
+                .insert(key, unquote(value.trim()));
+        }
+        Ok(Config { sections })
+    }
+
+    /// Selects the profile `name`: the keys of `[profile.NAME.SECTION]`
+    /// replace the ones of `[SECTION]`, `[profile.NAME]` holds its `file`.
+    pub fn with_profile(mut self, name: &str) -> Result<Self> {
+        let prefix = format!("profile.{}", name);
+        if !self.sections.contains_key(&prefix) {
+            return Err(Error::msg(format!(
+                "No profile {}, expected a [{}] config section",
+                name, prefix
+            )));
+        }
+        let overrides = self
+            .sections
+            .iter()
+            .filter_map(|(section, keys)| {
+                let section = section.strip_prefix(&prefix)?.strip_prefix('.')?;
+                Some((section.to_string(), keys.clone()))
+            })
+            .collect::<Vec<_>>();
+        for (section, keys) in overrides {
+            self.sections.entry(section).or_default().extend(keys);
+        }
+        Ok(self)
+    }
+
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.sections
+            .get(section)
+            .and_then(|s| s.get(key))
+            .map(|v| v.as_str())
+    }
+
+    /// The keys and values of a section, sorted by key.
+    pub fn section(&self, section: &str) -> Vec<(&str, &str)> {
+        let mut entries = self
+            .sections
+            .get(section)
+            .map(|s| {
+                s.iter()
+                    .map(|(k, v)| (k.as_str(), v.as_str()))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        entries.sort();
+        entries
+    }
+
+    pub fn require(&self, section: &str, key: &str) -> Result<&str> {
+        self.get(section, key).ok_or_else(|| {
+            Error::msg(format!(
+                "Missing config key `{}` in section [{}]",
+                key, section
+            ))
+        })
+    }
+}
+
+fn unquote(value: &str) -> String {
+    let value = match value.find(" #") {
+        Some(idx) if !value.starts_with('"') => value[..idx].trim_end(),
+        _ => value,
+    };
+    if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        value[1..value.len() - 1]
+            .replace("\\\"", "\"")
+            .replace("\\\\", "\\")
+    } else {
+        value.to_string()
+    }
+}
+
+/// `%APPDATA%` on Windows, used for the config and the data unless the XDG
+/// variables are set.
+pub fn app_data() -> Option<PathBuf> {
+    if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        None
+    }
+}
+
+/// The default config location, `$XDG_CONFIG_HOME/track-work/config.toml`
+/// falling back to `%APPDATA%\track-work\config.toml` on Windows and
+/// `~/.config/track-work/config.toml` elsewhere.
+pub fn default_path() -> PathBuf {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(app_data)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_else(|| PathBuf::from("."));
+    base.join("track-work").join("config.toml")
+}
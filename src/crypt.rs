@@ -0,0 +1,100 @@
+//! Encryption of the storage file at rest via the `age` tool. Files are
+//! encrypted to a recipient key, or with a passphrase `age` asks for on the
+//! terminal if no recipient is configured.
+
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+use anyhow::{Context, Error, Result};
+
+use crate::config::Config;
+
+const AGE_HEADER: &[u8] = b"age-encryption.org/";
+
+#[derive(Debug)]
+struct Settings {
+    /// Encrypt to this public key instead of a passphrase
+    recipient: Option<String>,
+    /// The private key for decryption, required with a recipient
+    identity: Option<PathBuf>,
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// Enables encryption of all written storage files.
+pub fn enable(config: &Config) {
+    let _ = SETTINGS.set(Settings {
+        recipient: config.get("encryption", "recipient").map(String::from),
+        identity: config.get("encryption", "identity").map(PathBuf::from),
+    });
+}
+
+pub fn enabled() -> bool {
+    SETTINGS.get().is_some()
+}
+
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(AGE_HEADER) || bytes.starts_with(b"-----BEGIN AGE ENCRYPTED FILE-----")
+}
+
+fn age(args: &[&str], input: &[u8]) -> Result<Vec<u8>> {
+    let mut child = Command::new("age")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .context("Could not run age, is it installed?")?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = input.to_vec();
+    // Write from a separate thread, age may start writing before it read
+    // all of its input.
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let mut output = Vec::new();
+    child
+        .stdout
+        .take()
+        .expect("stdout is piped")
+        .read_to_end(&mut output)?;
+    writer
+        .join()
+        .map_err(|_| Error::msg("Writing to age failed"))??;
+    if !child.wait()?.success() {
+        return Err(Error::msg(format!("age {} failed", args.join(" "))));
+    }
+    Ok(output)
+}
+
+/// Decrypts the content of a storage file read from `path`.
+pub fn decrypt(path: &Path, bytes: &[u8]) -> Result<Vec<u8>> {
+    // Otherwise the next write would silently store the entries unencrypted.
+    if !enabled() {
+        return Err(Error::msg(format!(
+            "{} is encrypted, pass --encrypt or enable it in the [encryption] config section",
+            path.display()
+        )));
+    }
+    let identity = SETTINGS
+        .get()
+        .and_then(|s| s.identity.as_ref())
+        .map(|p| p.to_string_lossy().into_owned());
+    let result = match &identity {
+        Some(identity) => age(&["--decrypt", "--identity", identity], bytes),
+        None => age(&["--decrypt"], bytes),
+    };
+    result.with_context(|| format!("Could not decrypt {}", path.display()))
+}
+
+/// Encrypts the content of a storage file if encryption is enabled.
+pub fn encrypt(bytes: Vec<u8>) -> Result<Vec<u8>> {
+    match SETTINGS.get() {
+        None => Ok(bytes),
+        Some(Settings {
+            recipient: Some(recipient),
+            ..
+        }) => age(&["--encrypt", "--recipient", recipient], &bytes),
+        Some(_) => age(&["--encrypt", "--passphrase"], &bytes),
+    }
+}
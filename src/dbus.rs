@@ -0,0 +1,852 @@
+//! `dbus`, a service on the session bus so desktop applets can start and
+//! stop sessions without running the CLI: `org.trackwork.Tracker` at
+//! `/org/trackwork/Tracker` with the methods `Start(s)`, `Stop()` and
+//! `Status() -> (bsx)`, the properties `Tracking`, `Objective` and `Since`
+//! and their `PropertiesChanged` signal.
+//!
+//! Only the part of the wire protocol the service needs is spoken, directly
+//! on the bus socket.
+
+use std::path::PathBuf;
+
+use anyhow::{Error, Result};
+
+#[cfg(unix)]
+pub use unix::service;
+
+#[cfg(not(unix))]
+pub fn service(_path: &PathBuf, _timer: &str) -> Result<()> {
+    Err(Error::msg("The DBus service is only available on Linux"))
+}
+
+#[cfg(unix)]
+const NAME: &str = "org.trackwork.Tracker";
+#[cfg(unix)]
+const PATH: &str = "/org/trackwork/Tracker";
+#[cfg(unix)]
+const INTERFACE: &str = "org.trackwork.Tracker";
+#[cfg(unix)]
+const PROPERTIES: &str = "org.freedesktop.DBus.Properties";
+
+#[cfg(unix)]
+const INTROSPECTION: &str = r#"<!DOCTYPE node PUBLIC "-//freedesktop//DTD D-BUS Object Introspection 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd">
+<node>
+  <interface name="org.trackwork.Tracker">
+    <method name="Start"><arg name="objective" type="s" direction="in"/></method>
+    <method name="Stop"/>
+    <method name="Status">
+      <arg name="tracking" type="b" direction="out"/>
+      <arg name="objective" type="s" direction="out"/>
+      <arg name="since" type="x" direction="out"/>
+    </method>
+    <property name="Tracking" type="b" access="read"/>
+    <property name="Objective" type="s" access="read"/>
+    <property name="Since" type="x" access="read"/>
+  </interface>
+  <interface name="org.freedesktop.DBus.Properties">
+    <method name="Get">
+      <arg name="interface" type="s" direction="in"/>
+      <arg name="property" type="s" direction="in"/>
+      <arg name="value" type="v" direction="out"/>
+    </method>
+    <method name="GetAll">
+      <arg name="interface" type="s" direction="in"/>
+      <arg name="properties" type="a{sv}" direction="out"/>
+    </method>
+    <signal name="PropertiesChanged">
+      <arg name="interface" type="s"/>
+      <arg name="changed" type="a{sv}"/>
+      <arg name="invalidated" type="as"/>
+    </signal>
+  </interface>
+  <interface name="org.freedesktop.DBus.Introspectable">
+    <method name="Introspect"><arg name="xml" type="s" direction="out"/></method>
+  </interface>
+</node>
+"#;
+
+/// The tracking state the properties show.
+#[cfg(unix)]
+#[derive(Debug, Clone, PartialEq)]
+struct State {
+    tracking: bool,
+    objective: String,
+    /// Unix time of the start of the running session, 0 if none
+    since: i64,
+}
+
+#[cfg(unix)]
+impl State {
+    fn load(path: &PathBuf, timer: &str) -> Result<State> {
+        let data = crate::read(path)?;
+        Ok(
+            match crate::last_of(&data, timer).map(|index| &data[index]) {
+                Some(entry) if entry.end.is_none() => State {
+                    tracking: true,
+                    objective: entry.objective.clone(),
+                    since: entry.start.timestamp(),
+                },
+                _ => State {
+                    tracking: false,
+                    objective: String::new(),
+                    since: 0,
+                },
+            },
+        )
+    }
+}
+
+/// Marshalled values, in little endian. Offsets are relative to the start
+/// of the buffer, which always starts 8-aligned in a message.
+#[cfg(unix)]
+#[derive(Default)]
+struct Writer {
+    buf: Vec<u8>,
+}
+
+#[cfg(unix)]
+impl Writer {
+    fn align(&mut self, n: usize) {
+        while !self.buf.len().is_multiple_of(n) {
+            self.buf.push(0);
+        }
+    }
+
+    fn byte(&mut self, value: u8) {
+        self.buf.push(value);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.align(4);
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.u32(value as u32);
+    }
+
+    fn i64(&mut self, value: i64) {
+        self.align(8);
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Strings and object paths
+    fn string(&mut self, value: &str) {
+        self.u32(value.len() as u32);
+        self.buf.extend_from_slice(value.as_bytes());
+        self.buf.push(0);
+    }
+
+    fn signature(&mut self, value: &str) {
+        self.buf.push(value.len() as u8);
+        self.buf.extend_from_slice(value.as_bytes());
+        self.buf.push(0);
+    }
+
+    /// An array whose elements are aligned to `align`, written by `elements`.
+    fn array(&mut self, align: usize, elements: impl FnOnce(&mut Writer)) {
+        self.u32(0);
+        let length_at = self.buf.len() - 4;
+        self.align(align);
+        let start = self.buf.len();
+        elements(self);
+        let length = (self.buf.len() - start) as u32;
+        self.buf[length_at..length_at + 4].copy_from_slice(&length.to_le_bytes());
+    }
+
+    /// A property value as variant.
+    fn property(&mut self, state: &State, name: &str) {
+        match name {
+            "Tracking" => {
+                self.signature("b");
+                self.bool(state.tracking);
+            }
+            "Objective" => {
+                self.signature("s");
+                self.string(&state.objective);
+            }
+            _ => {
+                self.signature("x");
+                self.i64(state.since);
+            }
+        }
+    }
+
+    /// All properties as `a{sv}`.
+    fn properties(&mut self, state: &State) {
+        self.array(8, |w| {
+            for name in ["Tracking", "Objective", "Since"] {
+                w.align(8);
+                w.string(name);
+                w.property(state, name);
+            }
+        });
+    }
+}
+
+/// Reads marshalled values in the endianness of the message.
+#[cfg(unix)]
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    little: bool,
+}
+
+#[cfg(unix)]
+impl<'a> Reader<'a> {
+    fn align(&mut self, n: usize) {
+        self.pos = self.pos.div_ceil(n) * n;
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .buf
+            .get(self.pos..self.pos + n)
+            .ok_or_else(|| Error::msg("Truncated DBus message"))?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        self.align(4);
+        let bytes = self.bytes(4)?;
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        Ok(if self.little {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let length = self.u32()? as usize;
+        let bytes = self.bytes(length + 1)?;
+        Ok(String::from_utf8_lossy(&bytes[..length]).into_owned())
+    }
+
+    fn signature(&mut self) -> Result<String> {
+        let length = self.byte()? as usize;
+        let bytes = self.bytes(length + 1)?;
+        Ok(String::from_utf8_lossy(&bytes[..length]).into_owned())
+    }
+}
+
+/// A received message, with the header fields the service looks at.
+#[cfg(unix)]
+#[derive(Debug, Default)]
+struct Message {
+    kind: u8,
+    serial: u32,
+    path: String,
+    interface: String,
+    member: String,
+    reply_serial: Option<u32>,
+    sender: String,
+    signature: String,
+    body: Vec<u8>,
+    little: bool,
+}
+
+#[cfg(unix)]
+const METHOD_CALL: u8 = 1;
+#[cfg(unix)]
+const METHOD_RETURN: u8 = 2;
+#[cfg(unix)]
+const ERROR: u8 = 3;
+#[cfg(unix)]
+const SIGNAL: u8 = 4;
+
+#[cfg(unix)]
+impl Message {
+    fn parse(buf: &[u8]) -> Result<Message> {
+        let mut reader = Reader {
+            buf,
+            pos: 0,
+            little: buf.first() == Some(&b'l'),
+        };
+        reader.bytes(1)?;
+        let kind = reader.byte()?;
+        reader.bytes(2)?;
+        let body_length = reader.u32()? as usize;
+        let serial = reader.u32()?;
+        let mut message = Message {
+            kind,
+            serial,
+            little: reader.little,
+            ..Message::default()
+        };
+        let fields_end = reader.u32()? as usize + 16;
+        while reader.pos < fields_end {
+            reader.align(8);
+            let code = reader.byte()?;
+            let value = match reader.signature()?.as_str() {
+                "u" => reader.u32()?.to_string(),
+                "g" => reader.signature()?,
+                _ => reader.string()?,
+            };
+            match code {
+                1 => message.path = value,
+                2 => message.interface = value,
+                3 => message.member = value,
+                5 => message.reply_serial = value.parse().ok(),
+                7 => message.sender = value,
+                8 => message.signature = value,
+                _ => {}
+            }
+        }
+        reader.align(8);
+        message.body = reader.bytes(body_length)?.to_vec();
+        Ok(message)
+    }
+
+    /// The string arguments of the body, enough for the methods served.
+    fn strings(&self) -> Result<Vec<String>> {
+        let mut reader = Reader {
+            buf: &self.body,
+            pos: 0,
+            little: self.little,
+        };
+        self.signature
+            .chars()
+            .map(|c| match c {
+                's' | 'o' => reader.string(),
+                _ => Err(Error::msg(format!(
+                    "Unexpected DBus signature {}",
+                    self.signature
+                ))),
+            })
+            .collect()
+    }
+}
+
+/// Header fields of an outgoing message, by code with their signature.
+#[cfg(unix)]
+enum Field<'a> {
+    Path(&'a str),
+    Interface(&'a str),
+    Member(&'a str),
+    ErrorName(&'a str),
+    ReplySerial(u32),
+    Destination(&'a str),
+    Signature(&'a str),
+}
+
+#[cfg(unix)]
+fn encode(kind: u8, serial: u32, fields: &[Field], body: &[u8]) -> Vec<u8> {
+    let mut w = Writer::default();
+    w.byte(b'l');
+    w.byte(kind);
+    w.byte(0);
+    w.byte(1);
+    w.u32(body.len() as u32);
+    w.u32(serial);
+    w.array(8, |w| {
+        for field in fields {
+            w.align(8);
+            match field {
+                Field::Path(path) => {
+                    w.byte(1);
+                    w.signature("o");
+                    w.string(path);
+                }
+                Field::Interface(name) | Field::Member(name) | Field::ErrorName(name) => {
+                    w.byte(match field {
+                        Field::Interface(_) => 2,
+                        Field::Member(_) => 3,
+                        _ => 4,
+                    });
+                    w.signature("s");
+                    w.string(name);
+                }
+                Field::ReplySerial(serial) => {
+                    w.byte(5);
+                    w.signature("u");
+                    w.u32(*serial);
+                }
+                Field::Destination(name) => {
+                    w.byte(6);
+                    w.signature("s");
+                    w.string(name);
+                }
+                Field::Signature(signature) => {
+                    w.byte(8);
+                    w.signature("g");
+                    w.signature(signature);
+                }
+            }
+        }
+    });
+    w.align(8);
+    w.buf.extend_from_slice(body);
+    w.buf
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::os::unix::net::UnixStream;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use anyhow::{Context, Error, Result};
+    use crossbeam_channel::{select, unbounded, Receiver};
+
+    use super::*;
+    use crate::{log, mqtt, Meta};
+
+    /// How often changes made by other commands are looked for.
+    const POLL: Duration = Duration::from_secs(5);
+
+    /// Replaces the `%XX` escapes of a bus address value.
+    fn unescape(value: &str) -> String {
+        let mut bytes = Vec::new();
+        let mut rest = value.as_bytes();
+        while let Some((&byte, tail)) = rest.split_first() {
+            match (byte, tail.get(..2)) {
+                (b'%', Some(hex)) => match u8::from_str_radix(&String::from_utf8_lossy(hex), 16) {
+                    Ok(decoded) => {
+                        bytes.push(decoded);
+                        rest = &tail[2..];
+                        continue;
+                    }
+                    Err(_) => bytes.push(byte),
+                },
+                _ => bytes.push(byte),
+            }
+            rest = tail;
+        }
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    /// Connects to the first usable `unix:` address of
+    /// `DBUS_SESSION_BUS_ADDRESS`, or `$XDG_RUNTIME_DIR/bus`.
+    fn connect() -> Result<UnixStream> {
+        let addresses = std::env::var("DBUS_SESSION_BUS_ADDRESS").unwrap_or_else(|_| {
+            format!(
+                "unix:path={}/bus",
+                std::env::var("XDG_RUNTIME_DIR").unwrap_or_default()
+            )
+        });
+        for address in addresses.split(';') {
+            let keys = match address.strip_prefix("unix:") {
+                Some(keys) => keys,
+                None => continue,
+            };
+            for (key, value) in keys.split(',').filter_map(|pair| pair.split_once('=')) {
+                let stream = match key {
+                    "path" => UnixStream::connect(unescape(value)),
+                    #[cfg(target_os = "linux")]
+                    "abstract" => {
+                        use std::os::linux::net::SocketAddrExt;
+                        std::os::unix::net::SocketAddr::from_abstract_name(unescape(value))
+                            .and_then(|addr| UnixStream::connect_addr(&addr))
+                    }
+                    _ => continue,
+                };
+                match stream {
+                    Ok(stream) => return Ok(stream),
+                    Err(e) => log::info(|| format!("Could not connect to {}: {}", address, e)),
+                }
+            }
+        }
+        Err(Error::msg(format!(
+            "Could not connect to the session bus at {}",
+            addresses
+        )))
+    }
+
+    /// Authenticates as the current user.
+    fn authenticate(stream: &mut UnixStream) -> Result<()> {
+        // SAFETY: getuid has no preconditions and can't fail
+        let uid = unsafe { libc::getuid() }.to_string();
+        let hex = uid
+            .bytes()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        stream.write_all(format!("\0AUTH EXTERNAL {}\r\n", hex).as_bytes())?;
+        let mut reply = String::new();
+        BufReader::new(&mut *stream).read_line(&mut reply)?;
+        if !reply.starts_with("OK") {
+            return Err(Error::msg(format!(
+                "The session bus refused the authentication: {}",
+                reply.trim()
+            )));
+        }
+        stream.write_all(b"BEGIN\r\n")?;
+        Ok(())
+    }
+
+    fn receive(stream: &mut UnixStream) -> Result<Message> {
+        let mut buf = vec![0; 16];
+        stream.read_exact(&mut buf)?;
+        let little = buf[0] == b'l';
+        let number = |bytes: &[u8]| {
+            let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+            if little {
+                u32::from_le_bytes(bytes)
+            } else {
+                u32::from_be_bytes(bytes)
+            }
+        };
+        let body = number(&buf[4..8]) as usize;
+        let fields = number(&buf[12..16]) as usize;
+        let total = (16 + fields).div_ceil(8) * 8 + body;
+        buf.resize(total, 0);
+        stream.read_exact(&mut buf[16..])?;
+        Message::parse(&buf)
+    }
+
+    /// Reads messages on a thread, so the service can also poll the file.
+    fn messages(mut stream: UnixStream) -> Receiver<Result<Message>> {
+        let (sender, receiver) = unbounded();
+        std::thread::spawn(move || loop {
+            let message = receive(&mut stream);
+            let failed = message.is_err();
+            if sender.send(message).is_err() || failed {
+                break;
+            }
+        });
+        receiver
+    }
+
+    struct Connection {
+        stream: UnixStream,
+        serial: u32,
+    }
+
+    impl Connection {
+        fn send(&mut self, kind: u8, fields: &[Field], body: &[u8]) -> Result<u32> {
+            self.serial += 1;
+            self.stream
+                .write_all(&encode(kind, self.serial, fields, body))?;
+            Ok(self.serial)
+        }
+
+        fn call_bus(&mut self, member: &str, signature: &str, body: &[u8]) -> Result<u32> {
+            let mut fields = vec![
+                Field::Path("/org/freedesktop/DBus"),
+                Field::Interface("org.freedesktop.DBus"),
+                Field::Member(member),
+                Field::Destination("org.freedesktop.DBus"),
+            ];
+            if !signature.is_empty() {
+                fields.push(Field::Signature(signature));
+            }
+            self.send(METHOD_CALL, &fields, body)
+        }
+
+        fn reply(&mut self, call: &Message, signature: &str, body: &[u8]) -> Result<()> {
+            let mut fields = vec![
+                Field::ReplySerial(call.serial),
+                Field::Destination(&call.sender),
+            ];
+            if !signature.is_empty() {
+                fields.push(Field::Signature(signature));
+            }
+            self.send(METHOD_RETURN, &fields, body).map(|_| ())
+        }
+
+        fn error(&mut self, call: &Message, name: &str, text: &str) -> Result<()> {
+            let mut body = Writer::default();
+            body.string(text);
+            self.send(
+                ERROR,
+                &[
+                    Field::ReplySerial(call.serial),
+                    Field::Destination(&call.sender),
+                    Field::ErrorName(name),
+                    Field::Signature("s"),
+                ],
+                &body.buf,
+            )
+            .map(|_| ())
+        }
+
+        fn properties_changed(&mut self, state: &State) -> Result<()> {
+            let mut body = Writer::default();
+            body.string(INTERFACE);
+            body.properties(state);
+            body.array(4, |_| {});
+            self.send(
+                SIGNAL,
+                &[
+                    Field::Path(PATH),
+                    Field::Interface(PROPERTIES),
+                    Field::Member("PropertiesChanged"),
+                    Field::Signature("sa{sv}as"),
+                ],
+                &body.buf,
+            )
+            .map(|_| ())
+        }
+    }
+
+    /// Answers a method call.
+    fn handle(
+        connection: &mut Connection,
+        path: &PathBuf,
+        timer: &str,
+        call: &Message,
+    ) -> Result<()> {
+        log::info(|| {
+            format!(
+                "DBus {}.{} from {}",
+                call.interface, call.member, call.sender
+            )
+        });
+        let args = call.strings();
+        let state = match State::load(path, timer) {
+            Ok(state) => state,
+            Err(e) => {
+                connection.error(
+                    call,
+                    "org.trackwork.Tracker.Error.Failed",
+                    &format!("{:#}", e),
+                )?;
+                return Ok(());
+            }
+        };
+        let mut body = Writer::default();
+        let result = match (call.interface.as_str(), call.member.as_str(), &args) {
+            ("org.freedesktop.DBus.Introspectable", "Introspect", _) => {
+                body.string(INTROSPECTION);
+                connection.reply(call, "s", &body.buf)?;
+                return Ok(());
+            }
+            ("org.freedesktop.DBus.Peer", "Ping", _) => {
+                connection.reply(call, "", &[])?;
+                return Ok(());
+            }
+            (PROPERTIES, "Get", Ok(args)) if args.len() == 2 && args[0] == INTERFACE => {
+                body.property(&state, &args[1]);
+                connection.reply(call, "v", &body.buf)?;
+                return Ok(());
+            }
+            (PROPERTIES, "GetAll", Ok(args)) if args.len() == 1 && args[0] == INTERFACE => {
+                body.properties(&state);
+                connection.reply(call, "a{sv}", &body.buf)?;
+                return Ok(());
+            }
+            (INTERFACE | "", "Status", _) => {
+                body.bool(state.tracking);
+                body.string(&state.objective);
+                body.i64(state.since);
+                connection.reply(call, "bsx", &body.buf)?;
+                return Ok(());
+            }
+            (INTERFACE | "", "Start", Ok(args)) if args.len() == 1 => {
+                crate::start(path, timer, args[0].clone(), Meta::new(), None, false)
+                    .map(|_| mqtt::event(path, timer, "started"))
+            }
+            (INTERFACE | "", "Stop", _) => {
+                // Keeps the objective instead of asking for one
+                crate::stop(path, timer, Some(state.objective), None, false)
+                    .map(|_| mqtt::event(path, timer, "stopped"))
+            }
+            _ => {
+                connection.error(
+                    call,
+                    "org.freedesktop.DBus.Error.UnknownMethod",
+                    &format!("No method {}.{}", call.interface, call.member),
+                )?;
+                return Ok(());
+            }
+        };
+        match result {
+            Ok(()) => connection.reply(call, "", &[])?,
+            Err(e) => connection.error(
+                call,
+                "org.trackwork.Tracker.Error.Failed",
+                &format!("{:#}", e),
+            )?,
+        }
+        Ok(())
+    }
+
+    /// Emits `PropertiesChanged` if the state differs from `state`. A failed
+    /// read, e.g. while another command replaces the file, is only reported.
+    fn refresh(
+        connection: &mut Connection,
+        path: &PathBuf,
+        timer: &str,
+        state: &mut State,
+    ) -> Result<()> {
+        match State::load(path, timer) {
+            Ok(current) if current != *state => {
+                *state = current;
+                connection.properties_changed(state)?;
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("Could not read {}: {:#}", path.display(), e),
+        }
+        Ok(())
+    }
+
+    /// Serves the session of `timer` on the session bus until killed.
+    pub fn service(path: &PathBuf, timer: &str) -> Result<()> {
+        let mut stream = connect()?;
+        authenticate(&mut stream)?;
+        let receiver = messages(stream.try_clone()?);
+        let mut connection = Connection { stream, serial: 0 };
+        connection.call_bus("Hello", "", &[])?;
+        let mut request = Writer::default();
+        request.string(NAME);
+        // Don't queue behind another instance
+        request.u32(4);
+        let request_serial = connection.call_bus("RequestName", "su", &request.buf)?;
+        let mut state = State::load(path, timer)?;
+        loop {
+            select! {
+                recv(receiver) -> message => {
+                    let message = message
+                        .context("The session bus connection closed")?
+                        .context("Could not read from the session bus")?;
+                    match message.kind {
+                        METHOD_CALL if message.path == PATH => {
+                            handle(&mut connection, path, timer, &message)?;
+                            refresh(&mut connection, path, timer, &mut state)?;
+                        }
+                        METHOD_CALL => {
+                            connection.error(
+                                &message,
+                                "org.freedesktop.DBus.Error.UnknownObject",
+                                &format!("No object {}", message.path),
+                            )?;
+                        }
+                        METHOD_RETURN if message.reply_serial == Some(request_serial) => {
+                            let mut reader = Reader { buf: &message.body, pos: 0, little: message.little };
+                            // 1: now the primary owner, 4: already the owner
+                            match reader.u32()? {
+                                1 | 4 => println!("Serving {} on the session bus", NAME),
+                                _ => return Err(Error::msg(format!("{} is already served", NAME))),
+                            }
+                        }
+                        ERROR if message.reply_serial == Some(request_serial) => {
+                            return Err(Error::msg(format!("Could not request the name {}", NAME)));
+                        }
+                        _ => {}
+                    }
+                },
+                default(POLL) => {
+                    // Changes made by other commands
+                    refresh(&mut connection, path, timer, &mut state)?;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_fields_round_trip() {
+        let mut body = Writer::default();
+        body.string("review");
+        let buf = encode(
+            METHOD_CALL,
+            7,
+            &[
+                Field::Path(PATH),
+                Field::Interface(INTERFACE),
+                Field::Member("Start"),
+                Field::Destination(NAME),
+                Field::ReplySerial(3),
+                Field::Signature("s"),
+            ],
+            &body.buf,
+        );
+        let message = Message::parse(&buf).unwrap();
+        assert_eq!(message.kind, METHOD_CALL);
+        assert_eq!(message.serial, 7);
+        assert_eq!(message.path, PATH);
+        assert_eq!(message.interface, INTERFACE);
+        assert_eq!(message.member, "Start");
+        assert_eq!(message.reply_serial, Some(3));
+        assert_eq!(message.signature, "s");
+        assert_eq!(message.strings().unwrap(), vec!["review".to_string()]);
+    }
+
+    #[test]
+    fn empty_body_round_trip() {
+        let buf = encode(SIGNAL, 1, &[Field::Member("Stop")], &[]);
+        assert_eq!(buf.len() % 8, 0);
+        let message = Message::parse(&buf).unwrap();
+        assert_eq!(message.kind, SIGNAL);
+        assert_eq!(message.member, "Stop");
+        assert!(message.body.is_empty());
+        assert!(message.strings().unwrap().is_empty());
+    }
+
+    #[test]
+    fn values_are_aligned() {
+        let mut w = Writer::default();
+        w.byte(1);
+        w.u32(2);
+        w.string("ab");
+        w.i64(-1);
+        w.signature("bsx");
+        assert_eq!(w.buf.len(), 29);
+        let mut reader = Reader {
+            buf: &w.buf,
+            pos: 0,
+            little: true,
+        };
+        assert_eq!(reader.byte().unwrap(), 1);
+        assert_eq!(reader.u32().unwrap(), 2);
+        assert_eq!(reader.string().unwrap(), "ab");
+        reader.align(8);
+        assert_eq!(reader.bytes(8).unwrap(), &(-1i64).to_le_bytes());
+        assert_eq!(reader.signature().unwrap(), "bsx");
+        assert!(reader.byte().is_err());
+    }
+
+    #[test]
+    fn reads_big_endian() {
+        let buf = [0, 0, 0, 2, b'h', b'i', 0];
+        let mut reader = Reader {
+            buf: &buf,
+            pos: 0,
+            little: false,
+        };
+        assert_eq!(reader.string().unwrap(), "hi");
+    }
+
+    #[test]
+    fn properties_are_a_dict_of_variants() {
+        let state = State {
+            tracking: true,
+            objective: "review".into(),
+            since: 1_600_000_000,
+        };
+        let mut w = Writer::default();
+        w.properties(&state);
+        let mut reader = Reader {
+            buf: &w.buf,
+            pos: 0,
+            little: true,
+        };
+        let length = reader.u32().unwrap() as usize;
+        reader.align(8);
+        assert_eq!(reader.pos + length, w.buf.len());
+        reader.align(8);
+        assert_eq!(reader.string().unwrap(), "Tracking");
+        assert_eq!(reader.signature().unwrap(), "b");
+        assert_eq!(reader.u32().unwrap(), 1);
+        reader.align(8);
+        assert_eq!(reader.string().unwrap(), "Objective");
+        assert_eq!(reader.signature().unwrap(), "s");
+        assert_eq!(reader.string().unwrap(), "review");
+        reader.align(8);
+        assert_eq!(reader.string().unwrap(), "Since");
+        assert_eq!(reader.signature().unwrap(), "x");
+        reader.align(8);
+        assert_eq!(reader.bytes(8).unwrap(), &1_600_000_000i64.to_le_bytes());
+    }
+}
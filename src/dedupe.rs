@@ -0,0 +1,59 @@
+//! Finds duplicated entries, e.g. after imports and merges, and keeps one
+//! of each.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use time::Duration;
+
+use crate::join::combine;
+use crate::{read, write, Tracker};
+
+/// Whether both start and end of `a` and `b` are at most `tolerance` apart.
+fn near(a: &Tracker, b: &Tracker, tolerance: Duration) -> bool {
+    let close = |x, y| {
+        let diff: Duration = x - y;
+        diff.abs() <= tolerance
+    };
+    a.timer == b.timer
+        && close(a.start, b.start)
+        && match (a.end, b.end) {
+            (Some(x), Some(y)) => close(x, y),
+            (None, None) => true,
+            _ => false,
+        }
+}
+
+/// Combines entries whose start and end differ by at most `tolerance`, with
+/// `overlapping` all overlapping entries of a timer, into the earliest one.
+/// Its objective wins, the notes and metadata of the others are kept.
+pub fn dedupe(path: &PathBuf, tolerance: Duration, overlapping: bool) -> Result<()> {
+    let mut data = read(path)?;
+    data.sort_by_key(|entry| entry.start);
+    let mut kept: Vec<Tracker> = Vec::with_capacity(data.len());
+    let mut removed = 0;
+    for entry in data {
+        let duplicate = kept.iter().rposition(|other| {
+            near(other, &entry, tolerance) || (overlapping && other.overlaps(&entry))
+        });
+        match duplicate {
+            Some(index) => {
+                println!("Duplicate: {}", entry);
+                println!("       of: {}", kept[index]);
+                kept[index] = combine(kept[index].clone(), entry);
+                removed += 1;
+            }
+            None => kept.push(entry),
+        }
+    }
+    if removed > 0 {
+        crate::confirm(&format!("Remove {} duplicates?", removed))?;
+        write(path, &kept)?;
+    }
+    if crate::dry_run() {
+        println!("Would remove {} duplicates", removed);
+    } else {
+        println!("Removed {} duplicates", removed);
+    }
+    Ok(())
+}
@@ -0,0 +1,26 @@
+/// The name of this machine, stamped on every started session. Can be
+/// overridden with the `TRACK_WORK_DEVICE` environment variable.
+pub fn name() -> String {
+    if let Ok(name) = std::env::var("TRACK_WORK_DEVICE") {
+        return name;
+    }
+    hostname().unwrap_or_default()
+}
+
+#[cfg(unix)]
+fn hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+    // SAFETY: the buffer is valid for `buf.len()` bytes and gethostname
+    // never writes beyond the given length.
+    let ret = unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) };
+    if ret != 0 {
+        return None;
+    }
+    let len = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+    Some(String::from_utf8_lossy(&buf[..len]).into_owned())
+}
+
+#[cfg(not(unix))]
+fn hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME").ok()
+}
@@ -0,0 +1,146 @@
+use std::path::PathBuf;
+
+use anyhow::{Error, Result};
+use csv::StringRecord;
+
+use crate::{git, lock, parse, records, store, Tracker};
+
+/// Repairs `doctor` applies to the storage file.
+#[derive(Debug, Default)]
+pub struct Fixes {
+    /// Remove rows that can't be parsed
+    pub drop_invalid: bool,
+    /// Swap start and end of entries that end before they start
+    pub swap_reversed: bool,
+    /// Split sessions spanning multiple days at local midnight
+    pub split_midnight: bool,
+}
+
+fn line(record: &StringRecord) -> u64 {
+    record.position().map_or(0, |p| p.line())
+}
+
+/// Reports problems in the storage file and applies the requested fixes.
+/// With `only_overlaps` just the overlapping sessions are listed.
+pub fn doctor(path: &PathBuf, fixes: &Fixes, only_overlaps: bool) -> Result<()> {
+    let mut problems = 0;
+    let mut invalid_rows = 0;
+    let mut entries: Vec<(u64, Tracker)> = Vec::new();
+    let (columns, records) = records(path)?;
+    for record in records {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                if !only_overlaps {
+                    println!("{}", e);
+                    problems += 1;
+                }
+                invalid_rows += 1;
+                continue;
+            }
+        };
+        let line = line(&record);
+        match parse(record, &columns) {
+            Ok(entry) => entries.push((line, entry)),
+            Err(e) => {
+                if !only_overlaps {
+                    println!("{}", e);
+                    problems += 1;
+                }
+                invalid_rows += 1;
+            }
+        }
+    }
+
+    let mut reversed = 0;
+    let mut multi_day = 0;
+    for (line, entry) in entries.iter().filter(|_| !only_overlaps) {
+        match entry.end {
+            Some(end) if end < entry.start => {
+                println!("line {}: ends before it starts: {}", line, entry);
+                reversed += 1;
+            }
+            Some(_) if entry.clone().split_at_midnight().len() > 1 => {
+                println!("line {}: spans multiple days: {}", line, entry);
+                multi_day += 1;
+            }
+            _ => {}
+        }
+    }
+    problems += reversed + multi_day;
+
+    let mut sorted = entries.iter().collect::<Vec<_>>();
+    sorted.sort_by_key(|(_, entry)| entry.start);
+    for (i, (line, entry)) in sorted.iter().enumerate() {
+        for (other_line, other) in &sorted[i + 1..] {
+            if other.start >= entry.end_or_now() {
+                break;
+            }
+            if entry.overlaps(other) {
+                println!(
+                    "line {}: overlaps line {}: {} / {}",
+                    line, other_line, entry, other
+                );
+                problems += 1;
+            }
+        }
+    }
+
+    let fix = (fixes.drop_invalid && invalid_rows > 0)
+        || (fixes.swap_reversed && reversed > 0)
+        || (fixes.split_midnight && multi_day > 0);
+    if !fix {
+        match problems {
+            0 => println!("No problems found"),
+            n => println!("{} problems found", n),
+        }
+        return Ok(());
+    }
+    if invalid_rows > 0 && !fixes.drop_invalid {
+        return Err(Error::msg(
+            "Rewriting the storage file would drop the invalid rows, correct them or pass --drop-invalid",
+        ));
+    }
+    let mut data = entries
+        .into_iter()
+        .map(|(_, entry)| entry)
+        .collect::<Vec<_>>();
+    let old = data.clone();
+    if fixes.swap_reversed {
+        for entry in data.iter_mut() {
+            if let Some(end) = entry.end.filter(|end| *end < entry.start) {
+                entry.end = Some(entry.start);
+                entry.start = end;
+            }
+        }
+    }
+    if fixes.split_midnight {
+        data = data
+            .into_iter()
+            .flat_map(Tracker::split_at_midnight)
+            .collect();
+    }
+    // Not through `write`, which can't read the file with unparseable rows
+    lock::check(path, &old, &data)?;
+    if crate::dry_run() {
+        if fixes.drop_invalid && invalid_rows > 0 {
+            println!("Would drop {} invalid rows", invalid_rows);
+        }
+        crate::print_diff(&old, &data);
+        return Ok(());
+    }
+    // Not journaled, the journal can't represent the unparseable rows. The
+    // previous version is still kept as a backup.
+    store(path, &data)?;
+    git::commit(path, "doctor: repair storage file")?;
+    if fixes.drop_invalid {
+        println!("Dropped {} invalid rows", invalid_rows);
+    }
+    if fixes.swap_reversed {
+        println!("Swapped start and end of {} entries", reversed);
+    }
+    if fixes.split_midnight {
+        println!("Split {} sessions at midnight", multi_day);
+    }
+    Ok(())
+}
@@ -0,0 +1,132 @@
+//! Estimates per objective, kept next to the storage file in
+//! `<name>-estimates.csv`, and how the tracked time compares to them.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Error, Result};
+use time::Duration;
+
+use crate::{archive, format, git, Tracker};
+
+fn estimates_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}-estimates.csv", stem))
+}
+
+/// The estimates by objective, in minutes.
+fn load(path: &Path) -> Result<BTreeMap<String, i64>> {
+    let file = estimates_path(path);
+    if !file.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let mut reader = csv::Reader::from_path(&file)
+        .with_context(|| format!("Could not read {}", file.display()))?;
+    reader
+        .records()
+        .map(|record| {
+            let record = record?;
+            let minutes = record
+                .get(1)
+                .and_then(|minutes| minutes.parse().ok())
+                .ok_or_else(|| Error::msg(format!("Invalid estimate in {}", file.display())))?;
+            Ok((record.get(0).unwrap_or_default().to_string(), minutes))
+        })
+        .collect()
+}
+
+fn save(path: &Path, estimates: &BTreeMap<String, i64>, message: &str) -> Result<()> {
+    let file = estimates_path(path);
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(["Objective", "Minutes"])?;
+    for (objective, minutes) in estimates {
+        writer.write_record([objective.as_str(), &minutes.to_string()])?;
+    }
+    fs::write(&file, writer.into_inner()?)
+        .with_context(|| format!("Could not write {}", file.display()))?;
+    git::commit(&file, message)
+}
+
+pub fn set(path: &Path, objective: &str, estimate: Duration) -> Result<()> {
+    let mut estimates = load(path)?;
+    estimates.insert(objective.to_string(), estimate.whole_minutes());
+    save(path, &estimates, &format!("estimate: {}", objective))?;
+    println!("Estimated {} at {}", objective, format::duration(estimate));
+    Ok(())
+}
+
+pub fn remove(path: &Path, objective: &str) -> Result<()> {
+    let mut estimates = load(path)?;
+    if estimates.remove(objective).is_none() {
+        return Err(Error::msg(format!(
+            "There is no estimate for {}",
+            objective
+        )));
+    }
+    save(path, &estimates, &format!("estimate: remove {}", objective))
+}
+
+/// Whether `entry` counts for the estimate of `objective`, the objective
+/// itself or one below it like `objective/task`.
+pub fn counts_for(entry: &Tracker, objective: &str) -> bool {
+    entry
+        .objective
+        .strip_prefix(objective)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Prints the estimated and tracked time of every estimated objective.
+pub fn report(path: &PathBuf) -> Result<()> {
+    let estimates = load(path)?;
+    if estimates.is_empty() {
+        println!("No estimates, add one with `estimate set <objective> <duration>`");
+        return Ok(());
+    }
+    let entries = archive::history(path)?;
+    println!("Objective, Estimate, Tracked, Variance");
+    let (mut total_estimate, mut total_tracked) = (Duration::zero(), Duration::zero());
+    for (objective, minutes) in estimates {
+        let estimate = Duration::minutes(minutes);
+        let tracked = entries
+            .iter()
+            .filter(|entry| counts_for(entry, &objective))
+            .fold(Duration::zero(), |acc, entry| {
+                acc + (entry.end_or_now() - entry.start)
+            });
+        println!(
+            "{}, {}, {}, {}",
+            objective,
+            format::duration(estimate),
+            format::duration(tracked),
+            variance(estimate, tracked)
+        );
+        total_estimate += estimate;
+        total_tracked += tracked;
+    }
+    println!(
+        "Total, {}, {}, {}",
+        format::duration(total_estimate),
+        format::duration(total_tracked),
+        variance(total_estimate, total_tracked)
+    );
+    Ok(())
+}
+
+/// How far off the estimate was, e.g. `+01:30 (+25%)` for an underestimate.
+fn variance(estimate: Duration, tracked: Duration) -> String {
+    let delta = tracked - estimate;
+    let sign = if delta < Duration::zero() { "-" } else { "+" };
+    let percent = if estimate > Duration::zero() {
+        delta.as_seconds_f64() / estimate.as_seconds_f64() * 100.0
+    } else {
+        0.0
+    };
+    format!(
+        "{}{} ({}{:.0}%)",
+        sign,
+        format::duration(delta.abs()),
+        sign,
+        percent.abs()
+    )
+}
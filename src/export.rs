@@ -0,0 +1,136 @@
+//! `export`, all entries including the archives as CSV on stdout, with
+//! `--anonymize` for sharing the time distribution without client names.
+//! The pseudonyms are kept in `<name>-pseudonyms.csv` next to the storage
+//! file, so they stay the same across exports.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Error, Result};
+
+use crate::config::Config;
+use crate::filter::Filter;
+use crate::{archive, git, Tracker, HEADER};
+
+fn pseudonyms_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}-pseudonyms.csv", stem))
+}
+
+/// Pseudonyms like `objective-3` by kind and name.
+struct Pseudonyms {
+    names: BTreeMap<(String, String), String>,
+    added: bool,
+}
+
+impl Pseudonyms {
+    fn load(path: &Path) -> Result<Pseudonyms> {
+        let file = pseudonyms_path(path);
+        let mut names = BTreeMap::new();
+        if file.exists() {
+            let mut reader = csv::Reader::from_path(&file)
+                .with_context(|| format!("Could not read {}", file.display()))?;
+            for record in reader.records() {
+                let record = record?;
+                match (record.get(0), record.get(1), record.get(2)) {
+                    (Some(kind), Some(name), Some(pseudonym)) => {
+                        names.insert((kind.to_string(), name.to_string()), pseudonym.to_string());
+                    }
+                    _ => return Err(Error::msg(format!("Invalid row in {}", file.display()))),
+                }
+            }
+        }
+        Ok(Pseudonyms {
+            names,
+            added: false,
+        })
+    }
+
+    fn get(&mut self, kind: &str, name: &str) -> String {
+        if name.is_empty() {
+            return String::new();
+        }
+        let key = (kind.to_string(), name.to_string());
+        if let Some(pseudonym) = self.names.get(&key) {
+            return pseudonym.clone();
+        }
+        let number = self.names.keys().filter(|(k, _)| k == kind).count() + 1;
+        let pseudonym = format!("{}-{}", kind, number);
+        self.names.insert(key, pseudonym.clone());
+        self.added = true;
+        pseudonym
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        if !self.added {
+            return Ok(());
+        }
+        let file = pseudonyms_path(path);
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record(["Kind", "Name", "Pseudonym"])?;
+        for ((kind, name), pseudonym) in &self.names {
+            writer.write_record([kind, name, pseudonym])?;
+        }
+        fs::write(&file, writer.into_inner()?)
+            .with_context(|| format!("Could not write {}", file.display()))?;
+        git::commit(&file, "export: pseudonyms")
+    }
+}
+
+/// Replaces each level of the objective, the timer and the metadata values
+/// of the `keys` of the `[anonymize]` config section, client and project by
+/// default. Notes and the device are left out.
+fn anonymize(path: &Path, config: &Config, data: &mut [Tracker]) -> Result<()> {
+    let keys = config
+        .get("anonymize", "keys")
+        .unwrap_or("client,project")
+        .split(',')
+        .map(str::trim)
+        .collect::<Vec<_>>();
+    let mut pseudonyms = Pseudonyms::load(path)?;
+    for entry in data.iter_mut() {
+        entry.objective = entry
+            .objective
+            .split('/')
+            .map(|level| pseudonyms.get("objective", level))
+            .collect::<Vec<_>>()
+            .join("/");
+        for (key, value) in entry.meta.iter_mut() {
+            if keys.contains(&key.as_str()) {
+                *value = pseudonyms.get(key, value);
+            }
+        }
+        entry.timer = pseudonyms.get("timer", &entry.timer);
+        entry.meta.remove("hash");
+        entry.notes.clear();
+        entry.device.clear();
+        entry.extra.clear();
+    }
+    pseudonyms.save(path)
+}
+
+/// Prints the entries of the archives and the storage file as CSV, only
+/// those matching `filter` if given.
+pub fn export(
+    path: &PathBuf,
+    config: &Config,
+    anonymized: bool,
+    filter: Option<&Filter>,
+) -> Result<()> {
+    let mut data = archive::history(path)?;
+    if let Some(filter) = filter {
+        data.retain(|entry| filter.matches(entry));
+    }
+    if anonymized {
+        anonymize(path, config, &mut data)?;
+    }
+    let mut writer = csv::Writer::from_writer(io::stdout());
+    writer.write_record(HEADER)?;
+    for entry in &data {
+        writer.write_record(entry.to_record())?;
+    }
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,264 @@
+//! Filter expressions for `info --where`, e.g.
+//! `project=acme && duration>1h && tag!=break`: conditions joined by `&&`
+//! and `||`, where `&&` binds tighter. Metadata holding a comma-separated
+//! list like `tag=backend,api` equals each of its items.
+
+use std::cmp::Ordering;
+
+use anyhow::{Error, Result};
+use time::{Date, Duration};
+
+use crate::{human, Tracker};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// Contains, ignoring case
+    Contains,
+    NotContains,
+}
+
+/// The operators, longer ones first so `!=` isn't taken for `=`.
+const OPS: [(&str, Op); 8] = [
+    ("!=", Op::Ne),
+    ("<=", Op::Le),
+    (">=", Op::Ge),
+    ("!~", Op::NotContains),
+    ("=", Op::Eq),
+    ("<", Op::Lt),
+    (">", Op::Gt),
+    ("~", Op::Contains),
+];
+
+#[derive(Debug, Clone)]
+enum Value {
+    Text(String),
+    Duration(Duration),
+    Date(Date),
+}
+
+#[derive(Debug, Clone)]
+enum Field {
+    Objective,
+    Notes,
+    Device,
+    Timer,
+    Duration,
+    Date,
+    /// Any other name is a metadata key
+    Meta(String),
+}
+
+#[derive(Debug, Clone)]
+struct Condition {
+    field: Field,
+    op: Op,
+    value: Value,
+}
+
+impl std::str::FromStr for Condition {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid =
+            |reason: &str| Error::msg(format!("Invalid condition {}: {}", s.trim(), reason));
+        let start = s
+            .find(|c| "!=<>~".contains(c))
+            .ok_or_else(|| invalid("expected e.g. project=acme"))?;
+        let (symbol, op) = OPS
+            .iter()
+            .find(|(symbol, _)| s[start..].starts_with(symbol))
+            .ok_or_else(|| invalid("unknown operator"))?;
+        let name = s[..start].trim();
+        let value = s[start + symbol.len()..].trim();
+        let field = match name {
+            "" => return Err(invalid("missing field")),
+            "objective" => Field::Objective,
+            "notes" => Field::Notes,
+            "device" => Field::Device,
+            "timer" => Field::Timer,
+            "duration" => Field::Duration,
+            "date" => Field::Date,
+            key => Field::Meta(key.to_string()),
+        };
+        let value = match field {
+            Field::Duration => Value::Duration(human::parse_duration(value)?),
+            Field::Date => Value::Date(human::parse_date(value)?),
+            // Quotes are optional, e.g. objective~"PROJ-12"
+            _ => Value::Text(
+                value
+                    .strip_prefix('"')
+                    .and_then(|value| value.strip_suffix('"'))
+                    .unwrap_or(value)
+                    .to_string(),
+            ),
+        };
+        let ordered = matches!(op, Op::Lt | Op::Le | Op::Gt | Op::Ge);
+        let contains = matches!(op, Op::Contains | Op::NotContains);
+        match value {
+            Value::Text(_) if ordered => Err(invalid("only duration and date can be compared")),
+            Value::Duration(_) | Value::Date(_) if contains => {
+                Err(invalid("only text can be searched with ~"))
+            }
+            _ => Ok(Condition {
+                field,
+                op: *op,
+                value,
+            }),
+        }
+    }
+}
+
+impl Condition {
+    fn matches(&self, entry: &Tracker) -> bool {
+        let ordering = match (&self.field, &self.value) {
+            (Field::Duration, Value::Duration(duration)) => {
+                (entry.end_or_now() - entry.start).cmp(duration)
+            }
+            (Field::Date, Value::Date(date)) => entry.start.date().cmp(date),
+            (field, Value::Text(value)) => {
+                let text: &str = match field {
+                    Field::Objective => &entry.objective,
+                    Field::Notes => &entry.notes,
+                    Field::Device => &entry.device,
+                    Field::Timer => &entry.timer,
+                    // A missing key is like an empty value
+                    Field::Meta(key) => entry.meta.get(key).map_or("", |v| v.as_str()),
+                    Field::Duration | Field::Date => unreachable!("parsed as text"),
+                };
+                match self.op {
+                    // Comma-separated lists like tags match any of their items
+                    Op::Eq | Op::Ne if matches!(field, Field::Meta(_)) => {
+                        let found =
+                            text == value || text.split(',').any(|item| item.trim() == value);
+                        return found == (self.op == Op::Eq);
+                    }
+                    Op::Contains => return text.to_lowercase().contains(&value.to_lowercase()),
+                    Op::NotContains => return !text.to_lowercase().contains(&value.to_lowercase()),
+                    _ => text.cmp(value.as_str()),
+                }
+            }
+            _ => unreachable!("the value is parsed for the field"),
+        };
+        match self.op {
+            Op::Eq => ordering == Ordering::Equal,
+            Op::Ne => ordering != Ordering::Equal,
+            Op::Lt => ordering == Ordering::Less,
+            Op::Le => ordering != Ordering::Greater,
+            Op::Gt => ordering == Ordering::Greater,
+            Op::Ge => ordering != Ordering::Less,
+            Op::Contains | Op::NotContains => unreachable!("only for text"),
+        }
+    }
+}
+
+/// Alternatives of conditions that all have to match.
+#[derive(Debug, Clone)]
+pub struct Filter(Vec<Vec<Condition>>);
+
+impl std::str::FromStr for Filter {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        s.split("||")
+            .map(|all| all.split("&&").map(str::parse).collect())
+            .collect::<Result<_>>()
+            .map(Filter)
+    }
+}
+
+impl Filter {
+    pub fn matches(&self, entry: &Tracker) -> bool {
+        self.0
+            .iter()
+            .any(|all| all.iter().all(|condition| condition.matches(entry)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::OffsetDateTime;
+
+    fn entry(objective: &str, hours: i64, meta: &[(&str, &str)]) -> Tracker {
+        let start = OffsetDateTime::now_local() - Duration::days(1);
+        let mut entry = Tracker::new(
+            start,
+            Some(start + Duration::hours(hours)),
+            objective.into(),
+        );
+        for (key, value) in meta {
+            entry.meta.insert(key.to_string(), value.to_string());
+        }
+        entry
+    }
+
+    fn matches(filter: &str, entry: &Tracker) -> bool {
+        filter.parse::<Filter>().unwrap().matches(entry)
+    }
+
+    #[test]
+    fn conditions() {
+        let review = entry("Review PROJ-12", 2, &[("project", "acme")]);
+        assert!(matches("project=acme", &review));
+        assert!(matches("project != other", &review));
+        assert!(matches("client=", &review));
+        assert!(matches(r#"objective~"proj-12""#, &review));
+        assert!(matches("objective!~meeting", &review));
+        assert!(matches("duration>1h", &review));
+        assert!(matches("duration<=2h", &review));
+        assert!(!matches("duration<2h", &review));
+        assert!(matches("date<today", &review));
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let review = entry("review", 2, &[("project", "acme")]);
+        assert!(matches("project=acme && duration>1h", &review));
+        assert!(!matches("project=acme && duration>3h", &review));
+        assert!(matches("project=other || duration>1h", &review));
+        assert!(matches(
+            "project=other && duration>3h || objective=review",
+            &review
+        ));
+        assert!(!matches(
+            "project=other && objective=review || duration>3h",
+            &review
+        ));
+    }
+
+    #[test]
+    fn lists_match_their_items() {
+        let tagged = entry("review", 1, &[("tag", "backend, api")]);
+        assert!(matches("tag=backend", &tagged));
+        assert!(matches("tag=api", &tagged));
+        assert!(matches("tag=backend, api", &tagged));
+        assert!(!matches("tag=back", &tagged));
+        assert!(matches("tag!=break", &tagged));
+        assert!(!matches("tag!=api", &tagged));
+        assert!(matches("tag!=", &tagged));
+    }
+
+    #[test]
+    fn invalid_filters() {
+        for invalid in [
+            "project",
+            "=acme",
+            "objective>review",
+            "duration~1h",
+            "duration>soon",
+            "date=someday",
+        ] {
+            assert!(
+                invalid.parse::<Filter>().is_err(),
+                "{:?} is invalid",
+                invalid
+            );
+        }
+    }
+}
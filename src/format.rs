@@ -0,0 +1,244 @@
+//! Output templates for `info --format`, e.g.
+//! `{date}\t{hours_decimal}\t{objective}`, given inline or by name from the
+//! `[formats]` config section, and how durations, dates and times are
+//! shown.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+use anyhow::{Error, Result};
+use time::{Date, Duration, OffsetDateTime};
+
+use crate::config::Config;
+use crate::Tracker;
+
+/// How durations are shown, `hm` like 01:45 by default.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DurationFormat {
+    /// Hours as a decimal number like 1.75
+    Decimal,
+    /// 01:45
+    Hm,
+    /// 01:45:30
+    Hms,
+    /// PT1H45M30S
+    Iso8601,
+}
+
+impl std::str::FromStr for DurationFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "decimal" => Ok(DurationFormat::Decimal),
+            "hm" => Ok(DurationFormat::Hm),
+            "hms" => Ok(DurationFormat::Hms),
+            "iso8601" => Ok(DurationFormat::Iso8601),
+            _ => Err(Error::msg(format!(
+                "Invalid duration format {}, expected decimal, hm, hms or iso8601",
+                s
+            ))),
+        }
+    }
+}
+
+static DURATION_FORMAT: AtomicU8 = AtomicU8::new(DurationFormat::Hm as u8);
+/// strftime-like formats of dates and times, `%F` and `%R` by default
+static DATE_FORMAT: OnceLock<String> = OnceLock::new();
+static TIME_FORMAT: OnceLock<String> = OnceLock::new();
+
+/// Uses `format`, else the `duration_format` of the `[display]` config
+/// section, and the `date_format` and `time_format` of that section.
+pub fn configure(config: &Config, format: Option<DurationFormat>) -> Result<()> {
+    let format = match format {
+        Some(format) => Some(format),
+        None => config
+            .get("display", "duration_format")
+            .map(str::parse)
+            .transpose()?,
+    };
+    if let Some(format) = format {
+        DURATION_FORMAT.store(format as u8, Ordering::SeqCst);
+    }
+    let check = |key, format: &str| {
+        time::validate_format_string(format)
+            .map_err(|e| Error::msg(format!("Invalid {} {}: {}", key, format, e)))
+    };
+    if let Some(format) = config.get("display", "date_format") {
+        check("date_format", format)?;
+        DATE_FORMAT.get_or_init(|| format.to_string());
+    }
+    if let Some(format) = config.get("display", "time_format") {
+        let format = match format {
+            "12h" => "%-I:%M %p",
+            "24h" => "%R",
+            format => format,
+        };
+        check("time_format", format)?;
+        TIME_FORMAT.get_or_init(|| format.to_string());
+    }
+    Ok(())
+}
+
+/// `date` in the configured format.
+pub fn date(date: Date) -> String {
+    // As a date and time, so a format with a time of day can't panic
+    date.midnight()
+        .assume_utc()
+        .format(DATE_FORMAT.get().map_or("%F", String::as_str))
+}
+
+/// The time of day of `at` in the configured format.
+pub fn time(at: OffsetDateTime) -> String {
+    at.format(TIME_FORMAT.get().map_or("%R", String::as_str))
+}
+
+/// The date and time of day of `at` in the configured formats.
+pub fn date_time(at: OffsetDateTime) -> String {
+    format!("{} {}", date(at.date()), time(at))
+}
+
+/// `duration` in the configured format.
+pub fn duration(duration: Duration) -> String {
+    let format = match DURATION_FORMAT.load(Ordering::SeqCst) {
+        f if f == DurationFormat::Decimal as u8 => DurationFormat::Decimal,
+        f if f == DurationFormat::Hms as u8 => DurationFormat::Hms,
+        f if f == DurationFormat::Iso8601 as u8 => DurationFormat::Iso8601,
+        _ => DurationFormat::Hm,
+    };
+    duration_as(duration, format)
+}
+
+/// `duration` in `format`, only `hm` drops the seconds.
+pub fn duration_as(duration: Duration, format: DurationFormat) -> String {
+    let (hours, minutes, seconds) = (
+        duration.whole_hours(),
+        duration.whole_minutes() % 60,
+        duration.whole_seconds() % 60,
+    );
+    match format {
+        DurationFormat::Decimal => format!("{:.2}", duration.as_seconds_f64() / 3600.0),
+        DurationFormat::Hm => format!("{:02}:{:02}", hours, minutes),
+        DurationFormat::Hms => format!("{:02}:{:02}:{:02}", hours, minutes, seconds),
+        DurationFormat::Iso8601 => {
+            let mut iso = "PT".to_string();
+            if hours != 0 {
+                iso += &format!("{}H", hours);
+            }
+            if minutes != 0 || (hours == 0 && seconds == 0) {
+                iso += &format!("{}M", minutes);
+            }
+            if seconds != 0 {
+                iso += &format!("{}S", seconds);
+            }
+            iso
+        }
+    }
+}
+
+/// The placeholders besides `meta.KEY`.
+const PLACEHOLDERS: [&str; 13] = [
+    "date",
+    "start",
+    "end",
+    "duration",
+    "duration_hms",
+    "duration_iso",
+    "hours_decimal",
+    "minutes",
+    "seconds",
+    "objective",
+    "notes",
+    "timer",
+    "device",
+];
+
+#[derive(Debug, Clone)]
+enum Part {
+    Text(String),
+    Placeholder(String),
+}
+
+/// A parsed output template.
+#[derive(Debug, Clone)]
+pub struct Format(Vec<Part>);
+
+impl Format {
+    /// The template named `s` in the `[formats]` config section, or `s`
+    /// itself if there is none.
+    pub fn load(config: &Config, s: &str) -> Result<Format> {
+        let template = config.get("formats", s).unwrap_or(s);
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while let Some(open) = rest.find('{') {
+            parts.push(Part::Text(unescape(&rest[..open])));
+            let close = rest[open..]
+                .find('}')
+                .ok_or_else(|| Error::msg(format!("Unclosed placeholder in format {}", s)))?;
+            let key = &rest[open + 1..open + close];
+            if !PLACEHOLDERS.contains(&key) && !key.starts_with("meta.") {
+                return Err(Error::msg(format!(
+                    "Unknown placeholder {{{}}}, expected one of {{{}}} or {{meta.KEY}}",
+                    key,
+                    PLACEHOLDERS.join("}, {")
+                )));
+            }
+            parts.push(Part::Placeholder(key.to_string()));
+            rest = &rest[open + close + 1..];
+        }
+        parts.push(Part::Text(unescape(rest)));
+        Ok(Format(parts))
+    }
+
+    /// The line of `entry`, a running session ends now.
+    pub fn render(&self, entry: &Tracker) -> String {
+        let end = entry.end.unwrap_or_else(OffsetDateTime::now_local);
+        let duration = end - entry.start;
+        self.0
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Placeholder(key) => match key.as_str() {
+                    "date" => entry.start.format("%F"),
+                    "start" => entry.start.format("%R"),
+                    "end" => entry.end.map(|end| end.format("%R")).unwrap_or_default(),
+                    "duration" => self::duration(duration),
+                    "duration_hms" => duration_as(duration, DurationFormat::Hms),
+                    "duration_iso" => duration_as(duration, DurationFormat::Iso8601),
+                    "hours_decimal" => duration_as(duration, DurationFormat::Decimal),
+                    "minutes" => duration.whole_minutes().to_string(),
+                    "seconds" => duration.whole_seconds().to_string(),
+                    "objective" => entry.objective.clone(),
+                    "notes" => entry.notes.lines().collect::<Vec<_>>().join("; "),
+                    "timer" => entry.timer.clone(),
+                    "device" => entry.device.clone(),
+                    key => key
+                        .strip_prefix("meta.")
+                        .and_then(|key| entry.meta.get(key))
+                        .cloned()
+                        .unwrap_or_default(),
+                },
+            })
+            .collect()
+    }
+}
+
+/// Replaces `\t`, `\n` and `\\` with what they stand for, as shells pass
+/// them on literally.
+fn unescape(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
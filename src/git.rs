@@ -0,0 +1,83 @@
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{Context, Error, Result};
+
+use crate::log;
+
+/// Whether every change to the storage file is committed to git.
+pub static ENABLED: AtomicBool = AtomicBool::new(false);
+
+fn data_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}
+
+/// Runs git in `dir`, returning its output.
+pub fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let _span = log::span(|| format!("git {}", args.join(" ")));
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .context("Could not run git, is it installed?")?;
+    if !output.status.success() {
+        return Err(Error::msg(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn ensure_repo(dir: &Path) -> Result<()> {
+    if git(dir, &["rev-parse", "--is-inside-work-tree"]).is_err() {
+        git(dir, &["init", "--quiet"])?;
+    }
+    Ok(())
+}
+
+fn file_name(path: &Path) -> Result<&str> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| Error::msg(format!("Invalid storage file name: {}", path.display())))
+}
+
+/// Stages a file to be included in the next commit.
+pub fn stage(path: &Path) -> Result<()> {
+    if !ENABLED.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+    let dir = data_dir(path);
+    ensure_repo(dir)?;
+    git(dir, &["add", "--", file_name(path)?])?;
+    Ok(())
+}
+
+/// Commits the storage file, creating the repository if necessary.
+pub fn commit(path: &Path, message: &str) -> Result<()> {
+    if !ENABLED.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+    stage(path)?;
+    let dir = data_dir(path);
+    if git(dir, &["diff", "--cached", "--quiet"]).is_ok() {
+        return Ok(());
+    }
+    git(dir, &["commit", "--quiet", "--message", message])?;
+    Ok(())
+}
+
+/// Pulls remote changes and pushes local commits of the data repository.
+pub fn sync(path: &Path) -> Result<()> {
+    let dir = data_dir(path);
+    git(dir, &["pull", "--rebase", "--quiet"])?;
+    git(dir, &["push", "--quiet"])?;
+    println!("Synchronized {}", dir.display());
+    Ok(())
+}
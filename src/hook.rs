@@ -0,0 +1,100 @@
+//! Git hooks of the current repository that switch the objective on branch
+//! checkouts and note commits in the running session.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Error, Result};
+
+use crate::git;
+
+/// Marks hooks written by `hook install`, others are never overwritten.
+const MARKER: &str = "# Installed by track-work";
+
+/// Quotes `s` for a POSIX shell.
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// The hook scripts by name. Failures, e.g. when nothing is tracked, are
+/// ignored so git is never disturbed.
+fn scripts(program: &str) -> [(&'static str, String); 2] {
+    [
+        (
+            "post-checkout",
+            format!(
+                "#!/bin/sh\n{}\n\
+                 # Only branch checkouts, not files\n\
+                 [ \"$3\" = 1 ] || exit 0\n\
+                 branch=$(git symbolic-ref --short -q HEAD) || exit 0\n\
+                 repo=$(basename \"$(git rev-parse --show-toplevel)\")\n\
+                 {} switch -o \"$repo: $branch\" >/dev/null 2>&1 || true\n",
+                MARKER, program
+            ),
+        ),
+        (
+            "post-commit",
+            format!(
+                "#!/bin/sh\n{}\n\
+                 {} note \"commit $(git log -1 --format='%h %s')\" >/dev/null 2>&1 || true\n",
+                MARKER, program
+            ),
+        ),
+    ]
+}
+
+/// The hooks directory of the git repository of the working directory.
+fn hooks_dir() -> Result<PathBuf> {
+    let hooks = git::git(Path::new("."), &["rev-parse", "--git-path", "hooks"])
+        .context("Not inside a git repository")?;
+    Ok(PathBuf::from(hooks.trim()))
+}
+
+/// Writes the hooks into the git repository of the working directory, they
+/// call this executable with the given storage file and timer.
+pub fn install(path: &Path, timer: &str, force: bool) -> Result<()> {
+    let hooks = hooks_dir()?;
+    let exe = std::env::current_exe().context("Could not find the track-work executable")?;
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut program = format!(
+        "{} --file {}",
+        quote(&exe.to_string_lossy()),
+        quote(&path.to_string_lossy())
+    );
+    if !timer.is_empty() {
+        program += &format!(" --timer {}", quote(timer));
+    }
+    fs::create_dir_all(&hooks)?;
+    for (name, script) in scripts(&program).iter() {
+        let hook = hooks.join(name);
+        if let Ok(existing) = fs::read_to_string(&hook) {
+            if !existing.contains(MARKER) && !force {
+                return Err(Error::msg(format!(
+                    "{} already exists, pass --force to replace it",
+                    hook.display()
+                )));
+            }
+        }
+        fs::write(&hook, script).with_context(|| format!("Could not write {}", hook.display()))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&hook, fs::Permissions::from_mode(0o755))?;
+        }
+        println!("Installed {}", hook.display());
+    }
+    Ok(())
+}
+
+/// Removes the hooks written by `install`.
+pub fn uninstall() -> Result<()> {
+    let hooks = hooks_dir()?;
+    for name in ["post-checkout", "post-commit"] {
+        let hook = hooks.join(name);
+        if fs::read_to_string(&hook).is_ok_and(|existing| existing.contains(MARKER)) {
+            fs::remove_file(&hook)?;
+            println!("Removed {}", hook.display());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,238 @@
+use std::borrow::Cow;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{Context, Error, Result};
+
+use crate::log;
+
+/// A tiny HTTP client on top of the `curl` executable, which keeps TLS and
+/// authentication handling out of this crate.
+pub struct Request<'a> {
+    method: &'a str,
+    url: String,
+    headers: Vec<String>,
+    user: Option<(&'a str, &'a str)>,
+    sigv4: Option<String>,
+    body: Option<Vec<u8>>,
+}
+
+pub struct Response {
+    pub status: u16,
+    headers: Vec<(String, String)>,
+    /// The raw body, which can be binary like an encrypted storage file
+    pub body: Vec<u8>,
+}
+
+impl<'a> Request<'a> {
+    pub fn new(method: &'a str, url: impl Into<String>) -> Self {
+        Request {
+            method,
+            url: url.into(),
+            headers: Vec::new(),
+            user: None,
+            sigv4: None,
+            body: None,
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push(format!("{}: {}", name, value));
+        self
+    }
+
+    pub fn basic_auth(mut self, user: &'a str, password: &'a str) -> Self {
+        self.user = Some((user, password));
+        self
+    }
+
+    /// Signs the request with AWS Signature V4, e.g. for `aws:amz:us-east-1:s3`.
+    /// The credentials are taken from `basic_auth`.
+    pub fn aws_sigv4(mut self, provider: impl Into<String>) -> Self {
+        self.sigv4 = Some(provider.into());
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    pub fn send(self) -> Result<Response> {
+        let _span = log::span(|| format!("{} {}", self.method, self.url));
+        let mut cmd = Command::new("curl");
+        cmd.args([
+            "--silent",
+            "--show-error",
+            "--include",
+            "--request",
+            self.method,
+        ])
+        .args(["--write-out", "\n%{http_code}"]);
+        for header in &self.headers {
+            cmd.args(["--header", header]);
+        }
+        if let Some(provider) = &self.sigv4 {
+            cmd.args(["--aws-sigv4", provider]);
+        }
+        // The credentials go through a config on stdin, on the command line
+        // every user could read them, so the body has to come from a file.
+        cmd.args(["--config", "-"]);
+        let body = match &self.body {
+            Some(body) => {
+                let file = BodyFile::write(body)?;
+                cmd.arg("--data-binary")
+                    .arg(format!("@{}", file.0.display()));
+                Some(file)
+            }
+            None => None,
+        };
+        cmd.arg(&self.url)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let mut child = cmd
+            .spawn()
+            .context("Could not run curl, is it installed?")?;
+        let mut config = String::new();
+        if let Some((user, password)) = self.user {
+            config += &format!("user = {}\n", quote(&format!("{}:{}", user, password)));
+        }
+        child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(config.as_bytes())?;
+        let output = child.wait_with_output()?;
+        drop(body);
+        if !output.status.success() {
+            return Err(Error::msg(format!(
+                "Request {} {} failed: {}",
+                self.method,
+                self.url,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        let output = output.stdout;
+        let (mut rest, status) = match output.iter().rposition(|&byte| byte == b'\n') {
+            Some(newline) => (&output[..newline], &output[newline + 1..]),
+            None => (&output[..0], &output[..]),
+        };
+        let status = String::from_utf8_lossy(status);
+        let status = status
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid HTTP status from curl: {}", status))?;
+        // Skip informational responses like `100 Continue` preceding the
+        // actual header block.
+        let mut headers = Vec::new();
+        while rest.starts_with(b"HTTP/") {
+            let (block, body) = split_once(rest, b"\r\n\r\n")
+                .or_else(|| split_once(rest, b"\n\n"))
+                .unwrap_or((rest, &[]));
+            headers = String::from_utf8_lossy(block)
+                .lines()
+                .skip(1)
+                .filter_map(|line| line.split_once(':'))
+                .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+                .collect();
+            rest = body;
+        }
+        Ok(Response {
+            status,
+            headers,
+            body: rest.to_vec(),
+        })
+    }
+}
+
+/// Quotes `value` for a curl config file.
+fn quote(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '\\' => quoted += "\\\\",
+            '"' => quoted += "\\\"",
+            '\n' => quoted += "\\n",
+            '\r' => quoted += "\\r",
+            '\t' => quoted += "\\t",
+            c => quoted.push(c),
+        }
+    }
+    quoted + "\""
+}
+
+/// A request body in a file only the user can read, removed when dropped.
+struct BodyFile(PathBuf);
+
+impl BodyFile {
+    fn write(body: &[u8]) -> Result<BodyFile> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "track-work-body-{}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::SeqCst)
+        ));
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let file = BodyFile(path);
+        options
+            .open(&file.0)
+            .and_then(|mut f| f.write_all(body))
+            .with_context(|| format!("Could not write {}", file.0.display()))?;
+        Ok(file)
+    }
+}
+
+impl Drop for BodyFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// Splits `bytes` around the first occurrence of `separator`.
+fn split_once<'b>(bytes: &'b [u8], separator: &[u8]) -> Option<(&'b [u8], &'b [u8])> {
+    bytes
+        .windows(separator.len())
+        .position(|window| window == separator)
+        .map(|at| (&bytes[..at], &bytes[at + separator.len()..]))
+}
+
+impl Response {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let name = name.to_lowercase();
+        self.headers
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The body as text, invalid UTF-8 replaced.
+    pub fn text(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.body)
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    pub fn error_for_status(self) -> Result<Self> {
+        if self.is_success() {
+            Ok(self)
+        } else {
+            Err(Error::msg(format!(
+                "Server responded with status {}: {}",
+                self.status,
+                self.text().trim()
+            )))
+        }
+    }
+}
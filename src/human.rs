@@ -0,0 +1,267 @@
+//! Parsing of the dates, times and durations given on the command line,
+//! e.g. `yesterday 9am`, `monday 14:00`, `-2h` or `1h30m`.
+
+use anyhow::{Context, Error, Result};
+use time::{Date, Duration, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset, Weekday};
+
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("monday", Weekday::Monday),
+    ("tuesday", Weekday::Tuesday),
+    ("wednesday", Weekday::Wednesday),
+    ("thursday", Weekday::Thursday),
+    ("friday", Weekday::Friday),
+    ("saturday", Weekday::Saturday),
+    ("sunday", Weekday::Sunday),
+];
+
+/// A weekday by its name or a prefix of at least three letters, e.g. `mon`.
+pub fn weekday(word: &str) -> Option<Weekday> {
+    WEEKDAYS
+        .iter()
+        .find(|(name, _)| word.len() >= 3 && name.starts_with(word))
+        .map(|(_, day)| *day)
+}
+
+/// A day relative to `today`: `today`, `yesterday`, a weekday (the most
+/// recent one, today included) or `YYYY-MM-DD`.
+fn day(word: &str, today: Date) -> Option<Date> {
+    match word {
+        "today" => Some(today),
+        "yesterday" => Some(today.previous_day()),
+        "tomorrow" => Some(today.next_day()),
+        _ => match weekday(word) {
+            Some(weekday) => {
+                let back = (7 + today.weekday().number_days_from_monday()
+                    - weekday.number_days_from_monday())
+                    % 7;
+                Some(today - Duration::days(back.into()))
+            }
+            None => Date::parse(word, "%F").ok(),
+        },
+    }
+}
+
+/// A time of day: `17:30`, `17:30:15`, `9am`, `9:30pm`, `noon` or `midnight`.
+pub fn clock(word: &str) -> Option<Time> {
+    match word {
+        "noon" => return Time::try_from_hms(12, 0, 0).ok(),
+        "midnight" => return Some(Time::midnight()),
+        _ => {}
+    }
+    let (word, pm) = match (word.strip_suffix("am"), word.strip_suffix("pm")) {
+        (Some(word), _) => (word, Some(false)),
+        (_, Some(word)) => (word, Some(true)),
+        _ => (word, None),
+    };
+    let mut parts = word.splitn(3, ':');
+    let hour: u8 = parts.next()?.parse().ok()?;
+    let minute: u8 = parts.next().map_or(Some(0), |m| m.parse().ok())?;
+    let second: u8 = parts.next().map_or(Some(0), |s| s.parse().ok())?;
+    let hour = match pm {
+        None if word.contains(':') => hour,
+        None => return None,
+        Some(_) if hour == 0 || hour > 12 => return None,
+        Some(false) => hour % 12,
+        Some(true) => hour % 12 + 12,
+    };
+    Time::try_from_hms(hour, minute, second).ok()
+}
+
+/// `at` in the local time zone, with the offset in effect at that instant
+/// rather than the current one, e.g. on the other side of a DST change.
+pub fn to_local(at: OffsetDateTime) -> OffsetDateTime {
+    at.to_offset(UtcOffset::local_offset_at(at))
+}
+
+/// `time` on `date` in the local time zone, with the offset in effect then.
+/// A time skipped when the clocks go forward is moved past the gap, e.g.
+/// 2:30 becomes 3:30.
+pub fn local(date: Date, time: Time) -> OffsetDateTime {
+    let dt = PrimitiveDateTime::new(date, time);
+    // Guessed from the offset an hour or two off, which differs near a change
+    let mut at = dt.assume_offset(UtcOffset::local_offset_at(dt.assume_utc()));
+    let offset = UtcOffset::local_offset_at(at);
+    if offset != at.offset() {
+        at = dt.assume_offset(offset);
+    }
+    to_local(at)
+}
+
+/// Parses a point in time in local time: `17:30`, `yesterday 9am`,
+/// `monday at 14:00`, `2022-03-01 09:00`, `now`, `-2h` or `2h ago`.
+pub fn parse_time(s: &str) -> Result<OffsetDateTime> {
+    let invalid = || {
+        format!(
+            "Invalid time, expected e.g. 17:30, yesterday 9am, monday 14:00 or -2h: {}",
+            s
+        )
+    };
+    if let Some(time) = crate::schema::parse_time(s) {
+        return Ok(time);
+    }
+    let now = OffsetDateTime::now_local();
+    let lower = s.trim().to_lowercase();
+    if lower == "now" {
+        return Ok(now);
+    }
+    if let Some(ago) = lower
+        .strip_prefix('-')
+        .or_else(|| lower.strip_suffix("ago"))
+    {
+        return Ok(to_local(now - parse_duration(ago).with_context(invalid)?));
+    }
+    let words = lower
+        .split_whitespace()
+        .filter(|word| *word != "at")
+        .collect::<Vec<_>>();
+    let today = now.date();
+    let parsed = match words[..] {
+        [time] => clock(time).map(|time| local(today, time)),
+        [date, time] => day(date, today)
+            .zip(clock(time))
+            .map(|(date, time)| local(date, time)),
+        ["last", date, time] => weekday(date)
+            .and_then(|_| day(date, today.previous_day()))
+            .zip(clock(time))
+            .map(|(date, time)| local(date, time)),
+        _ => None,
+    };
+    parsed.ok_or_else(|| Error::msg(invalid()))
+}
+
+/// Parses a date: `YYYY-MM-DD`, `today`, `yesterday` or a weekday.
+pub fn parse_date(s: &str) -> Result<Date> {
+    day(&s.trim().to_lowercase(), OffsetDateTime::now_local().date()).ok_or_else(|| {
+        Error::msg(format!(
+            "Invalid date, expected YYYY-MM-DD, yesterday or a weekday: {}",
+            s
+        ))
+    })
+}
+
+/// Parses a month given as `YYYY-MM` into the year and month.
+pub fn parse_month(s: &str) -> Result<(i32, u8)> {
+    let invalid = || Error::msg(format!("Invalid month, expected YYYY-MM: {}", s));
+    let (year, month) = s.trim().split_once('-').ok_or_else(invalid)?;
+    let year = year.parse::<i32>().map_err(|_| invalid())?;
+    match month.parse::<u8>() {
+        Ok(month) if (1..=12).contains(&month) => Ok((year, month)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Parses a duration like `1h30m`, `45 min`, `1.5h` or `2 days`.
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let invalid = || Error::msg(format!("Invalid duration, expected e.g. 1h30m: {}", s));
+    let mut rest = s.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    let mut total = Duration::zero();
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let value: f64 = rest[..number_len].parse().map_err(|_| invalid())?;
+        rest = rest[number_len..].trim_start();
+        let unit_len = rest
+            .find(|c: char| !c.is_alphabetic())
+            .unwrap_or(rest.len());
+        let seconds = match &rest[..unit_len] {
+            "d" | "day" | "days" => 86400.0,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3600.0,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60.0,
+            "s" | "sec" | "secs" | "second" | "seconds" => 1.0,
+            _ => return Err(invalid()),
+        };
+        rest = rest[unit_len..].trim_start();
+        total += Duration::seconds_f64(value * seconds);
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u8, day: u8) -> Date {
+        Date::try_from_ymd(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn relative_days() {
+        // A Wednesday
+        let today = date(2024, 1, 3);
+        assert_eq!(day("today", today), Some(today));
+        assert_eq!(day("yesterday", today), Some(date(2024, 1, 2)));
+        assert_eq!(day("tomorrow", today), Some(date(2024, 1, 4)));
+        assert_eq!(day("2023-12-24", today), Some(date(2023, 12, 24)));
+    }
+
+    #[test]
+    fn weekdays_go_back_to_the_most_recent_one() {
+        let wednesday = date(2024, 1, 3);
+        assert_eq!(day("wednesday", wednesday), Some(wednesday));
+        assert_eq!(day("mon", wednesday), Some(date(2024, 1, 1)));
+        // Across the week and the year
+        assert_eq!(day("thursday", wednesday), Some(date(2023, 12, 28)));
+        assert_eq!(day("sun", wednesday), Some(date(2023, 12, 31)));
+        assert_eq!(weekday("mo"), None);
+        assert_eq!(weekday("fri"), Some(Weekday::Friday));
+    }
+
+    #[test]
+    fn clocks() {
+        let hms = |h, m, s| Time::try_from_hms(h, m, s).ok();
+        assert_eq!(clock("17:30"), hms(17, 30, 0));
+        assert_eq!(clock("17:30:15"), hms(17, 30, 15));
+        assert_eq!(clock("9am"), hms(9, 0, 0));
+        assert_eq!(clock("12am"), hms(0, 0, 0));
+        assert_eq!(clock("12pm"), hms(12, 0, 0));
+        assert_eq!(clock("9:30pm"), hms(21, 30, 0));
+        assert_eq!(clock("noon"), hms(12, 0, 0));
+        assert_eq!(clock("midnight"), hms(0, 0, 0));
+        assert_eq!(clock("9"), None);
+        assert_eq!(clock("13pm"), None);
+        assert_eq!(clock("25:00"), None);
+    }
+
+    #[test]
+    fn times() {
+        let now = OffsetDateTime::now_local();
+        let yesterday = parse_time("yesterday 9am").unwrap();
+        assert_eq!(yesterday.date(), now.date().previous_day());
+        assert_eq!(yesterday.time(), Time::try_from_hms(9, 0, 0).unwrap());
+        let monday = parse_time("Monday at 14:00").unwrap();
+        assert_eq!(monday.weekday(), Weekday::Monday);
+        assert!(monday.date() <= now.date());
+        let ago = parse_time("-2h").unwrap() - (now - Duration::hours(2));
+        assert!(ago >= Duration::zero() && ago < Duration::minutes(1));
+        let ago = parse_time("2h ago").unwrap() - (now - Duration::hours(2));
+        assert!(ago >= Duration::zero() && ago < Duration::minutes(1));
+        for invalid in ["", "9", "someday 9am", "yesterday", "-2x", "last tuesday"] {
+            assert!(parse_time(invalid).is_err(), "{:?} is invalid", invalid);
+        }
+    }
+
+    #[test]
+    fn dates_and_months() {
+        assert_eq!(parse_date(" 2024-02-29 ").unwrap(), date(2024, 2, 29));
+        assert!(parse_date("2023-02-29").is_err());
+        assert_eq!(parse_month("2024-03").unwrap(), (2024, 3));
+        assert!(parse_month("2024-13").is_err());
+        assert!(parse_month("2024").is_err());
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::minutes(90));
+        assert_eq!(parse_duration("45 min").unwrap(), Duration::minutes(45));
+        assert_eq!(parse_duration("1.5h").unwrap(), Duration::minutes(90));
+        assert_eq!(parse_duration("2 days").unwrap(), Duration::days(2));
+        assert_eq!(parse_duration("90s").unwrap(), Duration::seconds(90));
+        for invalid in ["", "1", "h", "1y", "1h 30"] {
+            assert!(parse_duration(invalid).is_err(), "{:?} is invalid", invalid);
+        }
+    }
+}
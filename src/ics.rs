@@ -0,0 +1,120 @@
+use time::{OffsetDateTime, PrimitiveDateTime, UtcOffset};
+
+use crate::{human, Tracker};
+
+const ICS_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// The identifier of a session's event, kept in the `uid` metadata once
+/// synchronized so it survives changes of the start. Before that it is
+/// derived from the start instant.
+pub fn uid(entry: &Tracker) -> String {
+    match entry.meta.get("uid") {
+        Some(uid) => uid.clone(),
+        None => format!("{}@track-work", entry.start.timestamp()),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("\\n", "\n")
+        .replace("\\N", "\n")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+fn utc(datetime: OffsetDateTime) -> String {
+    datetime.to_offset(UtcOffset::UTC).format(ICS_FORMAT)
+}
+
+/// Renders a finished session as VEVENT, its notes as DESCRIPTION if
+/// `notes` is set. Running sessions have no end and are skipped.
+pub fn vevent(entry: &Tracker, notes: bool) -> Option<String> {
+    let end = entry.end?;
+    let description = if !notes || entry.notes.is_empty() {
+        String::new()
+    } else {
+        format!("DESCRIPTION:{}\r\n", escape(&entry.notes))
+    };
+    Some(format!(
+        "BEGIN:VEVENT\r\nUID:{}\r\nDTSTAMP:{}\r\nDTSTART:{}\r\nDTEND:{}\r\nSUMMARY:{}\r\n{}END:VEVENT\r\n",
+        uid(entry),
+        utc(OffsetDateTime::now_utc()),
+        utc(entry.start),
+        utc(end),
+        escape(&entry.objective),
+        description
+    ))
+}
+
+pub fn calendar<'a>(events: impl Iterator<Item = &'a Tracker>, notes: bool) -> String {
+    let mut out = String::from("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//track-work//EN\r\n");
+    for event in events.filter_map(|entry| vevent(entry, notes)) {
+        out.push_str(&event);
+    }
+    out.push_str("END:VCALENDAR\r\n");
+    out
+}
+
+fn parse_datetime(value: &str) -> Option<OffsetDateTime> {
+    let (value, utc) = match value.strip_suffix('Z') {
+        Some(value) => (value, true),
+        None => (value, false),
+    };
+    let datetime = PrimitiveDateTime::parse(value, "%Y%m%dT%H%M%S").ok()?;
+    if utc {
+        Some(human::to_local(datetime.assume_utc()))
+    } else {
+        Some(human::local(datetime.date(), datetime.time()))
+    }
+}
+
+/// Extracts all timed VEVENTs from iCalendar data, their UID as `uid`
+/// metadata. All-day events and events without an end are ignored.
+pub fn parse_events(data: &str) -> Vec<Tracker> {
+    // Undo line folding (RFC 5545, 3.1) before looking at properties.
+    let unfolded = data.replace("\r\n ", "").replace("\n ", "");
+    let mut events = Vec::new();
+    type Event = (
+        Option<OffsetDateTime>,
+        Option<OffsetDateTime>,
+        String,
+        Option<String>,
+    );
+    let mut current: Option<Event> = None;
+    for line in unfolded.lines().map(str::trim_end) {
+        match line {
+            "BEGIN:VEVENT" => current = Some((None, None, String::new(), None)),
+            "END:VEVENT" => {
+                if let Some((Some(start), Some(end), objective, uid)) = current.take() {
+                    let mut event = Tracker::new(start, Some(end), objective);
+                    if let Some(uid) = uid {
+                        event.meta.insert("uid".to_string(), uid);
+                    }
+                    events.push(event);
+                }
+            }
+            _ => {
+                let (event, (name, value)) = match (&mut current, line.split_once(':')) {
+                    (Some(event), Some(property)) => (event, property),
+                    _ => continue,
+                };
+                let name = name.split(';').next().unwrap_or("");
+                match name {
+                    "DTSTART" => event.0 = parse_datetime(value),
+                    "DTEND" => event.1 = parse_datetime(value),
+                    "SUMMARY" => event.2 = unescape(value),
+                    "UID" => event.3 = Some(value.to_string()).filter(|uid| !uid.is_empty()),
+                    _ => {}
+                }
+            }
+        }
+    }
+    events
+}
@@ -0,0 +1,96 @@
+//! How long the user has been idle, i.e. without keyboard or mouse input.
+
+#[cfg(not(windows))]
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicI64, Ordering};
+
+use time::Duration;
+
+/// Minutes of idleness after which `live` pauses, 0 disables idle detection.
+pub static THRESHOLD: AtomicI64 = AtomicI64::new(0);
+
+pub fn threshold() -> Option<Duration> {
+    match THRESHOLD.load(Ordering::SeqCst) {
+        minutes if minutes > 0 => Some(Duration::minutes(minutes)),
+        _ => None,
+    }
+}
+
+#[cfg(not(windows))]
+fn output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
+/// `HIDIdleTime` of the HID system in nanoseconds.
+#[cfg(target_os = "macos")]
+pub fn idle_time() -> Option<Duration> {
+    let output = output("ioreg", &["-c", "IOHIDSystem", "-d", "4"])?;
+    let nanos = output
+        .lines()
+        .find(|line| line.contains("\"HIDIdleTime\""))?
+        .rsplit('=')
+        .next()?
+        .trim()
+        .parse::<i64>()
+        .ok()?;
+    Some(Duration::nanoseconds(nanos))
+}
+
+/// Asks `xprintidle` on X11, or the GNOME idle monitor on Wayland, for the
+/// idle time in milliseconds. None if neither is available.
+#[cfg(not(any(target_os = "macos", windows)))]
+pub fn idle_time() -> Option<Duration> {
+    let millis = output("xprintidle", &[])
+        .and_then(|output| output.trim().parse::<i64>().ok())
+        .or_else(|| {
+            // Prints e.g. `(uint64 12345,)`
+            let output = output(
+                "gdbus",
+                &[
+                    "call",
+                    "--session",
+                    "--dest",
+                    "org.gnome.Mutter.IdleMonitor",
+                    "--object-path",
+                    "/org/gnome/Mutter/IdleMonitor/Core",
+                    "--method",
+                    "org.gnome.Mutter.IdleMonitor.GetIdletime",
+                ],
+            )?;
+            output
+                .trim()
+                .trim_start_matches("(uint64 ")
+                .trim_end_matches(",)")
+                .parse::<i64>()
+                .ok()
+        })?;
+    Some(Duration::milliseconds(millis))
+}
+
+/// The time since the last input of the session.
+#[cfg(windows)]
+pub fn idle_time() -> Option<Duration> {
+    use winapi::um::sysinfoapi::GetTickCount;
+    use winapi::um::winuser::{GetLastInputInfo, LASTINPUTINFO};
+
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+    // SAFETY: info is initialized with its size as required.
+    if unsafe { GetLastInputInfo(&mut info) } == 0 {
+        return None;
+    }
+    // Both are milliseconds since boot, wrapping after 49 days
+    let millis = unsafe { GetTickCount() }.wrapping_sub(info.dwTime);
+    Some(Duration::milliseconds(millis.into()))
+}
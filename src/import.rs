@@ -0,0 +1,401 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{Context, Error, Result};
+use csv::ReaderBuilder;
+use time::{Date, Duration, Format as TimeFormat, OffsetDateTime, PrimitiveDateTime};
+
+use crate::config::Config;
+use crate::http::Request;
+use crate::json::{self, Value};
+use crate::{human, read, schema, write, Tracker};
+
+#[derive(Debug)]
+pub enum Format {
+    ActivityWatch,
+    WakaTime,
+    RescueTime,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "activitywatch" => Ok(Format::ActivityWatch),
+            "wakatime" => Ok(Format::WakaTime),
+            "rescuetime" => Ok(Format::RescueTime),
+            "csv" => Ok(Format::Csv),
+            _ => Err(Error::msg(format!("Unknown import format: {}", s))),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Options {
+    /// Sessions separated by less than this are merged into one
+    pub merge_threshold: Duration,
+    /// First day to fetch from online services
+    pub from: Date,
+    /// Last day to fetch from online services
+    pub to: Date,
+    /// Which columns of a generic CSV file hold which field
+    pub map: Option<ColumnMap>,
+    /// The format of timestamps in a generic CSV file
+    pub time_format: String,
+    pub delimiter: u8,
+    pub has_headers: bool,
+}
+
+/// A column of a generic CSV file, either by 1-based position or by header.
+#[derive(Debug)]
+enum Column {
+    Index(usize),
+    Name(String),
+}
+
+impl Column {
+    fn resolve(&self, headers: Option<&csv::StringRecord>) -> Result<usize> {
+        match self {
+            Column::Index(idx) => Ok(*idx),
+            Column::Name(name) => headers
+                .and_then(|h| h.iter().position(|h| h.trim() == name))
+                .ok_or_else(|| Error::msg(format!("No column named `{}`", name))),
+        }
+    }
+}
+
+/// Maps the fields of an entry to CSV columns, e.g. `start=2,end=3,objective=5`.
+#[derive(Debug)]
+pub struct ColumnMap {
+    start: Column,
+    end: Column,
+    objective: Option<Column>,
+}
+
+impl FromStr for ColumnMap {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut columns = HashMap::new();
+        for part in s.split(',') {
+            let (field, column) = part
+                .split_once('=')
+                .ok_or_else(|| Error::msg(format!("Expected field=column, got `{}`", part)))?;
+            let column = match column.trim().parse::<usize>() {
+                Ok(0) => return Err(Error::msg("Column numbers start at 1")),
+                Ok(idx) => Column::Index(idx - 1),
+                Err(_) => Column::Name(column.trim().to_string()),
+            };
+            match field.trim() {
+                field @ "start" | field @ "end" | field @ "objective" => {
+                    columns.insert(field, column);
+                }
+                field => return Err(Error::msg(format!("Unknown field: {}", field))),
+            }
+        }
+        Ok(ColumnMap {
+            start: columns
+                .remove("start")
+                .ok_or_else(|| Error::msg("The column map needs a start column"))?,
+            end: columns
+                .remove("end")
+                .ok_or_else(|| Error::msg("The column map needs an end column"))?,
+            objective: columns.remove("objective"),
+        })
+    }
+}
+
+fn require_file(file: Option<&Path>) -> Result<&Path> {
+    file.ok_or_else(|| Error::msg("This import format requires a file"))
+}
+
+/// Imports sessions from another tracker's export. Sessions overlapping an
+/// already tracked entry are skipped, so importing is safe to repeat.
+pub fn import(
+    path: &PathBuf,
+    config: &Config,
+    format: &Format,
+    file: Option<&Path>,
+    options: &Options,
+) -> Result<()> {
+    let candidates = match format {
+        Format::ActivityWatch => activitywatch(require_file(file)?, options)?,
+        Format::WakaTime => wakatime(config, options)?,
+        Format::RescueTime => rescuetime(require_file(file)?)?,
+        Format::Csv => generic_csv(require_file(file)?, options)?,
+    };
+    let mut data = read(path)?;
+    let mut imported = 0;
+    for candidate in candidates {
+        if data.iter().any(|entry| entry.overlaps(&candidate)) {
+            continue;
+        }
+        println!("Imported: {}", candidate);
+        data.push(candidate);
+        imported += 1;
+    }
+    data.sort_by_key(|entry| entry.start);
+    write(path, &data)?;
+    println!("Imported {} sessions", imported);
+    Ok(())
+}
+
+/// Reads a `timestamp`/`duration` pair of an ActivityWatch event.
+fn aw_interval(event: &Value) -> Option<(OffsetDateTime, OffsetDateTime)> {
+    let start =
+        OffsetDateTime::parse(event.get("timestamp")?.as_str()?, TimeFormat::Rfc3339).ok()?;
+    let duration = Duration::seconds_f64(event.get("duration")?.as_f64()?);
+    Some((human::to_local(start), human::to_local(start + duration)))
+}
+
+/// Converts an ActivityWatch bucket export into sessions: the non-AFK
+/// periods make up the sessions, the application used the longest within
+/// a session becomes its objective.
+fn activitywatch(file: &Path, options: &Options) -> Result<Vec<Tracker>> {
+    let content = fs::read_to_string(file)
+        .with_context(|| format!("Could not read import file: {}", file.display()))?;
+    let export = json::parse(&content)?;
+    let buckets = export
+        .get("buckets")
+        .and_then(Value::as_object)
+        .ok_or_else(|| Error::msg("Not an ActivityWatch export: missing buckets"))?;
+
+    let mut active = Vec::new();
+    let mut windows = Vec::new();
+    for bucket in buckets.values() {
+        let events = bucket
+            .get("events")
+            .and_then(Value::as_array)
+            .unwrap_or(&[]);
+        match bucket.get("type").and_then(Value::as_str) {
+            Some("afkstatus") => active.extend(
+                events
+                    .iter()
+                    .filter(|e| {
+                        e.get("data")
+                            .and_then(|d| d.get("status"))
+                            .and_then(Value::as_str)
+                            == Some("not-afk")
+                    })
+                    .filter_map(aw_interval),
+            ),
+            Some("currentwindow") => windows.extend(events.iter().filter_map(|e| {
+                let app = e.get("data")?.get("app")?.as_str()?.to_string();
+                aw_interval(e).map(|interval| (interval, app))
+            })),
+            _ => {}
+        }
+    }
+    if active.is_empty() {
+        return Err(Error::msg(
+            "The export contains no AFK events to derive sessions from",
+        ));
+    }
+
+    active.sort();
+    let mut sessions: Vec<(OffsetDateTime, OffsetDateTime)> = Vec::new();
+    for (start, end) in active {
+        match sessions.last_mut() {
+            Some(last) if start - last.1 < options.merge_threshold => last.1 = last.1.max(end),
+            _ => sessions.push((start, end)),
+        }
+    }
+
+    Ok(sessions
+        .into_iter()
+        .map(|(start, end)| {
+            let mut usage: HashMap<&str, Duration> = HashMap::new();
+            for ((w_start, w_end), app) in &windows {
+                let overlap = end.min(*w_end) - start.max(*w_start);
+                if overlap.is_positive() {
+                    *usage.entry(app).or_insert_with(Duration::zero) += overlap;
+                }
+            }
+            let objective = usage
+                .into_iter()
+                .max_by_key(|(_, duration)| *duration)
+                .map(|(app, _)| app.to_string())
+                .unwrap_or_default();
+            Tracker::new(start, Some(end), objective)
+        })
+        .collect())
+}
+
+/// Fetches the coding durations recorded by WakaTime for every day in the
+/// requested range. Sessions are named `coding/<project>`.
+fn wakatime(config: &Config, options: &Options) -> Result<Vec<Tracker>> {
+    let api_key = config.require("wakatime", "api_key")?;
+    let base = config
+        .get("wakatime", "url")
+        .unwrap_or("https://wakatime.com/api/v1");
+    let mut sessions: Vec<Tracker> = Vec::new();
+    let mut date = options.from;
+    while date <= options.to {
+        let url = format!(
+            "{}/users/current/durations?date={}",
+            base.trim_end_matches('/'),
+            date.format("%F")
+        );
+        let response = Request::new("GET", url)
+            .basic_auth(api_key, "")
+            .send()?
+            .error_for_status()?;
+        let durations = json::parse(&response.text())?;
+        for duration in durations
+            .get("data")
+            .and_then(Value::as_array)
+            .unwrap_or(&[])
+        {
+            let interval = duration
+                .get("time")
+                .and_then(Value::as_f64)
+                .and_then(|time| {
+                    let start = OffsetDateTime::from_unix_timestamp(time as i64);
+                    let length = Duration::seconds_f64(duration.get("duration")?.as_f64()?);
+                    Some((human::to_local(start), human::to_local(start + length)))
+                });
+            let (start, end) = match interval {
+                Some(interval) => interval,
+                None => continue,
+            };
+            let project = duration
+                .get("project")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown");
+            let objective = format!("coding/{}", project);
+            match sessions.last_mut() {
+                Some(last)
+                    if last.objective == objective
+                        && last
+                            .end
+                            .is_some_and(|e| start - e < options.merge_threshold) =>
+                {
+                    last.end = last.end.max(Some(end));
+                }
+                _ => sessions.push(Tracker::new(start, Some(end), objective)),
+            }
+        }
+        date = date.next_day();
+    }
+    Ok(sessions)
+}
+
+fn parse_local(s: &str) -> Option<PrimitiveDateTime> {
+    PrimitiveDateTime::parse(s, "%Y-%m-%dT%H:%M:%S")
+        .or_else(|_| PrimitiveDateTime::parse(s, "%F %T"))
+        .or_else(|_| Date::parse(s, "%F").map(|d| d.midnight() + Duration::hours(9)))
+        .ok()
+}
+
+/// Aggregates a RescueTime CSV export into one entry per day and category.
+/// RescueTime only reports time spent, so the entries of a day are placed
+/// back to back starting at the earliest recorded time of that day.
+fn rescuetime(file: &Path) -> Result<Vec<Tracker>> {
+    let mut rdr = ReaderBuilder::new()
+        .has_headers(true)
+        .from_path(file)
+        .with_context(|| format!("Could not read import file: {}", file.display()))?;
+    let headers = rdr.headers()?.clone();
+    let column = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| headers.iter().position(|h| h.trim() == *name))
+            .ok_or_else(|| Error::msg(format!("Missing RescueTime column: {}", names[0])))
+    };
+    let date_col = column(&["Date"])?;
+    let seconds_col = column(&["Time Spent (seconds)", "Time Spent"])?;
+    let category_col = column(&["Category", "Activity"])?;
+
+    let mut days: BTreeMap<Date, (PrimitiveDateTime, BTreeMap<String, i64>)> = BTreeMap::new();
+    for (line, record) in rdr.records().enumerate() {
+        let record = record?;
+        let invalid = || Error::msg(format!("Invalid RescueTime row {}: {:?}", line + 2, record));
+        let datetime = record
+            .get(date_col)
+            .and_then(parse_local)
+            .ok_or_else(invalid)?;
+        let seconds: i64 = record
+            .get(seconds_col)
+            .and_then(|s| s.trim().parse().ok())
+            .ok_or_else(invalid)?;
+        let category = record.get(category_col).unwrap_or("").to_string();
+        let day = days
+            .entry(datetime.date())
+            .or_insert_with(|| (datetime, BTreeMap::new()));
+        day.0 = day.0.min(datetime);
+        *day.1.entry(category).or_insert(0) += seconds;
+    }
+
+    let mut entries = Vec::new();
+    for (_, (first, categories)) in days {
+        let mut start = human::local(first.date(), first.time());
+        for (objective, seconds) in categories {
+            let end = start + Duration::seconds(seconds);
+            entries.push(Tracker::new(start, Some(end), objective));
+            start = end;
+        }
+    }
+    Ok(entries)
+}
+
+/// Parses `s` in `format`, local time if it has no offset, or else in the
+/// formats of track-work's own storage files.
+fn parse_with(s: &str, format: &str) -> Option<OffsetDateTime> {
+    OffsetDateTime::parse(s, format)
+        .or_else(|_| {
+            PrimitiveDateTime::parse(s, format).map(|dt| human::local(dt.date(), dt.time()))
+        })
+        .ok()
+        .or_else(|| schema::parse_time(s))
+}
+
+/// Reads entries from an arbitrary CSV file using the column map and time
+/// format given on the command line.
+fn generic_csv(file: &Path, options: &Options) -> Result<Vec<Tracker>> {
+    let map = options
+        .map
+        .as_ref()
+        .ok_or_else(|| Error::msg("Importing CSV requires a column map, see --map"))?;
+    let mut rdr = ReaderBuilder::new()
+        .has_headers(options.has_headers)
+        .delimiter(options.delimiter)
+        .flexible(true)
+        .from_path(file)
+        .with_context(|| format!("Could not read import file: {}", file.display()))?;
+    let headers = if options.has_headers {
+        Some(rdr.headers()?.clone())
+    } else {
+        None
+    };
+    let start_col = map.start.resolve(headers.as_ref())?;
+    let end_col = map.end.resolve(headers.as_ref())?;
+    let objective_col = map
+        .objective
+        .as_ref()
+        .map(|c| c.resolve(headers.as_ref()))
+        .transpose()?;
+
+    let mut entries = Vec::new();
+    for (line, record) in rdr.records().enumerate() {
+        let record = record?;
+        let line = line + if options.has_headers { 2 } else { 1 };
+        let start = record
+            .get(start_col)
+            .and_then(|s| parse_with(s.trim(), &options.time_format))
+            .ok_or_else(|| Error::msg(format!("Invalid start in row {}: {:?}", line, record)))?;
+        let end = record
+            .get(end_col)
+            .and_then(|s| parse_with(s.trim(), &options.time_format))
+            .ok_or_else(|| Error::msg(format!("Invalid end in row {}: {:?}", line, record)))?;
+        let objective = objective_col
+            .and_then(|c| record.get(c))
+            .unwrap_or("")
+            .to_string();
+        entries.push(Tracker::new(start, Some(end), objective));
+    }
+    Ok(entries)
+}
@@ -0,0 +1,174 @@
+//! `init`, the first-time setup: asks for the storage file, git and the
+//! targets, writes the config file and the shell completions.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Error, Result};
+use console::Term;
+use structopt::clap::Shell;
+use structopt::StructOpt;
+
+use crate::{config, human};
+
+/// `$XDG_DATA_HOME/track-work/work.csv` falling back to
+/// `%APPDATA%\track-work\work.csv` on Windows and
+/// `~/.local/share/track-work/work.csv` elsewhere.
+fn default_file() -> PathBuf {
+    let base = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(config::app_data)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share"))
+        })
+        .unwrap_or_else(|| PathBuf::from("."));
+    base.join("track-work").join("work.csv")
+}
+
+/// Asks `question`, an empty answer is `default`.
+fn ask(term: &Term, question: &str, default: &str) -> Result<String> {
+    if default.is_empty() {
+        term.write_str(&format!("{}: ", question))?;
+    } else {
+        term.write_str(&format!("{} [{}]: ", question, default))?;
+    }
+    let answer = term.read_line()?.trim().to_string();
+    Ok(if answer.is_empty() {
+        default.to_string()
+    } else {
+        answer
+    })
+}
+
+/// Asks for a duration like 8h until it can be parsed, empty for none.
+fn ask_target(term: &Term, question: &str) -> Result<Option<String>> {
+    loop {
+        let answer = ask(term, question, "")?;
+        if answer.is_empty() {
+            return Ok(None);
+        }
+        match human::parse_duration(&answer) {
+            Ok(_) => return Ok(Some(answer)),
+            Err(e) => term.write_line(&format!("{}", e))?,
+        }
+    }
+}
+
+/// Quotes `s` as a config value.
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Asks the setup questions on the terminal and writes `config_path`, an
+/// existing one only with `force`.
+pub fn run(config_path: &Path, force: bool) -> Result<()> {
+    if config_path.exists() && !force {
+        return Err(Error::msg(format!(
+            "{} already exists, pass --force to replace it",
+            config_path.display()
+        )));
+    }
+    let term = Term::stdout();
+    if !term.features().is_attended() {
+        return Err(Error::msg("init asks its questions on a terminal"));
+    }
+    let file = ask(&term, "Storage file", &default_file().to_string_lossy())?;
+    let git = ask(&term, "Commit every change to git (y/n)", "n")?;
+    let mut config = format!(
+        "# Written by track-work init, see the README for all sections\n\n[storage]\nfile = {}\n",
+        quote(&file)
+    );
+    if git.starts_with('y') {
+        config += "\n[git]\nenabled = true\n";
+    }
+    let mut targets = String::new();
+    for (key, label) in [
+        ("daily", "Daily"),
+        ("weekly", "Weekly"),
+        ("monthly", "Monthly"),
+    ] {
+        if let Some(target) =
+            ask_target(&term, &format!("{} target, e.g. 8h, empty for none", label))?
+        {
+            targets += &format!("{} = {}\n", key, quote(&target));
+        }
+    }
+    if !targets.is_empty() {
+        config += &format!("\n[targets]\n{}", targets);
+    }
+    if let Some(dir) = config_path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(config_path, config)
+        .with_context(|| format!("Could not write {}", config_path.display()))?;
+    if let Some(dir) = Path::new(&file)
+        .parent()
+        .filter(|d| !d.as_os_str().is_empty())
+    {
+        fs::create_dir_all(dir)?;
+    }
+    println!("Wrote {}", config_path.display());
+    completions(config_path)
+}
+
+/// Writes the completions of the shell in `$SHELL`, PowerShell on Windows,
+/// next to the config file and prints how to load them, together with a
+/// prompt showing whether a session is running.
+fn completions(config_path: &Path) -> Result<()> {
+    let shell = std::env::var("SHELL").unwrap_or_else(|_| {
+        if cfg!(windows) {
+            "powershell".to_string()
+        } else {
+            String::new()
+        }
+    });
+    let shell = shell
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches(".exe");
+    let parsed = match shell.parse::<Shell>() {
+        Ok(parsed) => parsed,
+        Err(_) => {
+            println!("No completions for the shell {:?}", shell);
+            return Ok(());
+        }
+    };
+    let dir = config_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join("completions");
+    fs::create_dir_all(&dir)?;
+    crate::Opt::clap().gen_completions("track-work", parsed, &dir);
+    let script = dir.join(match parsed {
+        Shell::Bash => "track-work.bash",
+        Shell::Zsh => "_track-work",
+        Shell::Fish => "track-work.fish",
+        Shell::PowerShell => "_track-work.ps1",
+        Shell::Elvish => "track-work.elv",
+    });
+    println!("Wrote the {} completions to {}", shell, script.display());
+    let prompt = "$(track-work status -q && echo \"[tracking] \")";
+    match parsed {
+        Shell::Bash => {
+            println!("Add to your ~/.bashrc:");
+            println!("  source {}", script.display());
+            println!("  PS1='{}'\"$PS1\"", prompt);
+        }
+        Shell::Zsh => {
+            println!("Add to your ~/.zshrc, before compinit:");
+            println!("  fpath=({} $fpath)", dir.display());
+            println!("  setopt PROMPT_SUBST; PS1='{}'\"$PS1\"", prompt);
+        }
+        Shell::Fish => {
+            println!("Copy it to ~/.config/fish/completions/, for the prompt add to fish_prompt:");
+            println!("  track-work status -q; and echo -n '[tracking] '");
+        }
+        Shell::PowerShell => {
+            println!("Add to your $PROFILE:");
+            println!("  . '{}'", script.display());
+        }
+        _ => {}
+    }
+    Ok(())
+}
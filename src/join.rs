@@ -0,0 +1,116 @@
+//! Combines back-to-back sessions into one, e.g. after lots of accidental
+//! stops and starts.
+
+use std::path::PathBuf;
+
+use anyhow::{Error, Result};
+use time::Duration;
+
+use crate::{check_overlaps, read, write, Tracker};
+
+/// One session from `first` to `second`, which starts later. The objective
+/// and metadata of `first` win, the notes are kept from both.
+pub fn combine(first: Tracker, second: Tracker) -> Tracker {
+    let mut joined = first;
+    joined.end = match (joined.end, second.end) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        _ => None,
+    };
+    if !second.notes.is_empty() && !joined.notes.contains(&second.notes) {
+        if !joined.notes.is_empty() {
+            joined.notes.push('\n');
+        }
+        joined.notes.push_str(&second.notes);
+    }
+    for (key, value) in second.meta {
+        joined.meta.entry(key).or_insert(value);
+    }
+    joined
+}
+
+/// Joins the `a`th and `b`th entry counted back from the last one.
+pub fn join(
+    path: &PathBuf,
+    a: usize,
+    b: usize,
+    objective: Option<String>,
+    force: bool,
+) -> Result<()> {
+    let mut data = read(path)?;
+    let index = |n: usize| {
+        data.len()
+            .checked_sub(n)
+            .filter(|_| n > 0)
+            .ok_or_else(|| Error::msg(format!("There is no entry {}", n)))
+    };
+    let (a, b) = (index(a)?, index(b)?);
+    if a == b {
+        return Err(Error::msg("Give two different entries to join"));
+    }
+    if data[a].timer != data[b].timer {
+        return Err(Error::msg("Can't join entries of different timers"));
+    }
+    // Remove the later index first so the other stays valid
+    let (second, first) = if a > b {
+        (data.remove(a), data.remove(b))
+    } else {
+        (data.remove(b), data.remove(a))
+    };
+    let (first, second) = if first.start <= second.start {
+        (first, second)
+    } else {
+        (second, first)
+    };
+    println!("Joined: {}", first);
+    println!("   and: {}", second);
+    let mut joined = combine(first, second);
+    if let Some(objective) = objective {
+        joined.objective = objective;
+    }
+    check_overlaps(&data, &joined, force)?;
+    println!("  into: {}", joined);
+    data.push(joined);
+    data.sort_by_key(|entry| entry.start);
+    write(path, &data)
+}
+
+/// Joins all consecutive sessions of a timer separated by less than `gap`,
+/// with `same_objective` only those with equal objectives.
+pub fn join_gaps(path: &PathBuf, gap: Duration, same_objective: bool) -> Result<()> {
+    let mut data = read(path)?;
+    data.sort_by_key(|entry| entry.start);
+    let mut joined: Vec<Tracker> = Vec::with_capacity(data.len());
+    let mut count = 0;
+    for entry in data {
+        // The previous session of the same timer
+        let previous = joined
+            .iter()
+            .rposition(|previous| previous.timer == entry.timer);
+        let target = previous.filter(|&index| {
+            let previous = &joined[index];
+            previous
+                .end
+                .is_some_and(|end| end <= entry.start && entry.start - end < gap)
+                && (!same_objective || previous.objective == entry.objective)
+        });
+        match target {
+            Some(index) => {
+                println!("Joined: {}", joined[index]);
+                println!("   and: {}", entry);
+                joined[index] = combine(joined[index].clone(), entry);
+                count += 1;
+            }
+            None => joined.push(entry),
+        }
+    }
+    if count > 0 {
+        crate::confirm(&format!("Join {} sessions?", count))?;
+        write(path, &joined)?;
+    }
+    if crate::dry_run() {
+        println!("Would join {} sessions", count);
+    } else {
+        println!("Joined {} sessions", count);
+    }
+    Ok(())
+}
@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use anyhow::{Error, Result};
+use csv::{ReaderBuilder, StringRecord, WriterBuilder};
+use time::OffsetDateTime;
+
+use crate::{crypt, git, lock, read, store, tmp_path, Tracker};
+
+/// How many operations `undo` can go back.
+const MAX_OPERATIONS: usize = 50;
+
+/// The subcommand the changes are journaled for, like `stop`.
+static COMMAND: OnceLock<String> = OnceLock::new();
+
+/// Sets the subcommand name changes are recorded and described with.
+pub fn set_command(name: &str) {
+    let _ = COMMAND.set(name.to_string());
+}
+
+fn command() -> &'static str {
+    COMMAND.get().map_or("", String::as_str)
+}
+
+/// A change to the storage file: the rows it removed and the rows it added.
+struct Operation {
+    time: String,
+    command: String,
+    removed: Vec<Vec<String>>,
+    added: Vec<Vec<String>>,
+}
+
+fn journal_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".journal");
+    path.with_file_name(name)
+}
+
+fn rows(path: &Path) -> Result<Vec<Vec<String>>> {
+    Ok(read(&path.to_path_buf())?
+        .iter()
+        .map(Tracker::to_record)
+        .collect())
+}
+
+/// Rows of `from` that are missing in `to`, respecting duplicates.
+fn difference(from: &[Vec<String>], to: &[Vec<String>]) -> Vec<Vec<String>> {
+    let mut remaining: HashMap<&Vec<String>, usize> = HashMap::new();
+    for row in to {
+        *remaining.entry(row).or_insert(0) += 1;
+    }
+    from.iter()
+        .filter(|row| match remaining.get_mut(row) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                false
+            }
+            _ => true,
+        })
+        .cloned()
+        .collect()
+}
+
+// The journal is a flexible CSV file: sequence number, time, command line,
+// `-`/`+` and the fields of the removed/added row.
+fn load(path: &Path) -> Result<Vec<Operation>> {
+    let path = journal_path(path);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let mut rdr = ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_path(&path)?;
+    let mut operations: Vec<(String, Operation)> = Vec::new();
+    for record in rdr.records() {
+        let record = record?;
+        let field = |i| record.get(i).unwrap_or("").to_string();
+        let seq = field(0);
+        if operations.last().map(|(s, _)| s) != Some(&seq) {
+            operations.push((
+                seq.clone(),
+                Operation {
+                    time: field(1),
+                    command: field(2),
+                    removed: Vec::new(),
+                    added: Vec::new(),
+                },
+            ));
+        }
+        let operation = &mut operations.last_mut().expect("just pushed").1;
+        let row = record.iter().skip(4).map(String::from).collect();
+        match record.get(3) {
+            Some("-") => operation.removed.push(row),
+            Some("+") => operation.added.push(row),
+            _ => {}
+        }
+    }
+    Ok(operations.into_iter().map(|(_, op)| op).collect())
+}
+
+fn save(path: &Path, operations: &[Operation]) -> Result<()> {
+    let path = journal_path(path);
+    let tmp = tmp_path(&path);
+    let mut writer = WriterBuilder::new().flexible(true).from_path(&tmp)?;
+    let skip = operations.len().saturating_sub(MAX_OPERATIONS);
+    for (seq, operation) in operations.iter().enumerate().skip(skip) {
+        let seq = seq.to_string();
+        let changes = operation
+            .removed
+            .iter()
+            .map(|row| ("-", row))
+            .chain(operation.added.iter().map(|row| ("+", row)));
+        for (kind, row) in changes {
+            let mut record = vec![
+                seq.as_str(),
+                operation.time.as_str(),
+                operation.command.as_str(),
+                kind,
+            ];
+            record.extend(row.iter().map(String::as_str));
+            writer.write_record(&record)?;
+        }
+    }
+    writer.flush()?;
+    drop(writer);
+    fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+/// The rows of the storage file before a change, passed to `record` once
+/// the change is stored. `None` for encrypted files: the journal would store
+/// the rows in plain text, so they can only be rolled back with backups.
+pub fn snapshot(path: &Path) -> Result<Option<Vec<Vec<String>>>> {
+    if crypt::enabled() {
+        return Ok(None);
+    }
+    rows(path).map(Some)
+}
+
+/// Records the rows changed from the `snapshot` to `data`, after `data` was
+/// written to the storage file, and returns a short description of the
+/// change, if there was any.
+pub fn record(
+    path: &Path,
+    snapshot: Option<Vec<Vec<String>>>,
+    data: &[Tracker],
+) -> Result<Option<String>> {
+    let old = match snapshot {
+        Some(old) => old,
+        None => return Ok(Some("update".into())),
+    };
+    let new = data.iter().map(Tracker::to_record).collect::<Vec<_>>();
+    record_change(path, difference(&old, &new), difference(&new, &old))
+}
+
+/// Records a change whose removed and added rows are already known.
+pub fn record_change(
+    path: &Path,
+    removed: Vec<Vec<String>>,
+    added: Vec<Vec<String>>,
+) -> Result<Option<String>> {
+    if removed.is_empty() && added.is_empty() {
+        return Ok(None);
+    }
+    let description = describe(&removed, &added);
+    let mut operations = load(path)?;
+    operations.push(Operation {
+        time: OffsetDateTime::now_local().format("%F %T"),
+        command: command().to_string(),
+        removed,
+        added,
+    });
+    save(path, &operations)?;
+    Ok(Some(description))
+}
+
+/// Summarizes a change, e.g. `stop: 2h10m review` for a stopped session.
+fn describe(removed: &[Vec<String>], added: &[Vec<String>]) -> String {
+    let entries = |rows: &[Vec<String>]| {
+        rows.iter()
+            .filter_map(|row| Tracker::try_from(StringRecord::from(row.clone())).ok())
+            .collect::<Vec<_>>()
+    };
+    match (&entries(removed)[..], &entries(added)[..]) {
+        ([], [new]) if new.end.is_none() => format!("start: {}", new.objective),
+        ([old], [new]) if old.end.is_none() && new.start == old.start => match new.end {
+            Some(end) => {
+                let duration = end - new.start;
+                format!(
+                    "stop: {}h{:02}m {}",
+                    duration.whole_hours(),
+                    duration.whole_minutes() % 60,
+                    new.objective
+                )
+            }
+            None => format!("update: {}", new.objective),
+        },
+        _ => format!("{}: +{} -{} entries", command(), added.len(), removed.len()),
+    }
+    .trim_end()
+    .to_string()
+}
+
+/// Reverts the most recently journaled operation.
+pub fn undo(path: &PathBuf) -> Result<()> {
+    let mut operations = load(path)?;
+    let operation = operations
+        .pop()
+        .ok_or_else(|| Error::msg("Nothing to undo"))?;
+    // Entries are kept as read so that unknown columns survive, restored
+    // rows take them over from the row that replaced them.
+    let mut remaining: HashMap<Vec<String>, usize> = HashMap::new();
+    for row in &operation.added {
+        *remaining.entry(row.clone()).or_insert(0) += 1;
+    }
+    let current = read(path)?;
+    let (dropped, mut data): (Vec<Tracker>, Vec<Tracker>) =
+        current.clone().into_iter().partition(|entry| {
+            match remaining.get_mut(&entry.to_record()) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    true
+                }
+                _ => false,
+            }
+        });
+    for row in &operation.removed {
+        let mut entry = Tracker::try_from(StringRecord::from(row.clone()))?;
+        if let Some(replaced) = dropped.iter().find(|d| d.start == entry.start) {
+            entry.extra = replaced.extra.clone();
+        }
+        data.push(entry);
+    }
+    data.sort_by_key(|entry| entry.start);
+    lock::check(path, &current, &data)?;
+    if crate::dry_run() {
+        crate::print_diff(&current, &data);
+        return Ok(());
+    }
+    store(path, &data)?;
+    save(path, &operations)?;
+    git::commit(path, &format!("undo: {}", operation.command))?;
+    println!(
+        "Reverted `{}` from {} ({} removed, {} restored)",
+        operation.command,
+        operation.time,
+        operation.added.len(),
+        operation.removed.len()
+    );
+    Ok(())
+}
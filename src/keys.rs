@@ -0,0 +1,222 @@
+//! Key presses for `live`, read without waiting for enter. Ctrl-C still
+//! raises SIGINT since only echo and line buffering are turned off, the
+//! same goes for the console mode on Windows.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+
+/// Reads the bytes typed on the terminal until dropped, which restores the
+/// terminal settings.
+pub struct Keys {
+    receiver: Receiver<u8>,
+    // Kept so the channel never disconnects, e.g. when stdin isn't a terminal.
+    _sender: Sender<u8>,
+    done: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    #[cfg(unix)]
+    original: Option<libc::termios>,
+    /// The console input mode
+    #[cfg(windows)]
+    original: Option<winapi::shared::minwindef::DWORD>,
+}
+
+impl Keys {
+    pub fn receiver(&self) -> &Receiver<u8> {
+        &self.receiver
+    }
+
+    #[cfg(unix)]
+    pub fn start() -> Keys {
+        let (sender, receiver) = unbounded();
+        let done = Arc::new(AtomicBool::new(false));
+        // SAFETY: termios is plain old data, tcgetattr fills it in and it
+        // is only used if that succeeded.
+        let original = unsafe {
+            let mut termios: libc::termios = std::mem::zeroed();
+            if libc::isatty(libc::STDIN_FILENO) == 1
+                && libc::tcgetattr(libc::STDIN_FILENO, &mut termios) == 0
+            {
+                Some(termios)
+            } else {
+                None
+            }
+        };
+        let thread = original.map(|original| {
+            let mut raw = original;
+            raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+            raw.c_cc[libc::VMIN] = 1;
+            raw.c_cc[libc::VTIME] = 0;
+            // SAFETY: raw is a valid termios derived from the current one.
+            unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) };
+            let sender = sender.clone();
+            let done = done.clone();
+            std::thread::spawn(move || read_keys(sender, done))
+        });
+        Keys {
+            receiver,
+            _sender: sender,
+            done,
+            thread,
+            original,
+        }
+    }
+
+    #[cfg(windows)]
+    pub fn start() -> Keys {
+        use winapi::um::consoleapi::{GetConsoleMode, SetConsoleMode};
+        use winapi::um::wincon::{ENABLE_ECHO_INPUT, ENABLE_LINE_INPUT};
+
+        let (sender, receiver) = unbounded();
+        let done = Arc::new(AtomicBool::new(false));
+        let console = console_input().and_then(|input| {
+            let mut mode = 0;
+            // SAFETY: GetConsoleMode only writes the mode, it fails if the
+            // input isn't a console, e.g. redirected.
+            if unsafe { GetConsoleMode(input, &mut mode) } != 0 {
+                Some((input, mode))
+            } else {
+                None
+            }
+        });
+        let original = console.map(|(_, mode)| mode);
+        let thread = console.map(|(input, mode)| {
+            // SAFETY: the handle was checked to be a console by GetConsoleMode.
+            unsafe { SetConsoleMode(input, mode & !(ENABLE_LINE_INPUT | ENABLE_ECHO_INPUT)) };
+            let sender = sender.clone();
+            let done = done.clone();
+            std::thread::spawn(move || read_keys(sender, done))
+        });
+        Keys {
+            receiver,
+            _sender: sender,
+            done,
+            thread,
+            original,
+        }
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    pub fn start() -> Keys {
+        let (sender, receiver) = unbounded();
+        Keys {
+            receiver,
+            _sender: sender,
+            done: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        }
+    }
+}
+
+/// Polls stdin so the thread notices when it should stop, a blocking read
+/// would swallow input meant for whatever reads the terminal next.
+#[cfg(unix)]
+fn read_keys(sender: Sender<u8>, done: Arc<AtomicBool>) {
+    while !done.load(Ordering::SeqCst) {
+        let mut poll = libc::pollfd {
+            fd: libc::STDIN_FILENO,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: poll gets a single valid pollfd.
+        if unsafe { libc::poll(&mut poll, 1, 100) } <= 0 {
+            continue;
+        }
+        let mut byte = 0u8;
+        // SAFETY: the buffer is valid for the single byte read.
+        let read = unsafe { libc::read(libc::STDIN_FILENO, &mut byte as *mut u8 as *mut _, 1) };
+        if read <= 0 || sender.send(byte).is_err() {
+            return;
+        }
+    }
+}
+
+/// The console input handle, None if there is no console.
+#[cfg(windows)]
+fn console_input() -> Option<winapi::um::winnt::HANDLE> {
+    use winapi::um::handleapi::INVALID_HANDLE_VALUE;
+    use winapi::um::processenv::GetStdHandle;
+    use winapi::um::winbase::STD_INPUT_HANDLE;
+
+    // SAFETY: GetStdHandle has no preconditions.
+    let handle = unsafe { GetStdHandle(STD_INPUT_HANDLE) };
+    if handle.is_null() || handle == INVALID_HANDLE_VALUE {
+        None
+    } else {
+        Some(handle)
+    }
+}
+
+/// Waits for console input events with a timeout, like `poll` above, and
+/// sends the typed characters as UTF-8.
+#[cfg(windows)]
+fn read_keys(sender: Sender<u8>, done: Arc<AtomicBool>) {
+    use winapi::um::consoleapi::ReadConsoleInputW;
+    use winapi::um::synchapi::WaitForSingleObject;
+    use winapi::um::winbase::WAIT_OBJECT_0;
+    use winapi::um::wincontypes::{INPUT_RECORD, KEY_EVENT};
+
+    let input = match console_input() {
+        Some(input) => input,
+        None => return,
+    };
+    while !done.load(Ordering::SeqCst) {
+        // SAFETY: the handle is the console input checked in `start`.
+        if unsafe { WaitForSingleObject(input, 100) } != WAIT_OBJECT_0 {
+            continue;
+        }
+        // SAFETY: INPUT_RECORD is plain old data, ReadConsoleInputW fills
+        // in at most the single record it gets and the count.
+        let (record, read) = unsafe {
+            let mut record: INPUT_RECORD = std::mem::zeroed();
+            let mut read = 0;
+            if ReadConsoleInputW(input, &mut record, 1, &mut read) == 0 {
+                return;
+            }
+            (record, read)
+        };
+        if read == 0 || record.EventType != KEY_EVENT {
+            continue;
+        }
+        // SAFETY: the record is a key event as checked by its type.
+        let (down, unit) = unsafe {
+            let key = record.Event.KeyEvent();
+            (key.bKeyDown != 0, *key.uChar.UnicodeChar())
+        };
+        // Key releases, and presses like shift that type nothing
+        if !down || unit == 0 {
+            continue;
+        }
+        let typed = char::decode_utf16(Some(unit))
+            .next()
+            .and_then(Result::ok)
+            .unwrap_or(char::REPLACEMENT_CHARACTER);
+        let mut buffer = [0u8; 4];
+        for byte in typed.encode_utf8(&mut buffer).bytes() {
+            if sender.send(byte).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+impl Drop for Keys {
+    fn drop(&mut self) {
+        self.done.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        #[cfg(unix)]
+        if let Some(original) = &self.original {
+            // SAFETY: restores the settings read in `start`.
+            unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, original) };
+        }
+        #[cfg(windows)]
+        if let (Some(original), Some(input)) = (self.original, console_input()) {
+            // SAFETY: restores the mode read in `start`.
+            unsafe { winapi::um::consoleapi::SetConsoleMode(input, original) };
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! Locked periods, e.g. after submitting a timesheet: entries started on or
+//! before the date in `<name>-lock.txt` next to the storage file are only
+//! changed with `--force`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Error, Result};
+use time::Date;
+
+use crate::{changes, git, Tracker};
+
+fn lock_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}-lock.txt", stem))
+}
+
+/// The last locked day, if any.
+pub fn until(path: &Path) -> Result<Option<Date>> {
+    let file = lock_path(path);
+    if !file.exists() {
+        return Ok(None);
+    }
+    let content =
+        fs::read_to_string(&file).with_context(|| format!("Could not read {}", file.display()))?;
+    Date::parse(content.trim(), "%F")
+        .map(Some)
+        .with_context(|| format!("Invalid date in {}", file.display()))
+}
+
+/// Locks all days up to `until`. Moving the lock back unlocks days, which
+/// needs `--force`.
+pub fn set(path: &Path, until: Date) -> Result<()> {
+    if let Some(current) = self::until(path)? {
+        if until < current && !crate::force() {
+            return Err(Error::msg(format!(
+                "Locked until {}, pass --force to unlock days",
+                current.format("%F")
+            )));
+        }
+    }
+    let file = lock_path(path);
+    fs::write(&file, format!("{}\n", until.format("%F")))
+        .with_context(|| format!("Could not write {}", file.display()))?;
+    git::commit(&file, &format!("lock: until {}", until.format("%F")))?;
+    println!("Locked until {}", until.format("%F"));
+    Ok(())
+}
+
+pub fn show(path: &Path) -> Result<()> {
+    match until(path)? {
+        Some(until) => println!("Locked until {}", until.format("%F")),
+        None => println!("Nothing is locked"),
+    }
+    Ok(())
+}
+
+/// Fails if going from `old` to `new` adds, changes or removes entries of
+/// locked days, unless `--force` is given.
+pub fn check(path: &Path, old: &[Tracker], new: &[Tracker]) -> Result<()> {
+    let until = match until(path)? {
+        Some(until) if !crate::force() => until,
+        _ => return Ok(()),
+    };
+    if changes(old, new)
+        .iter()
+        .any(|(_, _, entry)| entry.start.date() <= until)
+    {
+        return Err(Error::msg(format!(
+            "Refusing to change entries locked until {}, pass --force to change them anyway",
+            until.format("%F")
+        )));
+    }
+    Ok(())
+}
+
+/// Whether writes have to be checked against the lock.
+pub fn enabled(path: &Path) -> bool {
+    lock_path(path).exists() && !crate::force()
+}
@@ -0,0 +1,128 @@
+//! Leveled log messages for `-v`/`-vv`, written to stderr or appended to
+//! the file of `--log-file` or the `[log]` config section.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use anyhow::{Context, Error, Result};
+use time::OffsetDateTime;
+
+use crate::config::Config;
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Level {
+    Off,
+    /// What is done, e.g. external commands and requests
+    Info,
+    /// The data involved
+    Debug,
+}
+
+impl std::str::FromStr for Level {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "off" => Ok(Level::Off),
+            "info" => Ok(Level::Info),
+            "debug" => Ok(Level::Debug),
+            _ => Err(Error::msg(format!(
+                "Invalid log level {}, expected off, info or debug",
+                s
+            ))),
+        }
+    }
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Off as u8);
+static FILE: OnceLock<Mutex<File>> = OnceLock::new();
+
+/// Logs at `Info` for a `verbosity` of 1 and `Debug` from 2, else at the
+/// `level` of the `[log]` config section. Its `file`, or `file` if given,
+/// gets the messages instead of stderr.
+pub fn configure(config: &Config, verbosity: u64, file: Option<&Path>) -> Result<()> {
+    let level = match verbosity {
+        0 => config
+            .get("log", "level")
+            .map(str::parse)
+            .transpose()?
+            .unwrap_or(Level::Off),
+        1 => Level::Info,
+        _ => Level::Debug,
+    };
+    LEVEL.store(level as u8, Ordering::SeqCst);
+    let file = file.or_else(|| config.get("log", "file").map(Path::new));
+    if let Some(file) = file {
+        let log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file)
+            .with_context(|| format!("Could not open the log file {}", file.display()))?;
+        let _ = FILE.set(Mutex::new(log));
+    }
+    Ok(())
+}
+
+pub fn enabled(level: Level) -> bool {
+    level != Level::Off && level as u8 <= LEVEL.load(Ordering::SeqCst)
+}
+
+fn write(level: Level, message: &str) {
+    let line = format!(
+        "{} {:5} {}\n",
+        OffsetDateTime::now_local().format("%FT%T%z"),
+        format!("{:?}", level).to_uppercase(),
+        message
+    );
+    match FILE.get() {
+        Some(file) => {
+            if let Ok(mut file) = file.lock() {
+                let _ = file.write_all(line.as_bytes());
+            }
+        }
+        None => eprint!("{}", line),
+    }
+}
+
+/// Logs what `message` returns if `Info` is enabled.
+pub fn info(message: impl FnOnce() -> String) {
+    if enabled(Level::Info) {
+        write(Level::Info, &message());
+    }
+}
+
+/// Logs what `message` returns if `Debug` is enabled.
+pub fn debug(message: impl FnOnce() -> String) {
+    if enabled(Level::Debug) {
+        write(Level::Debug, &message());
+    }
+}
+
+/// Logs when it starts and, when dropped, how long it took.
+pub struct Span {
+    name: String,
+    start: Instant,
+}
+
+/// Starts a span, e.g. around an external command.
+pub fn span(name: impl FnOnce() -> String) -> Option<Span> {
+    if !enabled(Level::Info) {
+        return None;
+    }
+    let name = name();
+    write(Level::Info, &name);
+    Some(Span {
+        name,
+        start: Instant::now(),
+    })
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        debug(|| format!("{} took {:?}", self.name, self.start.elapsed()));
+    }
+}
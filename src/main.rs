@@ -1,29 +1,175 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
 use std::fs;
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
 
 use anyhow::{Context, Error, Result};
-use console::Term;
+use console::{Style, Term};
 use crossbeam_channel::{bounded, select, tick, Receiver};
 use csv::{ReaderBuilder, StringRecord, Writer};
 use structopt::StructOpt;
-use time::{Date, Duration, OffsetDateTime};
+use time::{Date, Duration, OffsetDateTime, Time};
 
-static DEBUG: AtomicBool = AtomicBool::new(false);
+mod alias;
+mod append;
+mod archive;
+mod backup;
+mod breaks;
+mod bulk;
+mod caldav;
+mod chain;
+mod chart;
+mod config;
+mod crypt;
+mod dbus;
+mod dedupe;
+mod device;
+mod doctor;
+mod estimate;
+mod export;
+mod filter;
+mod format;
+mod git;
+mod hook;
+mod http;
+mod human;
+mod ics;
+mod idle;
+mod import;
+mod init;
+mod join;
+mod journal;
+mod json;
+mod keys;
+mod lock;
+mod log;
+mod merge;
+mod mqtt;
+mod notify;
+mod pager;
+mod pick;
+mod plan;
+mod pomodoro;
+mod power;
+mod purge;
+mod recur;
+mod remote;
+mod report;
+mod schedule;
+mod schema;
+mod search;
+mod serve;
+mod sha256;
+mod summary;
+mod systemd;
+mod table;
+mod template;
+mod theme;
+
+use config::Config;
+
+/// Print nothing but errors and what was asked for, e.g. no summary after `now`.
+static QUIET: AtomicBool = AtomicBool::new(false);
+/// Show what would be written instead of changing the storage file.
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+/// Do it anyway, e.g. change locked entries.
+static FORCE: AtomicBool = AtomicBool::new(false);
+/// Don't ask before rewriting many entries or replacing the storage file.
+static YES: AtomicBool = AtomicBool::new(false);
+/// Skip rows that can't be parsed instead of failing.
+static SKIP_INVALID: AtomicBool = AtomicBool::new(false);
+/// Split stopped and added sessions at local midnight.
+static SPLIT_MIDNIGHT: AtomicBool = AtomicBool::new(false);
+/// Sessions open for longer than this many hours are flagged, 0 disables.
+static MAX_OPEN_HOURS: AtomicI64 = AtomicI64::new(12);
+/// Sessions longer than this many hours get a warning to take a break, 0 disables.
+static MAX_SESSION_HOURS: AtomicI64 = AtomicI64::new(0);
+/// Close sessions open for longer than `MAX_OPEN_HOURS` when starting a new one.
+static AUTO_CLOSE: AtomicBool = AtomicBool::new(false);
+/// Leave the session running when `live` gets SIGTERM or SIGHUP instead of stopping it.
+static KEEP_ON_TERMINATE: AtomicBool = AtomicBool::new(false);
+/// Set by SIGTERM and SIGHUP, e.g. on shutdown or when the terminal is closed.
+static TERMINATED: AtomicBool = AtomicBool::new(false);
+
+const HEADER: [&str; 7] = [
+    "Start",
+    "End",
+    "Objective",
+    "Device",
+    "Notes",
+    "Meta",
+    "Timer",
+];
+
+/// Arbitrary key-value metadata of an entry, e.g. `ticket=PROJ-42`.
+type Meta = BTreeMap<String, String>;
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "Track Work", about = "A simple work tracker.")]
 struct Opt {
-    /// Prints some debugging information
-    #[structopt(short, long)]
+    /// Log what is done, -vv also the data involved
+    #[structopt(short, long, global = true, parse(from_occurrences))]
+    verbose: u64,
+    /// Log with -vv, kept for compatibility
+    #[structopt(short, long, hidden = true)]
     debug: bool,
-    /// The file where the working data is stored
-    #[structopt(parse(from_os_str), short, long, env = "TRACK_WORK_FILE")]
-    file: PathBuf,
+    /// Append the log messages to this file instead of printing them
+    #[structopt(long, global = true, parse(from_os_str))]
+    log_file: Option<PathBuf>,
+    /// No summary after now, stop, continue and switch, status only sets the exit code
+    #[structopt(short, long, global = true)]
+    quiet: bool,
+    /// The file where the working data is stored, defaults to the file of the --profile,
+    /// then to $TRACK_WORK_FILE and the file of the [storage] config section
+    #[structopt(parse(from_os_str), short, long)]
+    file: Option<PathBuf>,
+    /// Use the file and settings of a [profile.NAME] config section, e.g. work or personal
+    #[structopt(long, global = true, env = "TRACK_WORK_PROFILE")]
+    profile: Option<String>,
+    /// The configuration file, defaults to ~/.config/track-work/config.toml
+    #[structopt(parse(from_os_str), short, long, env = "TRACK_WORK_CONFIG")]
+    config: Option<PathBuf>,
+    /// Skip rows of the storage file that can't be parsed instead of failing
+    #[structopt(long)]
+    skip_invalid: bool,
+    /// Encrypt the storage file with age, see the [encryption] config section
+    #[structopt(long)]
+    encrypt: bool,
     /// The objective for this workin session, can be set anytime
-    #[structopt(short, long, default_value = "")]
+    #[structopt(short, long, default_value = "", global = true)]
     objective: String,
+    /// A named timer, sessions of different timers can run at the same time
+    #[structopt(long, default_value = "", global = true)]
+    timer: String,
+    /// Build the objective from this template of the [templates] config section
+    #[structopt(long, global = true, conflicts_with = "objective")]
+    template: Option<String>,
+    /// Show durations as decimal hours (1.75), hm (01:45), hms (01:45:00) or iso8601 (PT1H45M),
+    /// defaults to duration_format of the [display] config section or hm
+    #[structopt(long, global = true)]
+    duration_format: Option<format::DurationFormat>,
+    /// When to color the output: auto (on a terminal unless NO_COLOR is set), always or never
+    #[structopt(long, global = true, default_value = "auto")]
+    color: theme::ColorMode,
+    /// Show the rows that would be added and removed instead of changing the storage file
+    #[structopt(long, global = true)]
+    dry_run: bool,
+    /// Do it anyway: save overlapping sessions, change locked periods, replace hooks or the config
+    #[structopt(long, global = true)]
+    force: bool,
+    /// Don't ask before bulk-edit, rename-objective, dedupe, join, migrate, restore and purge
+    #[structopt(short, long, global = true)]
+    yes: bool,
+    /// Don't pipe the output of info, report and search through $PAGER
+    #[structopt(long, global = true)]
+    no_pager: bool,
+    /// A value for a template placeholder as key=value, can be repeated
+    #[structopt(long, global = true, number_of_values = 1, parse(try_from_str = template::parse_var))]
+    var: Vec<(String, String)>,
     #[structopt(subcommand)]
     cmd: Command,
 }
@@ -31,47 +177,627 @@ struct Opt {
 #[derive(Debug, StructOpt)]
 enum Command {
     /// Start tracking work now
-    Now,
+    Now {
+        /// Start at this time instead, e.g. "17:30", "yesterday 5pm" or "-15m"
+        #[structopt(long, allow_hyphen_values = true, parse(try_from_str = human::parse_time))]
+        at: Option<OffsetDateTime>,
+        /// Choose the objective from the recently used ones
+        #[structopt(long)]
+        pick: bool,
+        /// Metadata for the entry as key=value, can be repeated
+        #[structopt(long, number_of_values = 1, parse(try_from_str = parse_meta))]
+        meta: Vec<(String, String)>,
+    },
     /// Stop the currently tracked session
-    Stop,
+    Stop {
+        /// Stop at this time instead, e.g. "17:30", "yesterday 5pm" or "-15m"
+        #[structopt(long, allow_hyphen_values = true, parse(try_from_str = human::parse_time))]
+        at: Option<OffsetDateTime>,
+        /// Remove the objective of the session
+        #[structopt(long, conflicts_with = "objective")]
+        clear_objective: bool,
+    },
+    /// Start a session with the objective of the last (or nth last) finished one
+    Continue {
+        /// Which finished session to continue, counting back from the last one
+        #[structopt(default_value = "1")]
+        n: usize,
+        /// Start at this time instead, e.g. "17:30", "yesterday 5pm" or "-15m"
+        #[structopt(long, allow_hyphen_values = true, parse(try_from_str = human::parse_time))]
+        at: Option<OffsetDateTime>,
+    },
+    /// Add a timestamped note to the running session, -o also changes its objective
+    Note {
+        /// What happened, e.g. "switched to debugging"
+        text: Option<String>,
+    },
+    /// Show the currently tracked session
+    Status,
+    /// Stop the current session and start one with a new objective
+    Switch {
+        /// Switch at this time instead, e.g. "17:30", "yesterday 5pm" or "-15m"
+        #[structopt(long, allow_hyphen_values = true, parse(try_from_str = human::parse_time))]
+        at: Option<OffsetDateTime>,
+        /// Metadata for the entry as key=value, can be repeated
+        #[structopt(long, number_of_values = 1, parse(try_from_str = parse_meta))]
+        meta: Vec<(String, String)>,
+    },
     /// Start or display current sessions runtime, stops the current session when SIGINT is received
-    Live,
+    /// unless detached
+    Live {
+        /// Count down until this much work is tracked today, e.g. 8h, or "target" for the
+        /// configured daily target
+        #[structopt(long)]
+        until: Option<String>,
+        /// Leave the session running when quitting the view, run `live` again to reattach
+        #[structopt(long)]
+        detach: bool,
+        /// Alternate work and break intervals, given in minutes as work/break, e.g. 25/5
+        #[structopt(long)]
+        pomodoro: Option<pomodoro::Pomodoro>,
+    },
+    /// Remind about or start and stop tracking at the working hours of the [schedule] config section,
+    /// runs until killed
+    Daemon {
+        /// Notify systemd when ready, ping its watchdog and stop the running session on SIGTERM
+        #[structopt(long)]
+        systemd: bool,
+    },
+    /// Write systemd user units running the daemon with the current file, config and timer
+    InstallService {
+        /// Also a socket activated calendar feed (serve --ics) listening on this address
+        #[structopt(long)]
+        ics: Option<String>,
+    },
+    /// Manage git hooks that switch the objective on branch checkouts and note commits
+    Hook(Hook),
+    /// Notify if nothing is tracked during the working hours, meant to be run from cron
+    Remind,
+    /// Add a finished session manually, refused if it overlaps existing entries
+    Add {
+        /// The start time, e.g. "2022-03-01 09:00" or "monday 9am"
+        #[structopt(parse(try_from_str = human::parse_time))]
+        start: Option<OffsetDateTime>,
+        /// The end time, e.g. "2022-03-01 12:30" or "monday 12:30"
+        #[structopt(parse(try_from_str = human::parse_time))]
+        end: Option<OffsetDateTime>,
+        /// The start time, instead of the first argument
+        #[structopt(long, allow_hyphen_values = true, parse(try_from_str = human::parse_time))]
+        at: Option<OffsetDateTime>,
+        /// The length of the session instead of an end time, e.g. 1h30m
+        #[structopt(long, parse(try_from_str = human::parse_duration))]
+        duration: Option<Duration>,
+        /// Metadata for the entry as key=value, can be repeated
+        #[structopt(long, number_of_values = 1, parse(try_from_str = parse_meta))]
+        meta: Vec<(String, String)>,
+    },
+    /// Add the recurring entries of the [recur] config section for a range of days
+    Recur {
+        /// The first day (YYYY-MM-DD), defaults to today
+        #[structopt(long, parse(try_from_str = human::parse_date))]
+        from: Option<Date>,
+        /// The last day (YYYY-MM-DD), defaults to the first one
+        #[structopt(long, parse(try_from_str = human::parse_date))]
+        to: Option<Date>,
+    },
+    /// Join two entries into one, or all sessions separated by short gaps
+    Join {
+        /// The entries to join, counting back from the last one
+        #[structopt(required_unless = "gap-below", min_values = 2, max_values = 2)]
+        entries: Vec<usize>,
+        /// Instead join all consecutive sessions separated by less than this, e.g. 3m
+        #[structopt(long, parse(try_from_str = human::parse_duration), conflicts_with = "entries")]
+        gap_below: Option<Duration>,
+        /// With --gap-below only join sessions with the same objective
+        #[structopt(long, requires = "gap-below")]
+        same_objective: bool,
+    },
+    /// Remove duplicated entries, e.g. after imports, keeping their notes and metadata
+    Dedupe {
+        /// How far start and end of duplicates may differ, e.g. 1m
+        #[structopt(long, default_value = "0s", parse(try_from_str = human::parse_duration))]
+        tolerance: Duration,
+        /// Also combine all overlapping entries of a timer
+        #[structopt(long)]
+        overlapping: bool,
+    },
+    /// Split an entry in two at a given time, by default the last one
+    Split {
+        /// Which entry to split, counting back from the last one
+        #[structopt(default_value = "1")]
+        n: usize,
+        /// Where to split, e.g. "14:00" or "yesterday 2pm"
+        #[structopt(long, allow_hyphen_values = true, parse(try_from_str = human::parse_time))]
+        at: OffsetDateTime,
+        /// The objective of the second part, defaults to that of the first
+        #[structopt(long)]
+        objective_b: Option<String>,
+    },
+    /// Change an entry, by default the last one. A new objective is given via -o
+    Edit {
+        /// Which entry to change, counting back from the last one
+        #[structopt(default_value = "1")]
+        n: usize,
+        /// The new start time
+        #[structopt(long, allow_hyphen_values = true, parse(try_from_str = human::parse_time))]
+        start: Option<OffsetDateTime>,
+        /// The new end time
+        #[structopt(long, allow_hyphen_values = true, parse(try_from_str = human::parse_time))]
+        end: Option<OffsetDateTime>,
+        /// Extend the session by this duration, e.g. 15m
+        #[structopt(long, parse(try_from_str = human::parse_duration))]
+        extend: Option<Duration>,
+        /// Replace the notes, may span multiple lines
+        #[structopt(long)]
+        note: Option<String>,
+        /// Edit the notes in $EDITOR
+        #[structopt(long, conflicts_with = "note")]
+        edit_notes: bool,
+        /// Set metadata as key=value, an empty value removes the key. Can be repeated
+        #[structopt(long, number_of_values = 1, parse(try_from_str = parse_meta))]
+        meta: Vec<(String, String)>,
+    },
     /// Displays info about time worked so far. See: info -h
     Info {
         #[structopt(short, long)]
         /// Show info for each session, otherwise shows data for current date and total duration
         uncompressed: bool,
+        /// Include the yearly archive files created by `archive`
+        #[structopt(short, long)]
+        archives: bool,
+        /// Show the total duration per group instead: device, timer or objective
+        #[structopt(long)]
+        by: Option<GroupBy>,
+        /// With --by objective, roll `/`-separated objectives up to this many levels
+        #[structopt(long)]
+        depth: Option<usize>,
+        /// Show the idle periods between the sessions of each day instead
+        #[structopt(long)]
+        gaps: bool,
+        /// Check the breaks of each day against the rules of the [breaks] config section instead
+        #[structopt(long)]
+        breaks: bool,
+        /// Show the notes below each session, implies --uncompressed
+        #[structopt(long)]
+        notes: bool,
+        /// Show the time per day as bars, with the daily target of the [targets] config section marked
+        #[structopt(long)]
+        chart: bool,
+        /// Hide sessions shorter than this, e.g. 5m, implies --uncompressed
+        #[structopt(long, parse(try_from_str = human::parse_duration))]
+        min_duration: Option<Duration>,
+        /// Hide sessions longer than this, e.g. 10h, implies --uncompressed
+        #[structopt(long, parse(try_from_str = human::parse_duration))]
+        max_duration: Option<Duration>,
+        /// Only entries matching this filter, e.g. "project=acme && duration>1h && tag!=break"
+        #[structopt(long = "where")]
+        filter: Option<filter::Filter>,
+        /// Print each session with this template, e.g. '{date}\t{hours_decimal}\t{objective}',
+        /// or one of the [formats] config section. Implies --uncompressed
+        #[structopt(long)]
+        format: Option<String>,
+        /// Count running sessions up to now in every total, marked as running
+        #[structopt(long, conflicts_with = "exclude-running")]
+        include_running: bool,
+        /// Leave running sessions out entirely, e.g. for timesheets
+        #[structopt(long)]
+        exclude_running: bool,
         #[structopt(subcommand)]
         info: Option<Info>,
     },
+    /// Change all entries matching a filter, showing each change
+    BulkEdit {
+        /// Which entries to change, e.g. 'objective~"PROJ-12"', see info --where
+        #[structopt(long = "where")]
+        filter: filter::Filter,
+        /// A change as key=value, key+=value or key-=value for comma-separated lists,
+        /// keys other than objective and timer are metadata. Can be repeated
+        #[structopt(long, number_of_values = 1)]
+        set: Vec<bulk::Set>,
+    },
+    /// Rename an objective in all entries, e.g. to fix inconsistent naming
+    RenameObjective {
+        old: String,
+        new: String,
+        /// Also rename the objectives below it in the `/`-separated hierarchy
+        #[structopt(long)]
+        prefix: bool,
+    },
+    /// Manage estimates per objective, compared with the tracked time by report estimates
+    Estimate(Estimate),
+    /// Plan the hours per objective of a week, compared with the tracked time by report plan
+    Plan(Plan),
+    /// Reports over all history, see: report -h
+    Report(ReportKind),
+    /// List the entries whose objective or notes match, across all history
+    Search {
+        /// The text to look for, case-insensitive
+        pattern: String,
+        /// Treat the pattern as a regular expression
+        #[structopt(long)]
+        regex: bool,
+    },
+    /// Print all entries, including the archives, as CSV
+    Export {
+        /// Replace objectives, clients and projects with pseudonyms kept next to the storage
+        /// file and leave out notes, see the [anonymize] config section
+        #[structopt(long)]
+        anonymize: bool,
+        /// Only entries matching this filter, see info --where
+        #[structopt(long = "where")]
+        filter: Option<filter::Filter>,
+    },
+    /// Move entries before a cutoff date into yearly archive files, e.g. work-2022.csv
+    Archive {
+        /// Archive entries started before this date (YYYY-MM-DD), defaults to the start of this year
+        #[structopt(long, parse(try_from_str = human::parse_date))]
+        before: Option<Date>,
+    },
+    /// Permanently remove the entries before a date from the storage file and its archives
+    Purge {
+        /// Remove the entries started before this date (YYYY-MM-DD)
+        #[structopt(long, parse(try_from_str = human::parse_date))]
+        before: Date,
+    },
+    /// Merge the entries of another storage file into this one
+    Merge {
+        /// The storage file to merge in
+        #[structopt(parse(from_os_str))]
+        other: PathBuf,
+        /// How to resolve overlapping entries: ask, ours, theirs, both
+        #[structopt(long, default_value = "ask")]
+        strategy: merge::Strategy,
+    },
+    /// Revert the most recent change to the storage file
+    Undo,
+    /// Rewrite the storage file in the current schema version
+    Migrate,
+    /// Check the hash chain of the entries, see the [audit] config section
+    Verify,
+    /// Check the storage file for invalid rows, reversed, multi-day and overlapping sessions
+    Doctor {
+        /// Remove rows that can't be parsed
+        #[structopt(long)]
+        drop_invalid: bool,
+        /// Swap start and end of entries that end before they start
+        #[structopt(long)]
+        swap_reversed: bool,
+        /// Split sessions spanning multiple days at local midnight
+        #[structopt(long)]
+        split_midnight: bool,
+        /// Only list overlapping sessions
+        #[structopt(long)]
+        overlaps: bool,
+    },
+    /// Lock the entries up to a day, e.g. after submitting a timesheet. Shows the lock without --until
+    Lock {
+        /// The last locked day (YYYY-MM-DD), moving it back needs --force
+        #[structopt(long, parse(try_from_str = human::parse_date))]
+        until: Option<Date>,
+    },
+    /// Roll the storage file back to a backup, lists the available backups without argument
+    Restore {
+        /// The backup to restore
+        backup: Option<String>,
+    },
+    /// Synchronize the tracked sessions with a remote service
+    Sync {
+        #[structopt(subcommand)]
+        target: Sync,
+    },
+    /// Serve the tracked sessions over HTTP, e.g. as a subscribable calendar
+    Serve {
+        /// Serve a read-only iCalendar feed at /work.ics
+        #[structopt(long)]
+        ics: bool,
+        /// Include the notes of the sessions in the feed
+        #[structopt(long)]
+        notes: bool,
+        /// The address to listen on
+        #[structopt(long, default_value = "127.0.0.1:8080")]
+        bind: String,
+    },
+    /// Serve org.trackwork.Tracker on the session bus, for desktop applets
+    Dbus,
+    /// Import sessions exported by other trackers, skipping already tracked periods
+    Import {
+        /// The format of the imported data: csv, activitywatch, rescuetime, wakatime (fetched via the API key in [wakatime])
+        #[structopt(long, default_value = "csv")]
+        format: import::Format,
+        /// The file to import
+        #[structopt(parse(from_os_str))]
+        file: Option<PathBuf>,
+        /// First day to fetch from online services (YYYY-MM-DD), defaults to today
+        #[structopt(long, parse(try_from_str = human::parse_date))]
+        from: Option<Date>,
+        /// Last day to fetch from online services (YYYY-MM-DD), defaults to today
+        #[structopt(long, parse(try_from_str = human::parse_date))]
+        to: Option<Date>,
+        /// Merge imported sessions separated by less than this many minutes
+        #[structopt(long, default_value = "5")]
+        merge_threshold: i64,
+        /// CSV columns (1-based or header names) of the fields, e.g. start=2,end=3,objective=5
+        #[structopt(long)]
+        map: Option<import::ColumnMap>,
+        /// The timestamp format of the CSV columns, e.g. "%d.%m.%Y %H:%M"
+        #[structopt(long, default_value = "%F %T %z")]
+        time_format: String,
+        /// The field delimiter of the CSV file, a single ASCII character
+        #[structopt(long, default_value = ",", parse(try_from_str = parse_delimiter))]
+        delimiter: u8,
+        /// The CSV file has no header row
+        #[structopt(long)]
+        no_headers: bool,
+    },
+    /// Set up the config file, the storage file and shell completions interactively
+    Init,
+}
+
+#[derive(Debug, StructOpt)]
+enum Hook {
+    /// Install the hooks into the git repository of the working directory
+    Install,
+    /// Remove the hooks installed by track-work
+    Uninstall,
+}
+
+#[derive(Debug, StructOpt)]
+enum Estimate {
+    /// Estimate how long an objective, including the ones below it, will take
+    Set {
+        /// The objective, e.g. PROJ-42
+        name: String,
+        /// The estimate, e.g. 6h
+        #[structopt(parse(try_from_str = human::parse_duration))]
+        estimate: Duration,
+    },
+    /// Remove the estimate of an objective
+    Remove { name: String },
+}
+
+#[derive(Debug, StructOpt)]
+enum Plan {
+    /// Plan hours for an objective, including the ones below it
+    Set {
+        /// The objective, e.g. acme
+        name: String,
+        /// The planned time, e.g. 10h
+        #[structopt(parse(try_from_str = human::parse_duration))]
+        planned: Duration,
+        /// The week: this, next, last or any day of it
+        #[structopt(long, default_value = "next")]
+        week: plan::Week,
+    },
+    /// Remove the planned hours of an objective
+    Remove {
+        name: String,
+        /// The week: this, next, last or any day of it
+        #[structopt(long, default_value = "next")]
+        week: plan::Week,
+    },
+}
+
+/// The days a report covers, all by default.
+#[derive(Debug, StructOpt)]
+struct Period {
+    /// The first day (YYYY-MM-DD)
+    #[structopt(long, parse(try_from_str = human::parse_date))]
+    from: Option<Date>,
+    /// The last day (YYYY-MM-DD)
+    #[structopt(long, parse(try_from_str = human::parse_date))]
+    to: Option<Date>,
+}
+
+#[derive(Debug, StructOpt)]
+enum ReportKind {
+    /// A calendar grid of this month, shaded by the time tracked per day
+    Heatmap {
+        /// Show the last year instead
+        #[structopt(long)]
+        year: bool,
+    },
+    /// The time per weekday and per hour of the day
+    Distribution {
+        #[structopt(flatten)]
+        period: Period,
+    },
+    /// The time per objective in two periods and how it changed
+    Compare {
+        /// The period and the one to compare it with, e.g. month-0,month-1, week-0,week-1,
+        /// 2024-03,2024-02 or 2024-03-01..2024-03-15,2024-02-01..2024-02-15
+        #[structopt(long, use_delimiter = true, min_values = 2, max_values = 2)]
+        periods: Vec<report::Span>,
+    },
+    /// The used and remaining hours of the budgets of the [budgets] config section
+    Budget,
+    /// The estimated and the tracked time of every estimated objective
+    Estimates,
+    /// The current and longest run of workdays with tracked time and the badges earned
+    Streaks,
+    /// The planned and the tracked time per objective of a week, the biggest deviations first
+    Plan {
+        /// The week: this, next, last or any day of it
+        #[structopt(long, default_value = "this")]
+        week: plan::Week,
+    },
+    /// The hours per remaining workday needed to reach the monthly target
+    Forecast {
+        /// The monthly target, e.g. 160h, defaults to monthly of the [targets] config section
+        #[structopt(long, parse(try_from_str = human::parse_duration))]
+        target: Option<Duration>,
+        /// The workdays left this month including today, defaults to the working days of the
+        /// [schedule] config section or monday to friday
+        #[structopt(long)]
+        days: Option<u32>,
+    },
+    /// Summary statistics like the average day, the median session and the longest streak
+    Stats {
+        #[structopt(flatten)]
+        period: Period,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum Sync {
+    /// Pull and push the git repository containing the storage file
+    Git,
+    /// Exchange the storage file with a WebDAV server or S3 bucket configured in [remote]
+    Remote,
+    /// Exchange sessions with a CalDAV calendar configured in the [caldav] config section
+    Caldav {
+        /// Only upload local sessions
+        #[structopt(long, conflicts_with = "pull-only")]
+        push_only: bool,
+        /// Only download remote events
+        #[structopt(long)]
+        pull_only: bool,
+    },
 }
 
 #[derive(Debug, StructOpt)]
 enum Info {
     /// Show data from <delta> months ago
     Month {
-        #[structopt(default_value = "0")]
-        /// Show data from <delta> months ago
-        delta: u8,
+        /// Show data from <delta> months ago, defaults to the current month
+        delta: Option<u32>,
+        /// Show data from this month instead, as YYYY-MM
+        #[structopt(long, conflicts_with = "delta", parse(try_from_str = human::parse_month))]
+        date: Option<(i32, u8)>,
     },
+    /// Show data for today
+    Today,
+    /// Show data for yesterday
+    Yesterday,
     /// Show data for all tracked dates
     All,
 }
 
 #[derive(Debug)]
+enum GroupBy {
+    Device,
+    Timer,
+    Objective,
+}
+
+impl std::str::FromStr for GroupBy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "device" => Ok(GroupBy::Device),
+            "timer" => Ok(GroupBy::Timer),
+            "objective" => Ok(GroupBy::Objective),
+            _ => Err(Error::msg(format!("Cannot group by {}", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 struct Tracker {
     start: OffsetDateTime,
     end: Option<OffsetDateTime>,
     objective: String,
+    /// The machine the session was tracked on, empty if unknown
+    device: String,
+    /// Free text, one line per note
+    notes: String,
+    /// Stored as JSON object
+    meta: Meta,
+    /// The named timer of the session, empty for the default one
+    timer: String,
+    /// Values of columns track-work doesn't know, by column name
+    extra: Vec<(String, String)>,
 }
 
 impl Tracker {
-    fn start(objective: String) -> Self {
+    fn new(start: OffsetDateTime, end: Option<OffsetDateTime>, objective: String) -> Self {
         Tracker {
-            start: OffsetDateTime::now_local(),
-            end: None,
+            start,
+            end,
             objective,
+            device: String::new(),
+            notes: String::new(),
+            meta: Meta::new(),
+            timer: String::new(),
+            extra: Vec::new(),
+        }
+    }
+
+    /// Appends a line to the notes, prefixed with the current time.
+    fn add_note(&mut self, note: &str) {
+        if !self.notes.is_empty() {
+            self.notes.push('\n');
         }
+        self.notes.push_str(&format!(
+            "{} {}",
+            OffsetDateTime::now_local().format("%R"),
+            note
+        ));
+    }
+
+    fn start(objective: String, at: OffsetDateTime) -> Self {
+        Tracker {
+            device: device::name(),
+            ..Tracker::new(at, None, objective)
+        }
+    }
+
+    /// The end of the session, running sessions end now.
+    fn end_or_now(&self) -> OffsetDateTime {
+        self.end.unwrap_or_else(OffsetDateTime::now_local)
+    }
+
+    /// Splits a finished session at every local midnight it spans.
+    fn split_at_midnight(self) -> Vec<Tracker> {
+        let mut parts = Vec::new();
+        let mut rest = self;
+        while let Some(end) = rest.end {
+            let midnight = human::local(rest.start.date().next_day(), Time::midnight());
+            if end <= midnight {
+                break;
+            }
+            parts.push(Tracker {
+                end: Some(midnight),
+                ..rest.clone()
+            });
+            rest = Tracker {
+                start: midnight,
+                ..rest
+            };
+        }
+        parts.push(rest);
+        parts
+    }
+
+    /// Sessions of different timers may overlap, so they never conflict.
+    fn overlaps(&self, other: &Tracker) -> bool {
+        self.timer == other.timer
+            && self.start < other.end_or_now()
+            && other.start < self.end_or_now()
+    }
+
+    fn to_record(&self) -> Vec<String> {
+        vec![
+            schema::format_time(self.start),
+            self.end
+                .map(schema::format_time)
+                .unwrap_or_else(|| "".into()),
+            self.objective.clone(),
+            self.device.clone(),
+            self.notes.clone(),
+            if self.meta.is_empty() {
+                String::new()
+            } else {
+                json::Value::Object(
+                    self.meta
+                        .iter()
+                        .map(|(k, v)| (k.clone(), json::Value::String(v.clone())))
+                        .collect(),
+                )
+                .to_string()
+            },
+            self.timer.clone(),
+        ]
     }
 }
 
@@ -81,19 +807,16 @@ impl std::fmt::Display for Tracker {
             Some(end) => end - self.start,
             None => OffsetDateTime::now_local() - self.start,
         };
-        let duration = format!(
-            "{:02}:{:02},",
-            duration.whole_hours(),
-            duration.whole_minutes() % 60
-        );
+        let duration = format!("{},", format::duration(duration));
         let end_str = match self.end {
-            Some(end) => end.format("%R,"),
+            Some(end) => format!("{},", format::time(end)),
             None => ",".into(),
         };
         write!(
             f,
-            "{} {} {} {}",
-            self.start.format("%F, %R,"),
+            "{}, {}, {} {} {}",
+            format::date(self.start.date()),
+            format::time(self.start),
             end_str,
             duration,
             self.objective
@@ -101,97 +824,686 @@ impl std::fmt::Display for Tracker {
     }
 }
 
-impl From<StringRecord> for Tracker {
-    fn from(rec: StringRecord) -> Self {
+impl TryFrom<StringRecord> for Tracker {
+    type Error = Error;
+
+    fn try_from(rec: StringRecord) -> Result<Self> {
+        let invalid = |what: &str| {
+            let line = rec
+                .position()
+                .map(|p| format!("line {}: ", p.line()))
+                .unwrap_or_default();
+            Error::msg(format!(
+                "{}invalid {}: {}",
+                line,
+                what,
+                rec.iter().collect::<Vec<_>>().join(",")
+            ))
+        };
         let start = rec
             .get(0)
-            .map(|s| OffsetDateTime::parse(s, "%F %T %z"))
-            .expect("Could not read entry 0 of csv!")
-            .expect("Could not parse start!");
-        let end = rec
-            .get(1)
-            .map(|s| OffsetDateTime::parse(s, "%F %T %z").ok())
-            .unwrap_or(None);
+            .and_then(schema::parse_time)
+            .ok_or_else(|| invalid("start"))?;
+        let end = match rec.get(1) {
+            None | Some("") => None,
+            Some(s) => Some(schema::parse_time(s).ok_or_else(|| invalid("end"))?),
+        };
         let objective = rec.get(2).unwrap_or("").into();
-        Self {
+        let device = rec.get(3).unwrap_or("").into();
+        let notes = rec.get(4).unwrap_or("").into();
+        let meta = match rec.get(5) {
+            None | Some("") => Meta::new(),
+            Some(s) => json::parse(s)
+                .ok()
+                .as_ref()
+                .and_then(json::Value::as_object)
+                .ok_or_else(|| invalid("meta"))?
+                .iter()
+                .map(|(k, v)| {
+                    let v = match v {
+                        json::Value::String(s) => s.clone(),
+                        v => v.to_string(),
+                    };
+                    (k.clone(), v)
+                })
+                .collect(),
+        };
+        let timer = rec.get(6).unwrap_or("").into();
+        Ok(Self {
             start,
             end,
             objective,
+            device,
+            notes,
+            meta,
+            timer,
+            extra: Vec::new(),
+        })
+    }
+}
+
+/// Parses a `key=value` pair given via --meta, an empty value removes the key.
+fn parse_meta(s: &str) -> Result<(String, String)> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.trim().to_string()))
         }
+        _ => Err(Error::msg(format!(
+            "Invalid metadata, expected key=value: {}",
+            s
+        ))),
     }
 }
 
-fn debug() -> bool {
-    DEBUG.load(Ordering::SeqCst)
+/// Parses the --delimiter of imported CSV files, which has to be one byte.
+fn parse_delimiter(s: &str) -> Result<u8> {
+    match s.as_bytes() {
+        [byte] if byte.is_ascii() => Ok(*byte),
+        _ => Err(Error::msg(format!(
+            "Invalid delimiter, expected a single ASCII character: {}",
+            s
+        ))),
+    }
 }
 
-fn read(path: &PathBuf) -> Result<Vec<Tracker>> {
-    if path.exists() {
-        let file = fs::File::open(path)
-            .with_context(|| format!("Storage file not found: {}", path.display()))?;
-        let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(file);
-        let data = rdr
-            .records()
-            .inspect(|data| {
-                if debug() {
-                    println!("{:?}", data)
-                } else {
-                }
-            })
-            .filter_map(|d| d.ok())
-            .map(Tracker::from)
-            .collect();
-        Ok(data)
+type Records = Box<dyn Iterator<Item = csv::Result<StringRecord>>>;
+
+/// Parses a row in the current layout, keeping the values of the unknown
+/// `columns` that follow the known ones.
+fn parse(record: StringRecord, columns: &[String]) -> Result<Tracker> {
+    let extra = columns
+        .iter()
+        .cloned()
+        .zip(record.iter().skip(HEADER.len()).map(String::from))
+        .collect();
+    Ok(Tracker {
+        extra,
+        ..Tracker::try_from(record)?
+    })
+}
+
+/// The raw rows of the storage file, upgraded to the current schema.
+fn records(path: &PathBuf) -> Result<(Vec<String>, Records)> {
+    if !path.exists() {
+        return Ok((Vec::new(), Box::new(std::iter::empty())));
+    }
+    let mut file = fs::File::open(path)
+        .with_context(|| format!("Storage file not found: {}", path.display()))?;
+    let mut head = Vec::new();
+    (&mut file).take(64).read_to_end(&mut head)?;
+    let (head, rest): (Vec<u8>, Box<dyn std::io::Read>) = if crypt::is_encrypted(&head) {
+        let mut bytes = head;
+        file.read_to_end(&mut bytes)?;
+        (crypt::decrypt(path, &bytes)?, Box::new(std::io::empty()))
     } else {
-        Ok(Vec::new())
+        (head, Box::new(file))
+    };
+    let marked = schema::marked(&head);
+    // The schema marker is skipped as a comment, rows can't start with `#`.
+    let mut rdr = ReaderBuilder::new()
+        .has_headers(true)
+        .comment(Some(b'#'))
+        .from_reader(std::io::Cursor::new(head).chain(rest));
+    let version = schema::version(marked, rdr.headers()?)?;
+    let columns = schema::unknown_columns(version, rdr.headers()?);
+    let records = rdr
+        .into_records()
+        .inspect(|data| log::debug(|| format!("{:?}", data)))
+        .map(move |record| record.map(|record| schema::upgrade(version, record)));
+    Ok((columns, Box::new(records)))
+}
+
+/// Lazily reads the entries of the storage file one record at a time, so
+/// filters applied to the iterator never hold the whole history in memory.
+fn stream(path: &PathBuf) -> Result<Box<dyn Iterator<Item = Result<Tracker>>>> {
+    let name = path.display().to_string();
+    let (columns, records) = records(path)?;
+    let data = records
+        .map(move |record| {
+            record
+                .map_err(Error::from)
+                .and_then(|record| parse(record, &columns))
+                .with_context(|| {
+                    format!(
+                        "Invalid row in {}, run `doctor` or pass --skip-invalid",
+                        name
+                    )
+                })
+        })
+        .filter(|entry| match entry {
+            Err(e) if SKIP_INVALID.load(Ordering::SeqCst) => {
+                eprintln!("Skipping invalid row, {}", e.root_cause());
+                false
+            }
+            _ => true,
+        });
+    Ok(Box::new(data))
+}
+
+type Failure = Rc<RefCell<Option<Error>>>;
+
+/// Ends the entries at the first invalid one, whose error is left in the
+/// returned cell to be checked with `check` once they are consumed.
+fn until_invalid(
+    data: Box<dyn Iterator<Item = Result<Tracker>>>,
+) -> (Box<dyn Iterator<Item = Tracker>>, Failure) {
+    let failure: Failure = Rc::new(RefCell::new(None));
+    let cell = failure.clone();
+    let data = data.scan((), move |_, entry| match entry {
+        Ok(entry) => Some(entry),
+        Err(e) => {
+            cell.borrow_mut().replace(e);
+            None
+        }
+    });
+    (Box::new(data), failure)
+}
+
+fn check(failure: &Failure) -> Result<()> {
+    match failure.borrow_mut().take() {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+fn read(path: &PathBuf) -> Result<Vec<Tracker>> {
+    stream(path)?.collect()
+}
+
+fn dry_run() -> bool {
+    DRY_RUN.load(Ordering::SeqCst)
+}
+
+fn force() -> bool {
+    FORCE.load(Ordering::SeqCst)
+}
+
+/// Asks `question` on a terminal, unless `--yes` or `--dry-run` is given,
+/// and fails if it isn't answered with y.
+fn confirm(question: &str) -> Result<()> {
+    let term = Term::stdout();
+    if YES.load(Ordering::SeqCst) || dry_run() || !term.features().is_attended() {
+        return Ok(());
+    }
+    term.write_str(&format!("{} [y/N] ", question))?;
+    match term.read_line()?.trim() {
+        "y" | "Y" | "yes" => Ok(()),
+        _ => Err(Error::msg("Aborted, nothing was changed")),
+    }
+}
+
+/// The rows of `old` missing in `new` with `-` and the ones added with `+`,
+/// ordered by start.
+fn changes<'a>(old: &'a [Tracker], new: &'a [Tracker]) -> Vec<(OffsetDateTime, char, &'a Tracker)> {
+    let mut unmatched = new
+        .iter()
+        .map(|entry| entry.to_record())
+        .collect::<Vec<_>>();
+    let mut lines = Vec::new();
+    for entry in old {
+        let record = entry.to_record();
+        match unmatched.iter().position(|other| *other == record) {
+            Some(index) => {
+                unmatched.remove(index);
+            }
+            None => lines.push((entry.start, '-', entry)),
+        }
+    }
+    for entry in new {
+        let record = entry.to_record();
+        if let Some(index) = unmatched.iter().position(|other| *other == record) {
+            unmatched.remove(index);
+            lines.push((entry.start, '+', entry));
+        }
+    }
+    // Removed rows before the added ones replacing them
+    lines.sort_by_key(|(start, sign, _)| (*start, *sign == '+'));
+    lines
+}
+
+/// Prints the `changes` from `old` to `new`.
+fn print_diff(old: &[Tracker], new: &[Tracker]) {
+    let lines = changes(old, new);
+    if lines.is_empty() {
+        println!("Dry run, nothing would change");
+        return;
+    }
+    println!("Dry run, would write:");
+    for (_, sign, entry) in lines {
+        let style = if sign == '-' {
+            Style::new().red()
+        } else {
+            Style::new().green()
+        };
+        println!("{}", style.apply_to(format!("{} {}", sign, entry)));
+        let record = entry.to_record();
+        if !entry.notes.is_empty() {
+            println!("{}", style.apply_to(format!("  notes: {:?}", record[4])));
+        }
+        if !entry.meta.is_empty() {
+            println!("{}", style.apply_to(format!("  meta: {}", record[5])));
+        }
     }
 }
 
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+/// Writes all entries and records the change in the journal for `undo`.
 fn write(path: &PathBuf, data: &[Tracker]) -> Result<()> {
-    let file = fs::OpenOptions::new()
+    if lock::enabled(path) && path.exists() {
+        lock::check(path, &read(path)?, data)?;
+    }
+    write_unlocked(path, data)
+}
+
+/// Like `write`, but also changes locked entries, e.g. to archive them.
+fn write_unlocked(path: &PathBuf, data: &[Tracker]) -> Result<()> {
+    if dry_run() {
+        let old = if path.exists() {
+            read(path)?
+        } else {
+            Vec::new()
+        };
+        print_diff(&old, data);
+        return Ok(());
+    }
+    // Journaled only once stored, `undo` would revert a change that failed
+    let snapshot = journal::snapshot(path)?;
+    store(path, data)?;
+    let description = journal::record(path, snapshot, data)?;
+    if let Some(description) = description {
+        git::commit(path, &description)?;
+    }
+    Ok(())
+}
+
+/// Writes all entries to a temporary file next to the storage file, which
+/// then atomically replaces it. A crash midway never leaves a truncated file.
+fn store(path: &PathBuf, data: &[Tracker]) -> Result<()> {
+    let tmp = tmp_path(path);
+    let mut file = fs::OpenOptions::new()
         .write(true)
         .truncate(true)
         .create(true)
-        .open(path)?;
-    let mut writer = Writer::from_writer(file);
-    if debug() {
-        println!("{:?}", data);
+        .open(&tmp)
+        .with_context(|| format!("Could not create {}", tmp.display()))?;
+    let sealed;
+    let data = if chain::enabled() {
+        sealed = chain::seal(data);
+        &sealed[..]
+    } else {
+        data
+    };
+    let mut writer = Writer::from_writer(schema::marker().into_bytes());
+    log::debug(|| format!("Writing {:?}", data));
+    // Unknown columns are kept after the known ones, in order of appearance.
+    let mut columns: Vec<&str> = Vec::new();
+    for (name, _) in data.iter().flat_map(|entry| &entry.extra) {
+        if !columns.contains(&name.as_str()) {
+            columns.push(name);
+        }
     }
-    writer.write_record(&["Start", "End", "Objective"])?;
+    writer.write_record(HEADER.iter().chain(&columns))?;
     for entry in data.iter() {
-        writer.write_record(&[
-            entry.start.format("%F %T %z"),
+        let mut record = entry.to_record();
+        record.extend(columns.iter().map(|column| {
             entry
-                .end
-                .map(|e| e.format("%F %T %z"))
-                .unwrap_or_else(|| "".into()),
-            entry.objective.clone(),
-        ])?;
+                .extra
+                .iter()
+                .find(|(name, _)| name == column)
+                .map(|(_, value)| value.clone())
+                .unwrap_or_default()
+        }));
+        writer.write_record(record)?;
+    }
+    let content = writer.into_inner().map_err(|e| Error::msg(e.to_string()))?;
+    file.write_all(&crypt::encrypt(content)?)?;
+    file.sync_all()?;
+    backup::backup(path)?;
+    fs::rename(&tmp, path)
+        .with_context(|| format!("Could not replace storage file {}", path.display()))?;
+    // Persist the rename itself, directories can't be synced on windows.
+    #[cfg(unix)]
+    {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        fs::File::open(dir)?.sync_all()?;
     }
-    writer.flush()?;
     Ok(())
 }
 
-fn start(path: &PathBuf, objective: String, show: bool) -> Result<()> {
+/// The time after which an open session is considered forgotten.
+fn max_open() -> Option<Duration> {
+    match MAX_OPEN_HOURS.load(Ordering::SeqCst) {
+        hours if hours > 0 => Some(Duration::hours(hours)),
+        _ => None,
+    }
+}
+
+fn overdue(entry: &Tracker) -> bool {
+    entry.end.is_none()
+        && max_open().is_some_and(|max| OffsetDateTime::now_local() - entry.start > max)
+}
+
+/// A warning if a session of this length exceeds `MAX_SESSION_HOURS`.
+fn long_session(duration: Duration) -> Option<String> {
+    match MAX_SESSION_HOURS.load(Ordering::SeqCst) {
+        hours if hours > 0 && duration > Duration::hours(hours) => Some(format!(
+            "The session is longer than {}h, time for a break or a forgotten timer?",
+            hours
+        )),
+        _ => None,
+    }
+}
+
+/// Handles a still open session when starting a new one at `at`: a
+/// forgotten session is closed if `auto_close` is set, otherwise this fails.
+fn close_open(entry: &mut Tracker, at: OffsetDateTime) -> Result<()> {
+    let since = format::date_time(entry.start);
+    match max_open() {
+        Some(max) if overdue(entry) => {
+            if !AUTO_CLOSE.load(Ordering::SeqCst) {
+                return Err(Error::msg(format!(
+                    "The last session has been open since {}, did you forget to stop it? \
+                     Stop it with `stop --at <time>` or set auto_close in the [sessions] config section",
+                    since
+                )));
+            }
+            let end = std::cmp::min(entry.start + max, at);
+            entry.end = Some(end);
+            println!(
+                "Closed the session open since {} at {}",
+                since,
+                format::date_time(end)
+            );
+            Ok(())
+        }
+        _ => Err(Error::msg(format!(
+            "A session is already running since {}, stop it first",
+            since
+        ))),
+    }
+}
+
+/// Checks a time given via `--at`, it may not be in the future.
+fn check_at(at: Option<OffsetDateTime>) -> Result<OffsetDateTime> {
+    let now = OffsetDateTime::now_local();
+    match at {
+        Some(at) if at > now => Err(Error::msg(format!(
+            "{} is in the future",
+            format::date_time(at)
+        ))),
+        Some(at) => Ok(at),
+        None => Ok(now),
+    }
+}
+
+/// The index of the last entry of `timer`.
+fn last_of(data: &[Tracker], timer: &str) -> Option<usize> {
+    data.iter().rposition(|entry| entry.timer == timer)
+}
+
+/// Starts a session of `timer` at `at` or now, after handling a still open
+/// one with `close_open`. With `show` the summary is printed afterwards.
+fn start(
+    path: &PathBuf,
+    timer: &str,
+    objective: String,
+    meta: Meta,
+    at: Option<OffsetDateTime>,
+    show: bool,
+) -> Result<()> {
+    let at = check_at(at)?;
+    let new = Tracker {
+        meta,
+        timer: timer.into(),
+        ..Tracker::start(objective, at)
+    };
+    let too_early = |last: &Tracker| match last.end {
+        Some(end) if at < end => Err(Error::msg(format!(
+            "The last session ended at {}, can't start before that",
+            format::date_time(end)
+        ))),
+        _ => Ok(()),
+    };
+    if append::usable(path) {
+        if let Some(last) = append::last(path)? {
+            // An open session of the timer is always its last entry.
+            if last.entry.timer == timer && last.entry.end.is_some() {
+                too_early(&last.entry)?;
+                append::append(path, &new)?;
+                if show {
+                    summary::print(path, timer)?;
+                }
+                return Ok(());
+            }
+        }
+    }
     let mut data = read(path)?;
-    if let Some(entry) = data.last() {
+    if let Some(index) = last_of(&data, timer) {
+        let entry = &mut data[index];
         if entry.end.is_none() {
-            return Err(Error::msg(
-                "Last entry has no end. Please first correct this error",
-            ));
+            close_open(entry, at)?;
         }
+        too_early(entry)?;
     }
-    data.push(Tracker::start(objective));
+    data.push(new);
     write(path, &data)?;
     if show {
-        info(path, &None, false)?;
+        summary::print(path, timer)?;
     }
     Ok(())
 }
 
-fn stop(path: &PathBuf, objective: String, show: bool) -> Result<()> {
+/// Starts a new session like the `n`th last finished one.
+fn resume(path: &PathBuf, timer: &str, n: usize, at: Option<OffsetDateTime>) -> Result<()> {
+    let previous = read(path)?
+        .into_iter()
+        .rev()
+        .filter(|entry| entry.timer == timer && entry.end.is_some())
+        .nth(n.saturating_sub(1))
+        .ok_or_else(|| Error::msg(format!("There is no finished session {} to continue", n)))?;
+    println!("Continuing: {}", previous.objective);
+    start(path, timer, previous.objective, previous.meta, at, true)
+}
+
+/// Ends the running session and starts a new one in a single write.
+fn switch(
+    path: &PathBuf,
+    timer: &str,
+    objective: String,
+    meta: Meta,
+    at: Option<OffsetDateTime>,
+) -> Result<()> {
+    let at = check_at(at)?;
+    let mut data = read(path)?;
+    match last_of(&data, timer).map(|index| data.remove(index)) {
+        Some(mut entry) if entry.end.is_none() => {
+            if at < entry.start {
+                return Err(Error::msg(format!(
+                    "The session started at {}, can't switch before that",
+                    format::date_time(entry.start)
+                )));
+            }
+            entry.end = Some(at);
+            if SPLIT_MIDNIGHT.load(Ordering::SeqCst) {
+                data.extend(entry.split_at_midnight());
+            } else {
+                data.push(entry);
+            }
+        }
+        _ => return Err(Error::msg("No session is running, use `now` to start one")),
+    }
+    data.push(Tracker {
+        meta,
+        timer: timer.into(),
+        ..Tracker::start(objective, at)
+    });
+    data.sort_by_key(|entry| entry.start);
+    write(path, &data)?;
+    summary::print(path, timer)
+}
+
+/// Annotates the running session, noting objective changes as well.
+fn note(
+    path: &PathBuf,
+    timer: &str,
+    text: Option<String>,
+    objective: Option<String>,
+) -> Result<()> {
+    if text.is_none() && objective.is_none() {
+        return Err(Error::msg(
+            "Nothing to note, give a text or a new objective",
+        ));
+    }
+    let annotate = |entry: &mut Tracker| {
+        if entry.end.is_some() {
+            return Err(Error::msg("No session is running"));
+        }
+        if let Some(objective) = &objective {
+            let change = format!("objective: {} -> {}", entry.objective, objective);
+            entry.add_note(&change);
+            entry.objective = objective.clone();
+        }
+        if let Some(text) = &text {
+            entry.add_note(text);
+        }
+        Ok(())
+    };
+    if append::usable(path) {
+        if let Some(last) = append::last(path)? {
+            if last.entry.timer == timer {
+                let mut entry = last.entry.clone();
+                annotate(&mut entry)?;
+                return append::replace_last(path, last, &entry);
+            }
+        }
+    }
     let mut data = read(path)?;
-    if let Some(entry) = data.last_mut() {
+    let entry = last_of(&data, timer)
+        .map(|index| &mut data[index])
+        .ok_or_else(|| Error::msg("No session is running"))?;
+    annotate(entry)?;
+    write(path, &data)
+}
+
+/// Shows the running sessions of all timers, or only of `timer` if given.
+fn status(path: &PathBuf, timer: &str) -> Result<()> {
+    let running = read(path)?
+        .into_iter()
+        .filter(|entry| entry.end.is_none() && (timer.is_empty() || entry.timer == timer))
+        .collect::<Vec<_>>();
+    if QUIET.load(Ordering::SeqCst) {
+        // For scripts: whether a session is running is all that's asked
+        std::process::exit(if running.is_empty() { 1 } else { 0 });
+    }
+    if running.is_empty() {
+        println!("Not tracking");
+    }
+    for entry in running {
+        let duration = OffsetDateTime::now_local() - entry.start;
+        let name = if entry.timer.is_empty() {
+            String::new()
+        } else {
+            format!("[{}] ", entry.timer)
+        };
+        println!(
+            "{}Tracking since {} ({:02}:{:02}): {}",
+            name,
+            format::date_time(entry.start),
+            duration.whole_hours(),
+            duration.whole_minutes() % 60,
+            entry.objective
+        );
+        for (key, value) in &entry.meta {
+            println!("  {}={}", key, value);
+        }
+        for line in entry.notes.lines() {
+            println!("  {}", line);
+        }
+        if overdue(&entry) {
+            println!("The session has been open suspiciously long, did you forget to stop it?");
+        } else if let Some(warning) = long_session(duration) {
+            println!("{}", warning);
+        }
+    }
+    Ok(())
+}
+
+/// The new objective for stopping `entry`, if any: asks for one on a
+/// terminal if neither the command line nor the session has one.
+fn label(objective: Option<String>, entry: &Tracker) -> Result<Option<String>> {
+    let term = Term::stdout();
+    if objective.is_some() || !entry.objective.is_empty() || !term.features().is_attended() {
+        return Ok(objective);
+    }
+    term.write_str("What did you work on? ")?;
+    Ok(Some(term.read_line()?.trim().to_string()).filter(|o| !o.is_empty()))
+}
+
+/// Ends the running session. Its objective is only replaced if `objective`
+/// is given.
+fn stop(
+    path: &PathBuf,
+    timer: &str,
+    mut objective: Option<String>,
+    at: Option<OffsetDateTime>,
+    show: bool,
+) -> Result<()> {
+    let at = check_at(at)?;
+    let too_early = |entry: &Tracker| {
+        if at < entry.start {
+            Err(Error::msg(format!(
+                "The session started at {}, can't stop before that",
+                format::date_time(entry.start)
+            )))
+        } else {
+            Ok(())
+        }
+    };
+    if append::usable(path) {
+        match append::last(path)? {
+            Some(last) if last.entry.timer == timer => {
+                if last.entry.end.is_some() {
+                    return Err(Error::msg(
+                        "Last entry already finished. There was no work to track!",
+                    ));
+                }
+                too_early(&last.entry)?;
+                objective = label(objective, &last.entry)?;
+                let mut entry = last.entry.clone();
+                entry.end = Some(at);
+                if let Some(objective) = &objective {
+                    entry.objective = objective.clone();
+                }
+                let split =
+                    SPLIT_MIDNIGHT.load(Ordering::SeqCst) && entry.start.date() != at.date();
+                if !split {
+                    append::replace_last(path, last, &entry)?;
+                    if show {
+                        if let Some(warning) = long_session(at - entry.start) {
+                            println!("{}", warning);
+                        }
+                        summary::print(path, timer)?;
+                    }
+                    return Ok(());
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut data = read(path)?;
+    let index = last_of(&data, timer);
+    let mut warning = None;
+    if let Some(index) = index {
+        let entry = &mut data[index];
+        warning = long_session(at - entry.start);
         match entry.end {
             Some(_) => {
                 return Err(Error::msg(
@@ -199,99 +1511,609 @@ fn stop(path: &PathBuf, objective: String, show: bool) -> Result<()> {
                 ))
             }
             None => {
-                let end = OffsetDateTime::now_local();
-                entry.end = Some(end);
+                too_early(entry)?;
+                entry.end = Some(at);
             }
         }
-        entry.objective = objective;
+        if let Some(objective) = label(objective, entry)? {
+            entry.objective = objective;
+        }
+    }
+    if SPLIT_MIDNIGHT.load(Ordering::SeqCst) {
+        if let Some(index) = index {
+            let entry = data.remove(index);
+            data.extend(entry.split_at_midnight());
+            data.sort_by_key(|entry| entry.start);
+        }
     }
     write(path, &data)?;
     if show {
-        info(path, &None, false)?;
+        if let Some(warning) = warning {
+            println!("{}", warning);
+        }
+        summary::print(path, timer)?;
     }
     Ok(())
 }
-fn get_month_data(
-    data: Box<dyn Iterator<Item = Tracker>>,
-    delta: u8,
-) -> Box<dyn Iterator<Item = Tracker>> {
-    let current = OffsetDateTime::now_local();
-    let mut overflow = delta / 12; 
-    let delta = delta % 12 + 1;
-    // TOFIX: this is erroneous, b.c. months go from 1 - 12, but this can be 0 as well
-    let month = if let Some(month) = current.month().checked_sub(delta) { 
-        month + 1
+
+/// Renames the objective `old` to `new`, with `prefix` also in the
+/// objectives below `old`, e.g. `old/task`.
+fn rename_objective(path: &PathBuf, old: &str, new: &str, prefix: bool) -> Result<()> {
+    let mut data = read(path)?;
+    let mut renamed = 0;
+    for entry in &mut data {
+        let rest = match entry.objective.strip_prefix(old) {
+            Some(rest) if rest.is_empty() || (prefix && rest.starts_with('/')) => rest,
+            _ => continue,
+        };
+        entry.objective = format!("{}{}", new, rest);
+        renamed += 1;
+    }
+    if renamed > 0 {
+        confirm(&format!("Rename {} entries?", renamed))?;
+        write(path, &data)?;
+    }
+    println!("Renamed {} entries", renamed);
+    Ok(())
+}
+
+/// Fails if `entry` overlaps any of `data`, unless forced.
+fn check_overlaps(data: &[Tracker], entry: &Tracker, force: bool) -> Result<()> {
+    if entry.end.is_some_and(|end| end <= entry.start) {
+        return Err(Error::msg("The session has to end after it starts"));
+    }
+    let conflicts = data
+        .iter()
+        .filter(|other| other.overlaps(entry))
+        .collect::<Vec<_>>();
+    if !conflicts.is_empty() {
+        println!("The session overlaps");
+        for conflict in &conflicts {
+            println!("  {}", conflict);
+        }
+        if !force {
+            return Err(Error::msg(
+                "Refusing to save an overlapping session, pass --force to save it anyway",
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn add(path: &PathBuf, entry: Tracker, force: bool) -> Result<()> {
+    let mut data = read(path)?;
+    check_overlaps(&data, &entry, force)?;
+    println!("Added: {}", entry);
+    if SPLIT_MIDNIGHT.load(Ordering::SeqCst) {
+        data.extend(entry.split_at_midnight());
+    } else {
+        data.push(entry);
+    }
+    data.sort_by_key(|entry| entry.start);
+    write(path, &data)
+}
+
+/// The changes `edit` applies to an entry.
+#[derive(Debug, Default)]
+struct Change {
+    start: Option<OffsetDateTime>,
+    end: Option<OffsetDateTime>,
+    extend: Option<Duration>,
+    objective: Option<String>,
+    notes: Option<String>,
+    /// Edit the notes in $EDITOR
+    edit_notes: bool,
+    /// Keys to set, removed if the value is empty
+    meta: Vec<(String, String)>,
+}
+
+/// Lets the user edit `text` in $VISUAL or $EDITOR, vi or Notepad on
+/// Windows by default.
+fn external_editor(text: &str) -> Result<String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| if cfg!(windows) { "notepad" } else { "vi" }.into());
+    let mut words = editor.split_whitespace();
+    let program = words.next().ok_or_else(|| Error::msg("$EDITOR is empty"))?;
+    let file = std::env::temp_dir().join(format!("track-work-notes-{}.txt", std::process::id()));
+    let text = if text.is_empty() {
+        String::new()
     } else {
-        overflow += 1;
-        13 - (delta - current.month())
+        format!("{}\n", text)
     };
-    let year = current.year() - overflow as i32;
-    if debug() {
-        println!("Fetching data for {}-{}", year, month);
+    fs::write(&file, text)?;
+    let status = std::process::Command::new(program)
+        .args(words)
+        .arg(&file)
+        .status()
+        .with_context(|| format!("Could not run the editor {}", editor));
+    let edited = fs::read_to_string(&file);
+    fs::remove_file(&file)?;
+    if !status?.success() {
+        return Err(Error::msg("The editor failed, the notes were not changed"));
     }
+    Ok(edited?.trim_end().to_string())
+}
+
+/// Changes the `n`th entry counted back from the last one.
+fn edit(path: &PathBuf, n: usize, change: Change, force: bool) -> Result<()> {
+    let mut data = read(path)?;
+    let index = data
+        .len()
+        .checked_sub(n)
+        .filter(|_| n > 0)
+        .ok_or_else(|| Error::msg(format!("There is no entry {}", n)))?;
+    let mut entry = data.remove(index);
+    let old = entry.to_string();
+    if let Some(start) = change.start {
+        entry.start = start;
+    }
+    if let Some(end) = change.end {
+        entry.end = Some(end);
+    }
+    if let Some(extend) = change.extend {
+        let end = entry
+            .end
+            .ok_or_else(|| Error::msg("The session is still running, there is no end to move"))?;
+        entry.end = Some(end + extend);
+    }
+    if let Some(objective) = change.objective {
+        entry.objective = objective;
+    }
+    if let Some(notes) = change.notes {
+        entry.notes = notes;
+    }
+    if change.edit_notes {
+        entry.notes = external_editor(&entry.notes)?;
+    }
+    for (key, value) in change.meta {
+        if value.is_empty() {
+            entry.meta.remove(&key);
+        } else {
+            entry.meta.insert(key, value);
+        }
+    }
+    check_overlaps(&data, &entry, force)?;
+    println!("Changed: {}", old);
+    println!("     to: {}", entry);
+    data.push(entry);
+    data.sort_by_key(|entry| entry.start);
+    write(path, &data)
+}
+
+/// Splits the `n`th entry counted back from the last one at `at`, the
+/// second part may get another objective. Notes stay with the first part.
+fn split(path: &PathBuf, n: usize, at: OffsetDateTime, objective: Option<String>) -> Result<()> {
+    let mut data = read(path)?;
+    let index = data
+        .len()
+        .checked_sub(n)
+        .filter(|_| n > 0)
+        .ok_or_else(|| Error::msg(format!("There is no entry {}", n)))?;
+    let first = &mut data[index];
+    if at <= first.start || at >= first.end_or_now() {
+        return Err(Error::msg(format!(
+            "{} is not within the entry: {}",
+            format::date_time(at),
+            first
+        )));
+    }
+    let second = Tracker {
+        start: at,
+        notes: String::new(),
+        objective: objective.unwrap_or_else(|| first.objective.clone()),
+        ..first.clone()
+    };
+    first.end = Some(at);
+    println!("Split into: {}", first);
+    println!("       and: {}", second);
+    data.push(second);
+    data.sort_by_key(|entry| entry.start);
+    write(path, &data)
+}
+
+/// The year and month `delta` months before the current one.
+fn months_ago(delta: u32) -> (i32, u8) {
+    months_before(OffsetDateTime::now_local().date(), delta)
+}
+
+/// The year and month `delta` months before the month of `date`, whatever
+/// its day.
+fn months_before(date: Date, delta: u32) -> (i32, u8) {
+    let months = i64::from(date.year()) * 12 + i64::from(date.month()) - 1 - i64::from(delta);
+    (
+        months.div_euclid(12) as i32,
+        months.rem_euclid(12) as u8 + 1,
+    )
+}
+
+fn get_month_data(
+    data: Box<dyn Iterator<Item = Tracker>>,
+    (year, month): (i32, u8),
+) -> Box<dyn Iterator<Item = Tracker>> {
+    log::debug(|| format!("Fetching data for {}-{:02}", year, month));
     Box::new(data.filter(move |m| m.start.month() == month && m.start.year() == year))
 }
 
-fn compress(data: Box<dyn Iterator<Item = Tracker>>) -> Box<dyn Iterator<Item = (Date, Duration)>> {
+fn get_day_data(
+    data: Box<dyn Iterator<Item = Tracker>>,
+    date: Date,
+) -> Box<dyn Iterator<Item = Tracker>> {
+    log::debug(|| format!("Fetching data for {}", date.format("%F")));
+    Box::new(data.filter(move |m| m.start.date() == date))
+}
+
+/// The entries selected by the `info` subcommand.
+fn info_data(
+    info: &Info,
+    data: Box<dyn Iterator<Item = Tracker>>,
+) -> Box<dyn Iterator<Item = Tracker>> {
+    match info {
+        Info::Month {
+            date: Some(month), ..
+        } => get_month_data(data, *month),
+        Info::Month { delta, .. } => get_month_data(data, months_ago(delta.unwrap_or(0))),
+        Info::Today => get_day_data(data, OffsetDateTime::now_local().date()),
+        Info::Yesterday => get_day_data(data, OffsetDateTime::now_local().date().previous_day()),
+        Info::All => data,
+    }
+}
+
+/// The time worked per day, and whether it includes a running session.
+fn compress(
+    data: Box<dyn Iterator<Item = Tracker>>,
+) -> Box<dyn Iterator<Item = (Date, Duration, bool)>> {
     let mut map = HashMap::new();
     for entry in data {
-        let end = entry.end.unwrap_or_else(OffsetDateTime::now_local);
-        let duration = map
+        let (duration, running) = map
             .entry(entry.start.date())
-            .or_insert_with(|| Duration::new(0, 0));
-        *duration += end - entry.start;
+            .or_insert_with(|| (Duration::new(0, 0), false));
+        *duration += entry.end_or_now() - entry.start;
+        *running |= entry.end.is_none();
     }
-    Box::new(map.into_iter())
+    Box::new(
+        map.into_iter()
+            .map(|(date, (duration, running))| (date, duration, running)),
+    )
 }
 
-fn info(path: &PathBuf, info: &Option<Info>, uncompressed: bool) -> Result<()> {
-    let data = Box::new(read(path)?.into_iter());
-    let info = info.as_ref().unwrap_or(&Info::Month { delta: 0 });
-    if uncompressed {
-        let mut entries = match info {
-            Info::Month { delta } => get_month_data(data, *delta),
-            Info::All => data,
-        }.collect::<Vec<_>>();
-        entries.sort_by_key(|tracker| tracker.start);
-        println!("Date, Start, End, Duration, Objective");
-        let total = entries
-            .into_iter()
-            .inspect(|e| println!("{}", e))
-            .map(|e| e.end.unwrap_or_else(OffsetDateTime::now_local) - e.start)
-            .fold(Duration::new(0, 0), |acc, e| acc + e);
+/// `duration`, marked if it includes running sessions counted up to now.
+fn with_running(duration: Duration, running: bool) -> String {
+    if running {
+        format!(
+            "{} {}",
+            format::duration(duration),
+            theme::running("(running)")
+        )
+    } else {
+        format::duration(duration)
+    }
+}
+
+/// The groups of an entry's objective: the objective itself, or with
+/// `depth` each of its `/`-separated prefixes up to that many levels.
+fn objective_groups(objective: &str, depth: Option<usize>) -> Vec<String> {
+    let depth = match depth {
+        Some(depth) => depth,
+        None => return vec![objective.to_string()],
+    };
+    let levels = objective.split('/').map(str::trim).collect::<Vec<_>>();
+    (1..=depth.min(levels.len()))
+        .map(|level| levels[..level].join("/"))
+        .collect()
+}
+
+/// Prints the time per group, with `depth` objectives are rolled up their
+/// hierarchy so every prefix shows the time of everything below it.
+fn group(
+    data: Box<dyn Iterator<Item = Tracker>>,
+    by: &GroupBy,
+    depth: Option<usize>,
+    failure: &Failure,
+) -> Result<()> {
+    if depth.is_some() && !matches!(by, GroupBy::Objective) {
+        return Err(Error::msg("--depth only works with --by objective"));
+    }
+    let mut groups: HashMap<String, (Duration, bool)> = HashMap::new();
+    let mut total = Duration::zero();
+    let mut running = false;
+    for entry in data {
+        let keys = match by {
+            GroupBy::Device => vec![entry.device.clone()],
+            GroupBy::Timer => vec![entry.timer.clone()],
+            GroupBy::Objective => objective_groups(&entry.objective, depth),
+        };
+        let duration = entry.end_or_now() - entry.start;
+        for key in keys {
+            let group = groups
+                .entry(key)
+                .or_insert_with(|| (Duration::zero(), false));
+            group.0 += duration;
+            group.1 |= entry.end.is_none();
+        }
+        total += duration;
+        running |= entry.end.is_none();
+    }
+    check(failure)?;
+    let mut groups = groups.into_iter().collect::<Vec<_>>();
+    groups.sort();
+    let (header, empty) = match by {
+        GroupBy::Device => ("Device", "(unknown)"),
+        GroupBy::Timer => ("Timer", "(default)"),
+        GroupBy::Objective => ("Objective", "(none)"),
+    };
+    println!("{}, Duration", header);
+    for (key, (duration, running)) in groups {
+        let key = if key.is_empty() { empty } else { &key };
+        println!("{}, {}", key, with_running(duration, running));
+    }
+    println!("Total: {}", with_running(total, running));
+    Ok(())
+}
+
+/// How `info` presents the entries.
+#[derive(Debug, Default)]
+struct Report {
+    uncompressed: bool,
+    archives: bool,
+    by: Option<GroupBy>,
+    /// Levels of the objective hierarchy to group by
+    depth: Option<usize>,
+    gaps: bool,
+    /// Check the breaks against these rules
+    breaks: Option<breaks::Rules>,
+    notes: bool,
+    /// Only entries of this named timer
+    timer: Option<String>,
+    /// Only entries matching this filter
+    filter: Option<filter::Filter>,
+    /// Draw the time per day as bars
+    chart: bool,
+    /// The daily target marked in the chart
+    target: Option<Duration>,
+    /// Objective aliases applied to the entries
+    aliases: alias::Aliases,
+    /// Sessions shorter than this are hidden in the uncompressed view
+    min_duration: Option<Duration>,
+    /// Sessions longer than this are hidden in the uncompressed view
+    max_duration: Option<Duration>,
+    /// Print each session with this template instead
+    format: Option<format::Format>,
+    /// Whether running sessions count: always, never or, by default, in
+    /// the totals per day or group but not in the session list
+    running: Option<bool>,
+}
+
+/// Prints the idle periods between the sessions of each day.
+fn gaps(mut entries: Vec<Tracker>) {
+    entries.sort_by_key(|entry| entry.start);
+    println!("Date, Start, End, Gap");
+    let mut total = Duration::zero();
+    // The latest end so far, an entry may be contained in an earlier one.
+    let mut latest: Option<OffsetDateTime> = None;
+    for entry in entries {
+        let end = latest;
+        latest = match (latest, entry.end) {
+            (Some(latest), Some(end)) if latest > end => Some(latest),
+            (_, end) => end,
+        };
+        let (end, next) = match end {
+            Some(end) => (end, entry.start),
+            None => continue,
+        };
+        if next <= end || next.date() != end.date() {
+            continue;
+        }
+        let gap = next - end;
         println!(
-            "Total: {:02}:{:02}",
-            total.whole_hours(),
-            total.whole_minutes() % 60
+            "{}, {}, {}, {}",
+            format::date(end.date()),
+            format::time(end),
+            format::time(next),
+            format::duration(gap)
         );
+        total += gap;
+    }
+    println!("Total: {}", format::duration(total));
+}
+
+fn info(path: &PathBuf, info: &Option<Info>, report: &Report) -> Result<()> {
+    let mut data = stream(path)?;
+    if report.archives {
+        for archive in archive::archives(path)? {
+            data = Box::new(stream(&archive)?.chain(data));
+        }
+    }
+    if !report.aliases.is_empty() {
+        let aliases = report.aliases.clone();
+        data = Box::new(data.map(move |entry| {
+            entry.map(|entry| Tracker {
+                objective: aliases.apply(&entry.objective),
+                ..entry
+            })
+        }));
+    }
+    if let Some(timer) = report.timer.clone() {
+        data = Box::new(data.filter(move |entry| match entry {
+            Ok(entry) => entry.timer == timer,
+            Err(_) => true,
+        }));
+    }
+    if let Some(filter) = report.filter.clone() {
+        data = Box::new(data.filter(move |entry| match entry {
+            Ok(entry) => filter.matches(entry),
+            Err(_) => true,
+        }));
+    }
+    if report.running == Some(false) {
+        data = Box::new(data.filter(|entry| !matches!(entry, Ok(entry) if entry.end.is_none())));
+    }
+    let (data, failure) = until_invalid(data);
+    let info = info.as_ref().unwrap_or(&Info::Month {
+        delta: None,
+        date: None,
+    });
+    if let Some(by) = &report.by {
+        group(info_data(info, data), by, report.depth, &failure)?;
+    } else if report.gaps {
+        let entries = info_data(info, data).collect::<Vec<_>>();
+        check(&failure)?;
+        gaps(entries);
+    } else if let Some(rules) = &report.breaks {
+        let entries = info_data(info, data).collect::<Vec<_>>();
+        check(&failure)?;
+        breaks::report(entries, rules);
+    } else if report.uncompressed
+        || report.notes
+        || report.min_duration.is_some()
+        || report.max_duration.is_some()
+        || report.format.is_some()
+    {
+        let mut entries = info_data(info, data)
+            .filter(|entry| {
+                let duration = entry.end_or_now() - entry.start;
+                report.min_duration.is_none_or(|min| duration >= min)
+                    && report.max_duration.is_none_or(|max| duration <= max)
+            })
+            .collect::<Vec<_>>();
+        check(&failure)?;
+        entries.sort_by_key(|tracker| tracker.start);
+        if let Some(format) = &report.format {
+            for entry in entries {
+                println!("{}", format.render(&entry));
+            }
+            return Ok(());
+        }
+        let mut table =
+            table::Table::new(&["Date", "Start", "End", "Duration", "Objective"]).align_right(3);
+        let mut total = Duration::zero();
+        // Left out of the total, but shown
+        let mut running = None;
+        let mut reversed = 0;
+        let today = OffsetDateTime::now_local().date();
+        for entry in entries {
+            let duration = entry.end_or_now() - entry.start;
+            let date = format::date(entry.start.date());
+            let (end, shown) = match entry.end {
+                None => {
+                    if report.running == Some(true) {
+                        total += duration;
+                    }
+                    running = Some(running.unwrap_or_else(Duration::zero) + duration);
+                    (
+                        theme::running("running"),
+                        theme::running(&format::duration(duration)),
+                    )
+                }
+                Some(end) if end < entry.start => {
+                    reversed += 1;
+                    (
+                        theme::invalid(&format!("{} before start", format::time(end))),
+                        theme::invalid(&format!("-{}", format::duration(-duration))),
+                    )
+                }
+                Some(end) => {
+                    total += duration;
+                    (format::time(end), format::duration(duration))
+                }
+            };
+            table.row(vec![
+                if entry.start.date() == today {
+                    theme::today(&date)
+                } else {
+                    date
+                },
+                format::time(entry.start),
+                end,
+                shown,
+                theme::objective(&entry.objective),
+            ]);
+            if report.notes {
+                for line in entry.notes.lines() {
+                    table.line(format!("    {}", line));
+                }
+            }
+        }
+        table.print();
+        match running {
+            Some(running) if report.running == Some(true) => println!(
+                "Total: {}, including {} running up to now",
+                format::duration(total),
+                format::duration(running)
+            ),
+            Some(running) => {
+                println!("Total: {}", format::duration(total));
+                println!(
+                    "Running: {}, not in the total, see --include-running",
+                    format::duration(running)
+                );
+            }
+            None => println!("Total: {}", format::duration(total)),
+        }
+        if reversed > 0 {
+            println!(
+                "{} entries end before they start and are not in the total, see doctor --swap-reversed",
+                reversed
+            );
+        }
     } else {
-        let mut entries = match info {
-            Info::Month { delta } => compress(get_month_data(data, *delta)),
-            Info::All => compress(data),
-        }.collect::<Vec<_>>();
+        let mut entries = compress(info_data(info, data)).collect::<Vec<_>>();
+        check(&failure)?;
         entries.sort_by_key(|tracker| tracker.0);
+        if report.chart {
+            let days = entries
+                .iter()
+                .map(|(date, duration, _)| (*date, *duration))
+                .collect::<Vec<_>>();
+            chart::daily(&days, report.target);
+            return Ok(());
+        }
+        let today = OffsetDateTime::now_local().date();
+        let running = entries.iter().any(|(_, _, running)| *running);
         println!("Date, Duration");
         let total = entries
             .into_iter()
-            .inspect(|e| {
-                println!(
-                    "{}, {:02}:{:02}",
-                    e.0.format("%F"),
-                    e.1.whole_hours(),
-                    e.1.whole_minutes() % 60
-                )
+            .inspect(|(date, duration, running)| {
+                let mut text = format::date(*date);
+                if *date == today {
+                    text = theme::today(&text);
+                }
+                let hours = format::duration(*duration);
+                let hours = match report.target {
+                    Some(target) if *duration >= target => theme::reached(&hours),
+                    // Today may still reach it
+                    Some(_) if *date < today => theme::missed(&hours),
+                    _ => hours,
+                };
+                if *running {
+                    println!("{}, {} {}", text, hours, theme::running("(running)"))
+                } else {
+                    println!("{}, {}", text, hours)
+                }
             })
             .map(|e| e.1)
             .fold(Duration::new(0, 0), |acc, e| acc + e);
-        println!(
-            "Total: {:02}:{:02}",
-            total.whole_hours(),
-            total.whole_minutes() % 60
-        );
+        println!("Total: {}", with_running(total, running));
     }
     Ok(())
 }
 
+/// The daily work target from the `[targets]` config section, e.g. `daily = "8h"`.
+fn daily_target(config: &Config) -> Result<Option<Duration>> {
+    target(config, "daily")
+}
+
+/// A work target from the `[targets]` config section, `daily` or `monthly`.
+fn target(config: &Config, key: &str) -> Result<Option<Duration>> {
+    config
+        .get("targets", key)
+        .map(|value| {
+            human::parse_duration(value)
+                .with_context(|| format!("Invalid {} target: {}", key, value))
+        })
+        .transpose()
+}
+
 fn ctrl_channel() -> Result<Receiver<()>, ctrlc::Error> {
     let (sender, receiver) = bounded(100);
     ctrlc::set_handler(move || {
@@ -300,61 +2122,826 @@ fn ctrl_channel() -> Result<Receiver<()>, ctrlc::Error> {
     Ok(receiver)
 }
 
-fn live(path: &PathBuf, objective: String) -> Result<()> {
+/// Records SIGTERM and SIGHUP in `TERMINATED`, `live` and `daemon --systemd`
+/// check it on every tick.
+#[cfg(unix)]
+fn on_terminate() {
+    extern "C" fn handler(_: libc::c_int) {
+        TERMINATED.store(true, Ordering::SeqCst);
+    }
+    // SAFETY: the handler only stores to an atomic, which is async-signal-safe.
+    let handler = handler as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    unsafe {
+        libc::signal(libc::SIGTERM, handler);
+        libc::signal(libc::SIGHUP, handler);
+    }
+}
+
+/// Records closing the console window, logging off and shutting down in
+/// `TERMINATED`. Windows ends the process once the handler returns, so it
+/// waits a moment for `live` to stop the session; returning from `main`
+/// ends the process earlier.
+#[cfg(windows)]
+fn on_terminate() {
+    use winapi::shared::minwindef::{BOOL, DWORD, FALSE, TRUE};
+    use winapi::um::consoleapi::SetConsoleCtrlHandler;
+    use winapi::um::wincon::{CTRL_CLOSE_EVENT, CTRL_LOGOFF_EVENT, CTRL_SHUTDOWN_EVENT};
+
+    unsafe extern "system" fn handler(event: DWORD) -> BOOL {
+        match event {
+            CTRL_CLOSE_EVENT | CTRL_LOGOFF_EVENT | CTRL_SHUTDOWN_EVENT => {
+                TERMINATED.store(true, Ordering::SeqCst);
+                // Windows allows about five seconds after closing the console
+                std::thread::sleep(std::time::Duration::from_secs(4));
+                TRUE
+            }
+            // Ctrl-C is left to the ctrlc handler
+            _ => FALSE,
+        }
+    }
+    // SAFETY: the handler runs on its own thread and only touches an atomic.
+    unsafe { SetConsoleCtrlHandler(Some(handler), TRUE) };
+}
+
+#[cfg(not(any(unix, windows)))]
+fn on_terminate() {}
+
+/// What is being typed in `live`.
+enum Prompt {
+    Objective,
+    Note,
+}
+
+/// Shows the running session, or with `until` the time left until that much
+/// work has been tracked today. Keys: `p` pauses and resumes, `o` changes
+/// the objective, `n` adds a note, `d` detaches and `q` stops like Ctrl-C.
+/// With `detach` quitting leaves the session running as well.
+fn live(
+    path: &PathBuf,
+    timer: &str,
+    mut objective: String,
+    until: Option<Duration>,
+    detach: bool,
+) -> Result<()> {
     let data = read(path)?;
+    let today = OffsetDateTime::now_local().date();
+    let monday = today - Duration::days(today.weekday().number_days_from_monday().into());
+    // Finished sessions only, the running one is added on every tick.
+    let tracked_since = |since: Date| {
+        data.iter()
+            .filter(|entry| {
+                entry.timer == timer && entry.end.is_some() && entry.start.date() >= since
+            })
+            .fold(Duration::zero(), |total, entry| {
+                total + (entry.end_or_now() - entry.start)
+            })
+    };
+    let (mut tracked_today, mut tracked_week) = (tracked_since(today), tracked_since(monday));
     let term = Term::stdout();
     term.clear_screen()?;
-    let start_time = match data.last() {
+    let start_time = match last_of(&data, timer).map(|index| &data[index]) {
         Some(entry) if entry.end.is_none() => {
-            println!("Tracking work started at {}", entry.start.format("%F %R"));
+            println!(
+                "Tracking work started at {}",
+                format::date_time(entry.start)
+            );
+            if objective.is_empty() {
+                objective = entry.objective.clone();
+            }
             entry.start
         }
         Some(_) | None => {
             let start_time = OffsetDateTime::now_local();
-            println!("Tracking work starting now ({})", start_time.format("%F %R"));
-            start(path, "".into(), false)?;
+            println!(
+                "Tracking work starting now ({})",
+                format::date_time(start_time)
+            );
+            start(path, timer, objective.clone(), Meta::new(), None, false)?;
+            mqtt::event(path, timer, "started");
+            notify::event(
+                notify::Event::Started,
+                Some(objective.as_str())
+                    .filter(|o| !o.is_empty())
+                    .unwrap_or("no objective"),
+            );
             start_time
         }
     };
+    // The start of the running session, None while paused
+    let mut running = Some(start_time);
+    let keys = keys::Keys::start();
     let ctrl_c_events = ctrl_channel()?;
+    on_terminate();
     let ticks = tick(std::time::Duration::from_secs(1));
-    term.write_line("")?;
+    let mut warned = false;
+    let mut reached = false;
+    let mut input: Option<(Prompt, Vec<u8>)> = None;
+    let mut message = String::new();
+    let mut detached = detach;
+    // Since when the user is idle, the time after that is only kept if confirmed
+    let mut idle: Option<OffsetDateTime> = None;
+    let mut polls = 0u32;
+    let power = power::Monitor::start();
+    let mut suspended: Option<(power::Event, OffsetDateTime)> = None;
+    // A gap between ticks means the machine was suspended, even without logind
+    let mut last_tick = OffsetDateTime::now_local();
+    // Where the session ends when stopped because of suspending or locking
+    let mut stop_at: Option<OffsetDateTime> = None;
+    // The lines redrawn on every tick
+    const LINES: usize = 5;
+    term.write_str(&"\n".repeat(LINES))?;
     loop {
         select! {
-            recv(ticks) -> _ => {
-                term.move_cursor_up(1)?;
-                term.clear_line()?;
-                let duration = OffsetDateTime::now_local() - start_time;
-                let output = format!("Duration: {:02}:{:02}:{:02}",
-                    duration.whole_hours(),
-                    duration.whole_minutes()%60,
-                    duration.whole_seconds()%60);
-                term.write_line(&output)?;
+            recv(ticks) -> _ => {},
+            recv(keys.receiver()) -> key => {
+                let key = match key {
+                    Ok(key) => key,
+                    Err(_) => continue,
+                };
+                message.clear();
+                if let (Some(since), Some(started)) = (idle, running) {
+                    match key {
+                        b'y' => message = "Kept the idle time".into(),
+                        b'n' => {
+                            stop(path, timer, Some(objective.clone()), Some(since), false)?;
+                            tracked_today += since - started;
+                            tracked_week += since - started;
+                            start(path, timer, objective.clone(), Meta::new(), None, false)?;
+                            running = Some(OffsetDateTime::now_local());
+                            warned = false;
+                            message = "Discarded the idle time".into();
+                        }
+                        _ => continue,
+                    }
+                    idle = None;
+                    continue;
+                }
+                match input.as_mut() {
+                    Some((_, line)) => match key {
+                        b'\n' | b'\r' => {
+                            let (prompt, line) = input.take().expect("input is given");
+                            let line = String::from_utf8_lossy(&line).trim().to_string();
+                            match prompt {
+                                _ if line.is_empty() => {}
+                                Prompt::Objective => {
+                                    if running.is_some() {
+                                        note(path, timer, None, Some(line.clone()))?;
+                                    }
+                                    objective = line;
+                                    message = "Objective changed".into();
+                                }
+                                Prompt::Note if running.is_some() => {
+                                    note(path, timer, Some(line), None)?;
+                                    message = "Note added".into();
+                                }
+                                Prompt::Note => message = "Paused, resume to add notes".into(),
+                            }
+                        }
+                        // Escape
+                        0x1b => input = None,
+                        // Backspace
+                        0x7f | 0x08 => {
+                            let mut text = String::from_utf8_lossy(line).into_owned();
+                            text.pop();
+                            *line = text.into_bytes();
+                        }
+                        key => line.push(key),
+                    },
+                    None => match key {
+                        b'p' => match running.take() {
+                            Some(since) => {
+                                stop(path, timer, Some(objective.clone()), None, false)?;
+                                mqtt::event(path, timer, "paused");
+                                let duration = OffsetDateTime::now_local() - since;
+                                tracked_today += duration;
+                                tracked_week += duration;
+                                message = "Paused".into();
+                            }
+                            None => {
+                                start(path, timer, objective.clone(), Meta::new(), None, false)?;
+                                mqtt::event(path, timer, "resumed");
+                                running = Some(OffsetDateTime::now_local());
+                                warned = false;
+                                message = "Resumed".into();
+                            }
+                        },
+                        b'o' => input = Some((Prompt::Objective, Vec::new())),
+                        b'n' => input = Some((Prompt::Note, Vec::new())),
+                        b'd' => {
+                            detached = true;
+                            break;
+                        }
+                        b'q' => break,
+                        _ => {}
+                    },
+                }
             },
-            recv(ctrl_c_events) -> _ => {
-                println!();
-                println!("Tracking finished");
-                stop(path, objective, true)?;
-                break;
+            recv(power.receiver()) -> event => {
+                if let Ok(event) = event {
+                    suspended = Some((event, OffsetDateTime::now_local()));
+                }
+            },
+            recv(ctrl_c_events) -> _ => break,
+        }
+        if TERMINATED.load(Ordering::SeqCst) {
+            break;
+        }
+        let now = OffsetDateTime::now_local();
+        let event = suspended.take().or_else(|| {
+            Some((power::Event::Sleep, last_tick))
+                .filter(|_| now - last_tick > Duration::minutes(1))
+        });
+        last_tick = now;
+        mqtt::tick(path);
+        if let (Some((event, at)), Some(since)) = (event, running) {
+            let at = idle.map_or(at, |idle| idle.min(at));
+            match power::action(event) {
+                power::Action::Ignore => {}
+                power::Action::Pause => {
+                    stop(path, timer, Some(objective.clone()), Some(at), false)?;
+                    mqtt::event(path, timer, "paused");
+                    tracked_today += at - since;
+                    tracked_week += at - since;
+                    running = None;
+                    idle = None;
+                    message = match event {
+                        power::Event::Sleep => "Paused on suspend".into(),
+                        power::Event::Lock => "Paused on screen lock".into(),
+                    };
+                }
+                power::Action::Stop => {
+                    stop_at = Some(at);
+                    break;
+                }
+            }
+        }
+        if let (Some(threshold), Some(_), None, None) = (idle::threshold(), running, idle, &input) {
+            // Asking the system every tick would be wasteful
+            polls += 1;
+            if polls.is_multiple_of(5) {
+                if let Some(idle_time) = idle::idle_time().filter(|time| *time >= threshold) {
+                    idle = Some(now - idle_time);
+                    term.write_str("\x07")?;
+                }
             }
         }
+        let duration = running.map_or(Duration::zero(), |since| idle.unwrap_or(now) - since);
+        let clock = |d: Duration| {
+            format!(
+                "{:02}:{:02}:{:02}",
+                d.whole_hours(),
+                d.whole_minutes() % 60,
+                d.whole_seconds() % 60
+            )
+        };
+        let output = match until {
+            _ if running.is_none() => "Paused".to_string(),
+            _ if idle.is_some() => format!(
+                "Idle since {}, paused",
+                idle.map(format::time).unwrap_or_default()
+            ),
+            Some(until) => {
+                let left = until - tracked_today - duration;
+                if left > Duration::zero() {
+                    format!("Remaining: {}", clock(left))
+                } else {
+                    if !reached {
+                        reached = true;
+                        term.write_str("\x07")?;
+                        notify::send("Target reached", &format!("{} tracked today", clock(until)));
+                    }
+                    format!("Target reached, overtime: {}", clock(-left))
+                }
+            }
+            None => format!("Duration: {}", clock(duration)),
+        };
+        let last_line = match &input {
+            _ if idle.is_some() => "Keep the idle time? y/n".into(),
+            Some((Prompt::Objective, line)) => {
+                format!("New objective: {}", String::from_utf8_lossy(line))
+            }
+            Some((Prompt::Note, line)) => format!("Note: {}", String::from_utf8_lossy(line)),
+            None if !message.is_empty() => message.clone(),
+            None => running
+                .and(long_session(duration))
+                .unwrap_or_else(|| "p pause/resume, o objective, n note, d detach, q stop".into()),
+        };
+        term.clear_last_lines(LINES)?;
+        let shown = if objective.is_empty() {
+            "(none)"
+        } else {
+            &objective
+        };
+        term.write_line(&format!("Objective: {}", shown))?;
+        term.write_line(&output)?;
+        term.write_line(&format!("Today: {}", clock(tracked_today + duration)))?;
+        term.write_line(&format!("This week: {}", clock(tracked_week + duration)))?;
+        term.write_line(&last_line)?;
+        if !warned && notify::long_session().is_some_and(|max| duration >= max) {
+            warned = true;
+            notify::event(
+                notify::Event::LongSession,
+                &format!(
+                    "You've been working {}h{:02}m straight",
+                    duration.whole_hours(),
+                    duration.whole_minutes() % 60
+                ),
+            );
+        }
+    }
+    // Restores the terminal, stop may ask for an objective.
+    drop(keys);
+    // The terminal may be gone, so nothing is printed or asked for then.
+    let terminated = TERMINATED.load(Ordering::SeqCst);
+    if terminated {
+        detached = KEEP_ON_TERMINATE.load(Ordering::SeqCst);
+    } else {
+        println!();
+    }
+    if detached && running.is_some() {
+        if !terminated {
+            println!("Detached, the session keeps running. Run `live` again to reattach");
+        }
+        return Ok(());
+    }
+    if !terminated {
+        println!("Tracking finished");
+    }
+    if let Some(since) = running {
+        stop(
+            path,
+            timer,
+            Some(objective.clone()).filter(|o| terminated || !o.is_empty()),
+            stop_at.or(idle),
+            !terminated,
+        )?;
+        mqtt::event(path, timer, "stopped");
+        // Unconfirmed idle time is discarded
+        let duration = stop_at.or(idle).unwrap_or_else(OffsetDateTime::now_local) - since;
+        notify::event(
+            notify::Event::Stopped,
+            format!(
+                "{}h{:02}m {}",
+                duration.whole_hours(),
+                duration.whole_minutes() % 60,
+                objective
+            )
+            .trim_end(),
+        );
     }
     Ok(())
 }
 
 fn main() -> Result<()> {
-    let opts = Opt::from_args();
-    DEBUG.store(opts.debug, Ordering::SeqCst);
-    if debug() {
-        println!("{:?}", opts);
+    let matches = Opt::clap().get_matches();
+    let mut opts = Opt::from_clap(&matches);
+    journal::set_command(matches.subcommand_name().unwrap_or_default());
+    QUIET.store(opts.quiet, Ordering::SeqCst);
+    DRY_RUN.store(opts.dry_run, Ordering::SeqCst);
+    FORCE.store(opts.force, Ordering::SeqCst);
+    YES.store(opts.yes, Ordering::SeqCst);
+    SKIP_INVALID.store(opts.skip_invalid, Ordering::SeqCst);
+    let config_path = opts.config.take().unwrap_or_else(config::default_path);
+    if let Command::Init = opts.cmd {
+        return init::run(&config_path, opts.force);
+    }
+    let mut config = Config::load(&config_path)?;
+    if let Some(profile) = &opts.profile {
+        config = config.with_profile(profile)?;
+    }
+    let file = opts
+        .file
+        .take()
+        .or_else(|| {
+            let profile = format!("profile.{}", opts.profile.as_ref()?);
+            config.get(&profile, "file").map(PathBuf::from)
+        })
+        .or_else(|| std::env::var_os("TRACK_WORK_FILE").map(PathBuf::from))
+        .or_else(|| config.get("storage", "file").map(PathBuf::from))
+        .ok_or_else(|| {
+            Error::msg("No storage file, pass --file, set TRACK_WORK_FILE or run init")
+        })?;
+    let verbosity = if opts.debug { 2 } else { opts.verbose };
+    log::configure(&config, verbosity, opts.log_file.as_deref())?;
+    log::debug(|| format!("{:?}", opts));
+    if let Some(name) = &opts.template {
+        opts.objective = template::render(&config, name, &opts.var)?;
+    } else if !opts.var.is_empty() {
+        return Err(Error::msg("--var is only used with --template"));
+    }
+    if let Some(retention) = config.get("backup", "retention") {
+        let retention = retention
+            .parse()
+            .with_context(|| format!("Invalid backup retention: {}", retention))?;
+        backup::RETENTION.store(retention, Ordering::SeqCst);
+    }
+    if opts.encrypt || config.get("encryption", "enabled") == Some("true") {
+        crypt::enable(&config);
     }
+    SPLIT_MIDNIGHT.store(
+        config.get("sessions", "split_at_midnight") == Some("true"),
+        Ordering::SeqCst,
+    );
+    if let Some(hours) = config.get("sessions", "max_open_hours") {
+        let hours = hours
+            .parse()
+            .with_context(|| format!("Invalid max_open_hours: {}", hours))?;
+        MAX_OPEN_HOURS.store(hours, Ordering::SeqCst);
+    }
+    if let Some(hours) = config.get("sessions", "max_session_hours") {
+        let hours = hours
+            .parse()
+            .with_context(|| format!("Invalid max_session_hours: {}", hours))?;
+        MAX_SESSION_HOURS.store(hours, Ordering::SeqCst);
+    }
+    AUTO_CLOSE.store(
+        config.get("sessions", "auto_close") == Some("true"),
+        Ordering::SeqCst,
+    );
+    if let Some(minutes) = config.get("idle", "threshold_minutes") {
+        let minutes = minutes
+            .parse()
+            .with_context(|| format!("Invalid idle threshold: {}", minutes))?;
+        idle::THRESHOLD.store(minutes, Ordering::SeqCst);
+    }
+    power::configure(&config)?;
+    summary::configure(&config)?;
+    KEEP_ON_TERMINATE.store(
+        match config.get("sessions", "on_terminate") {
+            None | Some("stop") => false,
+            Some("keep") => true,
+            Some(other) => return Err(Error::msg(format!("Invalid on_terminate: {}", other))),
+        },
+        Ordering::SeqCst,
+    );
+    notify::configure(&config)?;
+    mqtt::configure(&config)?;
+    format::configure(&config, opts.duration_format)?;
+    theme::configure(&config, opts.color);
+    git::ENABLED.store(
+        config.get("git", "enabled") == Some("true"),
+        Ordering::SeqCst,
+    );
+    chain::ENABLED.store(
+        config.get("audit", "hash_chain") == Some("true"),
+        Ordering::SeqCst,
+    );
+    // Kept until the command is done, then waits for the pager
+    let _pager = match opts.cmd {
+        Command::Info { .. } | Command::Report(_) | Command::Search { .. } if !opts.no_pager => {
+            Some(pager::Pager::start())
+        }
+        _ => None,
+    };
     match opts.cmd {
-        Command::Now => start(&opts.file, opts.objective, true),
-        Command::Stop => stop(&opts.file, opts.objective, true),
-        Command::Live => live(&opts.file, opts.objective),
+        Command::Now { at, pick, meta } => {
+            let objective = if pick {
+                pick::objective(&read(&file)?)?.ok_or_else(|| Error::msg("No objective picked"))?
+            } else {
+                opts.objective
+            };
+            start(
+                &file,
+                &opts.timer,
+                objective,
+                meta.into_iter().collect(),
+                at,
+                true,
+            )?;
+            mqtt::event(&file, &opts.timer, "started");
+            Ok(())
+        }
+        Command::Stop {
+            at,
+            clear_objective,
+        } => {
+            let objective = if clear_objective {
+                Some(String::new())
+            } else {
+                Some(opts.objective).filter(|o| !o.is_empty())
+            };
+            stop(&file, &opts.timer, objective, at, true)?;
+            mqtt::event(&file, &opts.timer, "stopped");
+            Ok(())
+        }
+        Command::Continue { n, at } => {
+            resume(&file, &opts.timer, n, at)?;
+            mqtt::event(&file, &opts.timer, "started");
+            Ok(())
+        }
+        Command::Note { text } => note(
+            &file,
+            &opts.timer,
+            text,
+            Some(opts.objective).filter(|o| !o.is_empty()),
+        ),
+        Command::Status => status(&file, &opts.timer),
+        Command::Switch { at, meta } => {
+            switch(
+                &file,
+                &opts.timer,
+                opts.objective,
+                meta.into_iter().collect(),
+                at,
+            )?;
+            mqtt::event(&file, &opts.timer, "switched");
+            Ok(())
+        }
+        Command::Live {
+            until,
+            detach,
+            pomodoro,
+        } => match pomodoro {
+            Some(pomodoro) => pomodoro::run(&file, &opts.timer, opts.objective, pomodoro),
+            None => {
+                let until = match until.as_deref() {
+                    Some("target") => Some(daily_target(&config)?.ok_or_else(|| {
+                        Error::msg("No daily target set in the [targets] config section")
+                    })?),
+                    Some(until) => Some(human::parse_duration(until)?),
+                    None => None,
+                };
+                live(&file, &opts.timer, opts.objective, until, detach)
+            }
+        },
+        Command::Daemon { systemd } => match schedule::Schedule::load(&config)? {
+            Some(schedule) => schedule::daemon(&file, &opts.timer, &schedule, systemd),
+            None => Err(Error::msg(
+                "No working hours set, add start and end to the [schedule] config section",
+            )),
+        },
+        Command::InstallService { ics } => systemd::install(
+            &file,
+            &config_path,
+            opts.profile.as_deref(),
+            &opts.timer,
+            ics.as_deref(),
+        ),
+        Command::Hook(Hook::Install) => hook::install(&file, &opts.timer, opts.force),
+        Command::Hook(Hook::Uninstall) => hook::uninstall(),
+        Command::Remind => match schedule::Schedule::load(&config)? {
+            Some(schedule) => schedule::remind(&file, &opts.timer, &schedule).map(|_| ()),
+            None => Err(Error::msg(
+                "No working hours set, add start and end to the [schedule] config section",
+            )),
+        },
+        Command::Add {
+            start,
+            end,
+            at,
+            duration,
+            meta,
+        } => {
+            // With --at the only argument is the end
+            let (start, end) = match (at, start, end) {
+                (Some(at), end, None) => (at, end),
+                (None, Some(start), end) => (start, end),
+                _ => return Err(Error::msg("Give the start either as argument or via --at")),
+            };
+            let end = match (end, duration) {
+                (Some(end), None) => end,
+                (None, Some(duration)) => start + duration,
+                _ => return Err(Error::msg("Give either an end time or --duration")),
+            };
+            add(
+                &file,
+                Tracker {
+                    device: device::name(),
+                    meta: meta.into_iter().collect(),
+                    timer: opts.timer,
+                    ..Tracker::new(start, Some(end), opts.objective)
+                },
+                opts.force,
+            )
+        }
+        Command::Recur { from, to } => {
+            let from = from.unwrap_or_else(|| OffsetDateTime::now_local().date());
+            recur::materialize(&file, &config, from, to.unwrap_or(from), opts.force)
+        }
+        Command::Join {
+            entries,
+            gap_below,
+            same_objective,
+        } => match gap_below {
+            Some(gap) => join::join_gaps(&file, gap, same_objective),
+            None => join::join(
+                &file,
+                entries[0],
+                entries[1],
+                Some(opts.objective).filter(|o| !o.is_empty()),
+                opts.force,
+            ),
+        },
+        Command::Dedupe {
+            tolerance,
+            overlapping,
+        } => dedupe::dedupe(&file, tolerance, overlapping),
+        Command::Split { n, at, objective_b } => split(&file, n, at, objective_b),
+        Command::Edit {
+            n,
+            start,
+            end,
+            extend,
+            note,
+            edit_notes,
+            meta,
+        } => edit(
+            &file,
+            n,
+            Change {
+                start,
+                end,
+                extend,
+                objective: Some(opts.objective).filter(|o| !o.is_empty()),
+                notes: note,
+                edit_notes,
+                meta,
+            },
+            opts.force,
+        ),
         Command::Info {
             uncompressed,
+            archives,
+            by,
+            depth,
+            gaps,
+            breaks,
+            notes,
+            chart,
+            min_duration,
+            max_duration,
+            filter,
+            format,
+            include_running,
+            exclude_running,
             info: info_level,
-        } => info(&opts.file, &info_level, uncompressed),
+        } => info(
+            &file,
+            &info_level,
+            &Report {
+                uncompressed,
+                archives,
+                by,
+                depth,
+                gaps,
+                breaks: if breaks {
+                    Some(breaks::Rules::load(&config)?)
+                } else {
+                    None
+                },
+                notes,
+                timer: Some(opts.timer).filter(|t| !t.is_empty()),
+                filter,
+                aliases: alias::Aliases::load(&config)?,
+                chart,
+                target: daily_target(&config)?,
+                min_duration,
+                max_duration,
+                format: format
+                    .map(|format| format::Format::load(&config, &format))
+                    .transpose()?,
+                running: match (include_running, exclude_running) {
+                    (true, _) => Some(true),
+                    (_, true) => Some(false),
+                    _ => None,
+                },
+            },
+        ),
+        Command::BulkEdit { filter, set } => bulk::bulk_edit(&file, &filter, &set),
+        Command::RenameObjective { old, new, prefix } => {
+            rename_objective(&file, &old, &new, prefix)
+        }
+        Command::Report(ReportKind::Heatmap { year }) => {
+            report::heatmap(&file, year, daily_target(&config)?)
+        }
+        Command::Report(ReportKind::Distribution { period }) => {
+            report::distribution(&file, period.from, period.to)
+        }
+        Command::Report(ReportKind::Stats { period }) => {
+            report::stats(&file, period.from, period.to)
+        }
+        Command::Report(ReportKind::Compare { periods }) => {
+            report::compare(&file, &periods[0], &periods[1])
+        }
+        Command::Report(ReportKind::Budget) => report::budget(&file, &config),
+        Command::Report(ReportKind::Estimates) => estimate::report(&file),
+        Command::Report(ReportKind::Plan { week }) => plan::report(&file, week),
+        Command::Plan(Plan::Set {
+            name,
+            planned,
+            week,
+        }) => plan::set(&file, week, &name, planned),
+        Command::Plan(Plan::Remove { name, week }) => plan::remove(&file, week, &name),
+        Command::Report(ReportKind::Streaks) => {
+            report::streaks(&file, &config, schedule::workdays(&config)?)
+        }
+        Command::Report(ReportKind::Forecast { target, days }) => {
+            let target = match target {
+                Some(target) => target,
+                None => self::target(&config, "monthly")?.ok_or_else(|| {
+                    Error::msg("Give --target or set monthly in the [targets] config section")
+                })?,
+            };
+            report::forecast(&file, target, schedule::workdays(&config)?, days)
+        }
+        Command::Estimate(Estimate::Set { name, estimate }) => {
+            estimate::set(&file, &name, estimate)
+        }
+        Command::Estimate(Estimate::Remove { name }) => estimate::remove(&file, &name),
+        Command::Export { anonymize, filter } => {
+            export::export(&file, &config, anonymize, filter.as_ref())
+        }
+        Command::Search { pattern, regex } => {
+            search::search(&file, &search::Pattern::new(&pattern, regex)?)
+        }
+        Command::Archive { before } => {
+            let cutoff = before.unwrap_or_else(|| {
+                Date::try_from_yo(OffsetDateTime::now_local().year(), 1).expect("valid date")
+            });
+            archive::archive(&file, cutoff)
+        }
+        Command::Purge { before } => purge::purge(&file, before),
+        Command::Merge { other, strategy } => merge::merge(&file, &other, strategy),
+        Command::Undo => journal::undo(&file),
+        Command::Verify => chain::verify(&file),
+        Command::Migrate => schema::migrate(&file),
+        Command::Doctor {
+            drop_invalid,
+            swap_reversed,
+            split_midnight,
+            overlaps,
+        } => doctor::doctor(
+            &file,
+            &doctor::Fixes {
+                drop_invalid,
+                swap_reversed,
+                split_midnight,
+            },
+            overlaps,
+        ),
+        Command::Lock { until } => match until {
+            Some(until) => lock::set(&file, until),
+            None => lock::show(&file),
+        },
+        Command::Restore { backup } => backup::restore(&file, backup.as_deref()),
+        Command::Sync { target } => match target {
+            Sync::Git => git::sync(&file),
+            Sync::Remote => remote::sync(&file, &config),
+            Sync::Caldav {
+                push_only,
+                pull_only,
+            } => caldav::sync(&file, &config, !pull_only, !push_only),
+        },
+        Command::Serve { ics, notes, bind } => serve::serve(&file, &bind, ics, notes),
+        Command::Dbus => dbus::service(&file, &opts.timer),
+        Command::Import {
+            format,
+            file: source,
+            merge_threshold,
+            from,
+            to,
+            map,
+            time_format,
+            delimiter,
+            no_headers,
+        } => {
+            let today = OffsetDateTime::now_local().date();
+            let options = import::Options {
+                merge_threshold: Duration::minutes(merge_threshold),
+                from: from.unwrap_or(today),
+                to: to.unwrap_or(today),
+                map,
+                time_format,
+                delimiter,
+                has_headers: !no_headers,
+            };
+            import::import(&file, &config, &format, source.as_deref(), &options)
+        }
+        Command::Init => unreachable!("init runs before the config is loaded"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u8, day: u8) -> Date {
+        Date::try_from_ymd(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn months_before_wrap_around_years() {
+        assert_eq!(months_before(date(2024, 5, 15), 0), (2024, 5));
+        assert_eq!(months_before(date(2024, 1, 15), 1), (2023, 12));
+        assert_eq!(months_before(date(2024, 3, 1), 14), (2023, 1));
+        assert_eq!(months_before(date(2024, 12, 1), 24), (2022, 12));
+    }
+
+    #[test]
+    fn months_before_ignore_the_day() {
+        assert_eq!(months_before(date(2024, 3, 31), 1), (2024, 2));
+        assert_eq!(months_before(date(2023, 3, 31), 1), (2023, 2));
+        assert_eq!(months_before(date(2024, 5, 31), 1), (2024, 4));
+        assert_eq!(months_before(date(2024, 1, 31), 2), (2023, 11));
     }
 }
@@ -0,0 +1,127 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::{Error, Result};
+use console::Term;
+
+use crate::{read, write, Tracker};
+
+/// How to resolve an entry of the other file that overlaps local entries.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strategy {
+    Ask,
+    Ours,
+    Theirs,
+    Both,
+}
+
+impl FromStr for Strategy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "ask" => Ok(Strategy::Ask),
+            "ours" => Ok(Strategy::Ours),
+            "theirs" => Ok(Strategy::Theirs),
+            "both" => Ok(Strategy::Both),
+            _ => Err(Error::msg(format!("Unknown merge strategy: {}", s))),
+        }
+    }
+}
+
+fn same(a: &Tracker, b: &Tracker) -> bool {
+    a.start.timestamp() == b.start.timestamp()
+        && a.end.map(|e| e.timestamp()) == b.end.map(|e| e.timestamp())
+        && a.objective == b.objective
+}
+
+fn ask(term: &Term, theirs: &Tracker, ours: &[&Tracker]) -> Result<Strategy> {
+    term.write_line("Conflict, the merged entry")?;
+    term.write_line(&format!("  + {}", theirs))?;
+    term.write_line("overlaps")?;
+    for entry in ours {
+        term.write_line(&format!("  - {}", entry))?;
+    }
+    loop {
+        term.write_str("Keep [o]urs, [t]heirs or [b]oth? ")?;
+        let answer = term.read_line()?;
+        match answer.trim() {
+            "o" | "ours" => return Ok(Strategy::Ours),
+            "t" | "theirs" => return Ok(Strategy::Theirs),
+            "b" | "both" => return Ok(Strategy::Both),
+            _ => {}
+        }
+    }
+}
+
+/// What a merge did to the local entries.
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub added: usize,
+    pub duplicates: usize,
+    pub replaced: usize,
+}
+
+/// Merges `theirs` into `data`. Exact duplicates are dropped, overlapping
+/// entries are resolved by `strategy`.
+pub fn merge_entries(
+    data: &mut Vec<Tracker>,
+    theirs: Vec<Tracker>,
+    strategy: Strategy,
+) -> Result<Summary> {
+    let term = Term::stdout();
+    if strategy == Strategy::Ask && !term.features().is_attended() {
+        return Err(Error::msg(
+            "Cannot ask how to resolve conflicts without a terminal, use --strategy",
+        ));
+    }
+    let mut summary = Summary::default();
+    for entry in theirs {
+        if data.iter().any(|ours| same(ours, &entry)) {
+            summary.duplicates += 1;
+            continue;
+        }
+        let conflicts = data
+            .iter()
+            .filter(|ours| ours.overlaps(&entry))
+            .collect::<Vec<_>>();
+        let resolution = match (conflicts.is_empty(), strategy) {
+            (true, _) => Strategy::Both,
+            (false, Strategy::Ask) => ask(&term, &entry, &conflicts)?,
+            (false, strategy) => strategy,
+        };
+        match resolution {
+            Strategy::Ours => continue,
+            Strategy::Theirs => {
+                let before = data.len();
+                data.retain(|ours| !ours.overlaps(&entry));
+                summary.replaced += before - data.len();
+            }
+            Strategy::Both | Strategy::Ask => {
+                if !conflicts.is_empty() {
+                    eprintln!("Warning: kept both overlapping entries");
+                    eprintln!("  + {}", entry);
+                    for ours in &conflicts {
+                        eprintln!("  - {}", ours);
+                    }
+                }
+            }
+        }
+        data.push(entry);
+        summary.added += 1;
+    }
+    data.sort_by_key(|entry| entry.start);
+    Ok(summary)
+}
+
+/// Merges the entries of `other` into the storage file.
+pub fn merge(path: &PathBuf, other: &PathBuf, strategy: Strategy) -> Result<()> {
+    let mut data = read(path)?;
+    let summary = merge_entries(&mut data, read(other)?, strategy)?;
+    write(path, &data)?;
+    println!(
+        "Merged {} entries, skipped {} duplicates, replaced {} local entries",
+        summary.added, summary.duplicates, summary.replaced
+    );
+    Ok(())
+}
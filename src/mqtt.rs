@@ -0,0 +1,190 @@
+//! Publishing events and the tracking state to an MQTT broker via the
+//! `mosquitto_pub` executable, configured in the `[mqtt]` config section,
+//! e.g. so home automation can turn on a focus light.
+//!
+//! Events go to `<topic>/event`, the state is retained at `<topic>/state`
+//! and published again every `state_interval_seconds` by `live` and
+//! `daemon`.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use anyhow::{Context, Error, Result};
+use time::OffsetDateTime;
+
+use crate::config::Config;
+use crate::json::Value;
+use crate::{last_of, log, read, Tracker};
+
+struct Settings {
+    host: String,
+    port: Option<String>,
+    user: Option<String>,
+    topic: String,
+    interval: u64,
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+/// When the state was last published
+static PUBLISHED: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Enables publishing if a `host` is configured.
+pub fn configure(config: &Config) -> Result<()> {
+    let host = match config.get("mqtt", "host") {
+        Some(host) => host.to_string(),
+        None => return Ok(()),
+    };
+    let interval = config
+        .get("mqtt", "state_interval_seconds")
+        .map(|s| {
+            s.parse::<u64>()
+                .with_context(|| format!("Invalid state_interval_seconds: {}", s))
+        })
+        .transpose()?
+        .unwrap_or(60);
+    // mosquitto_pub only takes it on the command line, where every user
+    // could read it.
+    if config.get("mqtt", "password").is_some() {
+        return Err(Error::msg(
+            "The MQTT password is not read from the config, put it into mosquitto_pub's own \
+             ~/.config/mosquitto_pub as -P <password> instead",
+        ));
+    }
+    let _ = SETTINGS.set(Settings {
+        host,
+        port: config.get("mqtt", "port").map(str::to_string),
+        user: config.get("mqtt", "user").map(str::to_string),
+        topic: config
+            .get("mqtt", "topic")
+            .unwrap_or("track-work")
+            .trim_end_matches('/')
+            .to_string(),
+        interval,
+    });
+    Ok(())
+}
+
+fn publish(settings: &Settings, topic: &str, message: &str, retain: bool) -> Result<()> {
+    let topic = format!("{}/{}", settings.topic, topic);
+    let _span = log::span(|| format!("mosquitto_pub {}", topic));
+    let mut command = Command::new("mosquitto_pub");
+    command.arg("-h").arg(&settings.host);
+    if let Some(port) = &settings.port {
+        command.arg("-p").arg(port);
+    }
+    if let Some(user) = &settings.user {
+        command.arg("-u").arg(user);
+    }
+    if retain {
+        command.arg("-r");
+    }
+    log::debug(|| format!("Publishing {}", message));
+    let output = command
+        .arg("-t")
+        .arg(&topic)
+        .arg("-m")
+        .arg(message)
+        .stdin(Stdio::null())
+        .output()
+        .context("Could not run mosquitto_pub, is it installed?")?;
+    if !output.status.success() {
+        return Err(Error::msg(format!(
+            "mosquitto_pub failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+fn session(entry: &Tracker) -> BTreeMap<String, Value> {
+    let mut session = BTreeMap::new();
+    session.insert("objective".into(), Value::String(entry.objective.clone()));
+    session.insert("timer".into(), Value::String(entry.timer.clone()));
+    session.insert("start".into(), Value::String(entry.start.format("%FT%T%z")));
+    if let Some(end) = entry.end {
+        session.insert("end".into(), Value::String(end.format("%FT%T%z")));
+    }
+    session
+}
+
+/// Publishes the running sessions as the retained state, like
+/// `{"tracking":true,"objective":"review","sessions":[...]}`.
+fn publish_state(settings: &Settings, path: &PathBuf) -> Result<()> {
+    let data = read(path)?;
+    let running = data
+        .iter()
+        .filter(|entry| entry.end.is_none())
+        .collect::<Vec<_>>();
+    let now = OffsetDateTime::now_local();
+    let mut state = BTreeMap::new();
+    state.insert("tracking".into(), Value::Bool(!running.is_empty()));
+    if let Some(first) = running.first() {
+        state.insert("objective".into(), Value::String(first.objective.clone()));
+    }
+    let sessions = running
+        .iter()
+        .map(|entry| {
+            let mut session = session(entry);
+            let seconds = (now - entry.start).whole_seconds();
+            session.insert("seconds".into(), Value::Number(seconds as f64));
+            Value::Object(session)
+        })
+        .collect();
+    state.insert("sessions".into(), Value::Array(sessions));
+    publish(settings, "state", &Value::Object(state).to_string(), true)?;
+    if let Ok(mut published) = PUBLISHED.lock() {
+        *published = Some(Instant::now());
+    }
+    Ok(())
+}
+
+/// Publishes `event`, e.g. started, stopped or paused, with the last
+/// session of `timer`, and the new state. Failures are only printed, the
+/// change itself is saved already.
+pub fn event(path: &PathBuf, timer: &str, event: &str) {
+    let settings = match SETTINGS.get() {
+        Some(settings) if !crate::dry_run() => settings,
+        _ => return,
+    };
+    let result = read(path).and_then(|data| {
+        let mut message = last_of(&data, timer)
+            .map(|index| session(&data[index]))
+            .unwrap_or_default();
+        message.insert("event".into(), Value::String(event.into()));
+        publish(
+            settings,
+            "event",
+            &Value::Object(message).to_string(),
+            false,
+        )?;
+        publish_state(settings, path)
+    });
+    if let Err(e) = result {
+        eprintln!("Could not publish to MQTT: {:#}", e);
+    }
+}
+
+/// Publishes the state again if the interval has passed, called regularly
+/// by long-running commands.
+pub fn tick(path: &PathBuf) {
+    let settings = match SETTINGS.get() {
+        Some(settings) if settings.interval > 0 => settings,
+        _ => return,
+    };
+    let due = PUBLISHED
+        .lock()
+        .map(|published| published.is_none_or(|at| at.elapsed().as_secs() >= settings.interval))
+        .unwrap_or(false);
+    if due {
+        if let Err(e) = publish_state(settings, path) {
+            log::info(|| format!("Could not publish the MQTT state: {:#}", e));
+            // Try again at the next interval instead of every tick
+            if let Ok(mut published) = PUBLISHED.lock() {
+                *published = Some(Instant::now());
+            }
+        }
+    }
+}
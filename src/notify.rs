@@ -0,0 +1,89 @@
+//! Desktop notifications via `notify-send` on Linux and `osascript` on macOS.
+
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result};
+use time::Duration;
+
+use crate::config::Config;
+use crate::log;
+
+/// Events that notify if enabled in the `[notifications]` config section.
+#[derive(Debug, Clone, Copy)]
+pub enum Event {
+    Started,
+    Stopped,
+    LongSession,
+}
+
+#[derive(Debug, Default)]
+struct Settings {
+    started: bool,
+    stopped: bool,
+    /// Notify once a session has been running this long
+    long_session: Option<Duration>,
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// Reads which events notify, all are disabled by default.
+pub fn configure(config: &Config) -> Result<()> {
+    let long_session = match config.get("notifications", "long_session_hours") {
+        Some(hours) => {
+            let hours = hours
+                .parse::<f64>()
+                .with_context(|| format!("Invalid long_session_hours: {}", hours))?;
+            Some(Duration::seconds((hours * 3600.0) as i64)).filter(|d| *d > Duration::zero())
+        }
+        None => None,
+    };
+    let _ = SETTINGS.set(Settings {
+        started: config.get("notifications", "started") == Some("true"),
+        stopped: config.get("notifications", "stopped") == Some("true"),
+        long_session,
+    });
+    Ok(())
+}
+
+/// How long a session may run before `Event::LongSession` notifies.
+pub fn long_session() -> Option<Duration> {
+    SETTINGS.get().and_then(|settings| settings.long_session)
+}
+
+/// Notifies about `event` if it is enabled.
+pub fn event(event: Event, body: &str) {
+    let settings = match SETTINGS.get() {
+        Some(settings) => settings,
+        None => return,
+    };
+    let (enabled, summary) = match event {
+        Event::Started => (settings.started, "Tracking started"),
+        Event::Stopped => (settings.stopped, "Session stopped"),
+        Event::LongSession => (settings.long_session.is_some(), "Time for a break"),
+    };
+    if enabled {
+        send(summary, body);
+    }
+}
+
+/// Shows a desktop notification, failures are ignored since notifications
+/// are a convenience only.
+pub fn send(summary: &str, body: &str) {
+    let mut command = if cfg!(target_os = "macos") {
+        let script = format!("display notification {:?} with title {:?}", body, summary);
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(script);
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.arg("--app-name=track-work").arg(summary).arg(body);
+        command
+    };
+    let result = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    log::info(|| format!("Notification {:?}: {:?}", summary, result));
+}
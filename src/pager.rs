@@ -0,0 +1,80 @@
+//! Long output through `$PAGER`, like git does it: stdout is pointed at the
+//! pager until the guard is dropped.
+
+use std::io::Write;
+use std::process::Child;
+#[cfg(unix)]
+use std::process::{Command, Stdio};
+
+/// Pages stdout while alive, waits for the pager when dropped.
+pub struct Pager {
+    child: Option<Child>,
+    /// The terminal stdout pointed to before
+    #[cfg(unix)]
+    original: libc::c_int,
+}
+
+impl Pager {
+    /// Starts `$PAGER`, `less` by default, if stdout is a terminal. An empty
+    /// `PAGER` or `cat` turns paging off.
+    #[cfg(unix)]
+    pub fn start() -> Pager {
+        let none = Pager {
+            child: None,
+            original: -1,
+        };
+        if !console::Term::stdout().is_term() {
+            return none;
+        }
+        let pager = std::env::var("PAGER").unwrap_or_else(|_| "less".to_string());
+        if pager.trim().is_empty() || pager.trim() == "cat" {
+            return none;
+        }
+        // Keep the colors decided for the terminal, stdout becomes a pipe
+        console::set_colors_enabled(console::colors_enabled());
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(&pager).stdin(Stdio::piped());
+        if std::env::var_os("LESS").is_none() {
+            // Quit if it fits on one screen, pass colors through, keep the output
+            command.env("LESS", "FRX");
+        }
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(_) => return none,
+        };
+        let stdin = child.stdin.take().expect("stdin is piped");
+        use std::os::unix::io::AsRawFd;
+        // SAFETY: dup and dup2 only get open file descriptors, the pipe
+        // stays open as stdout when `stdin` is dropped.
+        let original = unsafe {
+            let original = libc::dup(libc::STDOUT_FILENO);
+            libc::dup2(stdin.as_raw_fd(), libc::STDOUT_FILENO);
+            original
+        };
+        Pager {
+            child: Some(child),
+            original,
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn start() -> Pager {
+        Pager { child: None }
+    }
+}
+
+impl Drop for Pager {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = std::io::stdout().flush();
+            #[cfg(unix)]
+            // SAFETY: restores the stdout saved in `start`, which closes the
+            // pipe so the pager sees the end of the output.
+            unsafe {
+                libc::dup2(self.original, libc::STDOUT_FILENO);
+                libc::close(self.original);
+            }
+            let _ = child.wait();
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! An interactive picker for previously used objectives: typing filters the
+//! list, the arrow keys move the selection and enter confirms it. Unless the
+//! typed text is an objective already, it is offered as a new one last.
+
+use std::collections::HashSet;
+
+use anyhow::{Error, Result};
+use console::{Key, Term};
+
+use crate::Tracker;
+
+/// How many objectives are shown at once.
+const SHOWN: usize = 10;
+
+/// The unique objectives of `data`, most recently used first.
+fn recent(data: &[Tracker]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    data.iter()
+        .rev()
+        .map(|entry| entry.objective.trim())
+        .filter(|objective| !objective.is_empty() && seen.insert(*objective))
+        .map(String::from)
+        .collect()
+}
+
+/// Whether the characters of `query` appear in `candidate` in order.
+fn matches(candidate: &str, query: &str) -> bool {
+    let mut chars = candidate.chars().flat_map(char::to_lowercase);
+    query
+        .chars()
+        .flat_map(char::to_lowercase)
+        .all(|q| chars.any(|c| c == q))
+}
+
+fn render(
+    term: &Term,
+    query: &str,
+    items: &[&String],
+    new: bool,
+    selected: usize,
+) -> Result<usize> {
+    term.write_line(&format!("Objective: {}", query))?;
+    let marker = |i| if i == selected { ">" } else { " " };
+    for (i, item) in items.iter().enumerate().take(SHOWN) {
+        term.write_line(&format!("{} {}", marker(i), item))?;
+    }
+    let shown = items.len().min(SHOWN);
+    if new {
+        term.write_line(&format!("{} new: {}", marker(shown), query.trim()))?;
+    }
+    Ok(shown + new as usize + 1)
+}
+
+/// Lets the user pick one of the objectives used in `data` or type a new
+/// one, `None` if the picker was cancelled with escape.
+pub fn objective(data: &[Tracker]) -> Result<Option<String>> {
+    let term = Term::stdout();
+    if !term.features().is_attended() {
+        return Err(Error::msg("Picking an objective requires a terminal"));
+    }
+    let objectives = recent(data);
+    let mut query = String::new();
+    let mut selected = 0;
+    let mut lines = 0;
+    loop {
+        let items = objectives
+            .iter()
+            .filter(|objective| matches(objective, &query))
+            .collect::<Vec<_>>();
+        // A typed objective that is a subsequence of a known one, e.g. `api`
+        // of `rapid-ui`, could otherwise never be entered
+        let new = !query.trim().is_empty() && !objectives.iter().any(|o| o == query.trim());
+        let shown = items.len().min(SHOWN);
+        selected = selected.min((shown + new as usize).saturating_sub(1));
+        term.clear_last_lines(lines)?;
+        lines = render(&term, &query, &items, new, selected)?;
+        match term.read_key()? {
+            Key::Char(c) => query.push(c),
+            Key::Backspace => {
+                query.pop();
+            }
+            Key::ArrowUp => selected = selected.saturating_sub(1),
+            Key::ArrowDown if selected + 1 < shown + new as usize => selected += 1,
+            Key::Enter => {
+                term.clear_last_lines(lines)?;
+                let objective = match items.get(selected).filter(|_| selected < shown) {
+                    Some(item) => item.to_string(),
+                    None => query.trim().to_string(),
+                };
+                return Ok(Some(objective));
+            }
+            Key::Escape => {
+                term.clear_last_lines(lines)?;
+                return Ok(None);
+            }
+            _ => {}
+        }
+    }
+}
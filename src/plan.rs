@@ -0,0 +1,187 @@
+//! Planned hours per objective for a week, kept next to the storage file in
+//! `<name>-plans.csv`, and how the tracked time compares to them.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Error, Result};
+use console::style;
+use time::{Date, Duration, OffsetDateTime};
+
+use crate::estimate::counts_for;
+use crate::{archive, format, git, human};
+
+/// A week, given by its monday.
+#[derive(Debug, Clone, Copy)]
+pub struct Week(Date);
+
+impl std::str::FromStr for Week {
+    type Err = Error;
+
+    /// `this`, `next`, `last` or any day of the week like `2024-03-04`.
+    fn from_str(s: &str) -> Result<Self> {
+        let today = OffsetDateTime::now_local().date();
+        let date = match s {
+            "this" => today,
+            "next" => today + Duration::weeks(1),
+            "last" => today - Duration::weeks(1),
+            _ => human::parse_date(s)?,
+        };
+        Ok(Week(
+            date - Duration::days(date.weekday().number_days_from_monday().into()),
+        ))
+    }
+}
+
+fn plans_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}-plans.csv", stem))
+}
+
+/// The planned minutes by week and objective.
+fn load(path: &Path) -> Result<BTreeMap<(Date, String), i64>> {
+    let file = plans_path(path);
+    if !file.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let mut reader = csv::Reader::from_path(&file)
+        .with_context(|| format!("Could not read {}", file.display()))?;
+    reader
+        .records()
+        .map(|record| {
+            let record = record?;
+            let invalid = || Error::msg(format!("Invalid plan in {}", file.display()));
+            let week =
+                Date::parse(record.get(0).unwrap_or_default(), "%F").map_err(|_| invalid())?;
+            let minutes = record
+                .get(2)
+                .and_then(|minutes| minutes.parse().ok())
+                .ok_or_else(invalid)?;
+            Ok((
+                (week, record.get(1).unwrap_or_default().to_string()),
+                minutes,
+            ))
+        })
+        .collect()
+}
+
+fn save(path: &Path, plans: &BTreeMap<(Date, String), i64>, message: &str) -> Result<()> {
+    let file = plans_path(path);
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(["Week", "Objective", "Minutes"])?;
+    for ((week, objective), minutes) in plans {
+        writer.write_record([week.format("%F"), objective.clone(), minutes.to_string()])?;
+    }
+    fs::write(&file, writer.into_inner()?)
+        .with_context(|| format!("Could not write {}", file.display()))?;
+    git::commit(&file, message)
+}
+
+pub fn set(path: &Path, week: Week, objective: &str, planned: Duration) -> Result<()> {
+    let mut plans = load(path)?;
+    plans.insert((week.0, objective.to_string()), planned.whole_minutes());
+    save(
+        path,
+        &plans,
+        &format!("plan: {} in week of {}", objective, week.0.format("%F")),
+    )?;
+    println!(
+        "Planned {} for {} in the week of {}",
+        format::duration(planned),
+        objective,
+        week.0.format("%F")
+    );
+    Ok(())
+}
+
+pub fn remove(path: &Path, week: Week, objective: &str) -> Result<()> {
+    let mut plans = load(path)?;
+    if plans.remove(&(week.0, objective.to_string())).is_none() {
+        return Err(Error::msg(format!(
+            "Nothing planned for {} in the week of {}",
+            objective,
+            week.0.format("%F")
+        )));
+    }
+    save(
+        path,
+        &plans,
+        &format!(
+            "plan: remove {} in week of {}",
+            objective,
+            week.0.format("%F")
+        ),
+    )
+}
+
+/// Prints the planned and tracked time per objective of `week`, the biggest
+/// deviations first. Tracked objectives without a plan are listed as well.
+pub fn report(path: &PathBuf, week: Week) -> Result<()> {
+    let planned = load(path)?
+        .into_iter()
+        .filter(|((monday, _), _)| *monday == week.0)
+        .map(|((_, objective), minutes)| (objective, Duration::minutes(minutes)))
+        .collect::<BTreeMap<_, _>>();
+    let sunday = week.0 + Duration::days(6);
+    let entries = archive::history(path)?
+        .into_iter()
+        .filter(|entry| (week.0..=sunday).contains(&entry.start.date()))
+        .collect::<Vec<_>>();
+    let mut rows = planned
+        .iter()
+        .map(|(objective, plan)| (objective.clone(), *plan, Duration::zero()))
+        .collect::<Vec<_>>();
+    for entry in &entries {
+        let duration = entry.end_or_now() - entry.start;
+        match rows
+            .iter_mut()
+            .find(|(objective, _, _)| counts_for(entry, objective))
+        {
+            Some(row) => row.2 += duration,
+            None => rows.push((entry.objective.clone(), Duration::zero(), duration)),
+        }
+    }
+    if rows.is_empty() {
+        println!(
+            "Nothing planned or tracked in the week of {}",
+            week.0.format("%F")
+        );
+        return Ok(());
+    }
+    rows.sort_by_key(|(_, plan, tracked)| std::cmp::Reverse((*tracked - *plan).abs()));
+    println!("Week of {}", week.0.format("%F"));
+    println!("Objective, Planned, Tracked, Deviation");
+    let (mut total_plan, mut total_tracked) = (Duration::zero(), Duration::zero());
+    for (objective, plan, tracked) in rows {
+        let deviation = tracked - plan;
+        let sign = if deviation < Duration::zero() {
+            "-"
+        } else {
+            "+"
+        };
+        let deviation_text = format!("{}{}", sign, format::duration(deviation.abs()));
+        // Off by more than a quarter of the plan, or not planned at all
+        let off = deviation.abs() * 4 > plan && deviation != Duration::zero();
+        let deviation_text = match (off, deviation < Duration::zero()) {
+            (false, _) => style(deviation_text),
+            (true, true) => style(deviation_text).yellow(),
+            (true, false) => style(deviation_text).red(),
+        };
+        println!(
+            "{}, {}, {}, {}",
+            objective,
+            format::duration(plan),
+            format::duration(tracked),
+            deviation_text
+        );
+        total_plan += plan;
+        total_tracked += tracked;
+    }
+    println!(
+        "Total, {}, {}",
+        format::duration(total_plan),
+        format::duration(total_tracked)
+    );
+    Ok(())
+}
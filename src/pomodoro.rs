@@ -0,0 +1,131 @@
+//! Pomodoro mode for `live`: work intervals are tracked as sessions, the
+//! breaks between them are left untracked.
+
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+
+use anyhow::{Error, Result};
+use console::Term;
+use crossbeam_channel::{select, tick};
+use time::{Duration, OffsetDateTime};
+
+use crate::{
+    ctrl_channel, human, last_of, note, notify, on_terminate, read, start, stop, Meta,
+    KEEP_ON_TERMINATE, TERMINATED,
+};
+
+/// The lengths of the work and break intervals, e.g. `25/5` in minutes.
+#[derive(Debug, Clone, Copy)]
+pub struct Pomodoro {
+    work: Duration,
+    rest: Duration,
+}
+
+impl std::str::FromStr for Pomodoro {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let length = |part: &str| match part.trim().parse::<u32>() {
+            Ok(minutes) => Ok(Duration::minutes(minutes.into())),
+            Err(_) => human::parse_duration(part),
+        };
+        match s.split_once('/') {
+            Some((work, rest)) => {
+                let (work, rest) = (length(work)?, length(rest)?);
+                if work <= Duration::zero() || rest <= Duration::zero() {
+                    return Err(Error::msg("Pomodoro intervals have to be longer than 0"));
+                }
+                Ok(Pomodoro { work, rest })
+            }
+            None => Err(Error::msg(format!(
+                "Invalid pomodoro {}, expected work/break in minutes, e.g. 25/5",
+                s
+            ))),
+        }
+    }
+}
+
+fn clock(duration: Duration) -> String {
+    format!(
+        "{:02}:{:02}",
+        duration.whole_minutes(),
+        duration.whole_seconds() % 60
+    )
+}
+
+/// Counts down alternating work and break intervals until SIGINT. A work
+/// interval continues the running session if there is one, each completed
+/// one is noted in its session.
+pub fn run(path: &PathBuf, timer: &str, objective: String, pomodoro: Pomodoro) -> Result<()> {
+    let data = read(path)?;
+    let term = Term::stdout();
+    term.clear_screen()?;
+    let (objective, mut since) = match last_of(&data, timer).map(|index| &data[index]) {
+        Some(entry) if entry.end.is_none() && objective.is_empty() => {
+            (entry.objective.clone(), entry.start)
+        }
+        Some(entry) if entry.end.is_none() => (objective, entry.start),
+        _ => {
+            start(path, timer, objective.clone(), Meta::new(), None, false)?;
+            (objective, OffsetDateTime::now_local())
+        }
+    };
+    println!(
+        "Pomodoro: {} work, {} break",
+        clock(pomodoro.work),
+        clock(pomodoro.rest)
+    );
+    let ctrl_c_events = ctrl_channel()?;
+    on_terminate();
+    let ticks = tick(std::time::Duration::from_secs(1));
+    let mut working = true;
+    let mut done = 0;
+    term.write_line("")?;
+    loop {
+        select! {
+            recv(ticks) -> _ => {
+                // The terminal may be gone, so the session is stopped quietly.
+                if TERMINATED.load(Ordering::SeqCst) {
+                    if working && !KEEP_ON_TERMINATE.load(Ordering::SeqCst) {
+                        stop(path, timer, Some(objective), None, false)?;
+                    }
+                    break;
+                }
+                let now = OffsetDateTime::now_local();
+                let length = if working { pomodoro.work } else { pomodoro.rest };
+                let left = since + length - now;
+                if left <= Duration::zero() {
+                    term.move_cursor_up(1)?;
+                    term.clear_line()?;
+                    term.write_str("\x07")?;
+                    if working {
+                        done += 1;
+                        note(path, timer, Some(format!("pomodoro {} done", done)), None)?;
+                        stop(path, timer, Some(objective.clone()), Some(now), false)?;
+                        notify::send("Pomodoro done", &format!("{} done, take a break", done));
+                    } else {
+                        start(path, timer, objective.clone(), Meta::new(), Some(now), false)?;
+                        notify::send("Break over", "Back to work");
+                    }
+                    working = !working;
+                    since = now;
+                    term.write_line("")?;
+                    continue;
+                }
+                term.move_cursor_up(1)?;
+                term.clear_line()?;
+                let phase = if working { "Work" } else { "Break" };
+                term.write_line(&format!("{}: {} left, {} done", phase, clock(left), done))?;
+            },
+            recv(ctrl_c_events) -> _ => {
+                println!();
+                if working {
+                    stop(path, timer, Some(objective).filter(|o| !o.is_empty()), None, true)?;
+                }
+                println!("Completed {} pomodoros", done);
+                break;
+            }
+        }
+    }
+    Ok(())
+}
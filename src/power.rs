@@ -0,0 +1,137 @@
+//! Suspend and screen lock events from logind, read by monitoring the
+//! system bus with `gdbus`.
+
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::OnceLock;
+
+use anyhow::{Context, Error, Result};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+
+use crate::config::Config;
+
+/// What `live` does when the machine is suspended or the screen is locked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Ignore,
+    Pause,
+    Stop,
+}
+
+impl std::str::FromStr for Action {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "ignore" => Ok(Action::Ignore),
+            "pause" => Ok(Action::Pause),
+            "stop" => Ok(Action::Stop),
+            _ => Err(Error::msg(format!(
+                "Invalid action {}, expected ignore, pause or stop",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Sleep,
+    Lock,
+}
+
+#[derive(Debug)]
+struct Settings {
+    on_sleep: Action,
+    on_lock: Action,
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// Reads the actions from the `[sessions]` config section, by default
+/// suspending and locking are ignored.
+pub fn configure(config: &Config) -> Result<()> {
+    let action = |key| -> Result<Action> {
+        match config.get("sessions", key) {
+            Some(action) => action.parse().with_context(|| format!("Invalid {}", key)),
+            None => Ok(Action::Ignore),
+        }
+    };
+    let _ = SETTINGS.set(Settings {
+        on_sleep: action("on_sleep")?,
+        on_lock: action("on_lock")?,
+    });
+    Ok(())
+}
+
+pub fn action(event: Event) -> Action {
+    match (SETTINGS.get(), event) {
+        (Some(settings), Event::Sleep) => settings.on_sleep,
+        (Some(settings), Event::Lock) => settings.on_lock,
+        (None, _) => Action::Ignore,
+    }
+}
+
+/// Receives logind's `PrepareForSleep` and session `Lock` signals until
+/// dropped. Without `gdbus` or logind nothing is ever received.
+pub struct Monitor {
+    receiver: Receiver<Event>,
+    // Kept so the channel never disconnects when the monitor exits.
+    _sender: Sender<Event>,
+    child: Option<Child>,
+}
+
+impl Monitor {
+    pub fn start() -> Monitor {
+        let (sender, receiver) = unbounded();
+        let child = Command::new("gdbus")
+            .args(["monitor", "--system", "--dest", "org.freedesktop.login1"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()
+            .map(|mut child| {
+                let stdout = child.stdout.take().expect("stdout is piped");
+                let sender = sender.clone();
+                std::thread::spawn(move || {
+                    for line in BufReader::new(stdout).lines() {
+                        let line = match line {
+                            Ok(line) => line,
+                            Err(_) => return,
+                        };
+                        // e.g. `/org/freedesktop/login1: org.freedesktop.login1.Manager.PrepareForSleep (true,)`
+                        let event = if line.contains(".PrepareForSleep (true,)") {
+                            Event::Sleep
+                        } else if line.contains("login1.Session.Lock ") {
+                            Event::Lock
+                        } else {
+                            continue;
+                        };
+                        if sender.send(event).is_err() {
+                            return;
+                        }
+                    }
+                });
+                child
+            });
+        Monitor {
+            receiver,
+            _sender: sender,
+            child,
+        }
+    }
+
+    pub fn receiver(&self) -> &Receiver<Event> {
+        &self.receiver
+    }
+}
+
+impl Drop for Monitor {
+    fn drop(&mut self) {
+        if let Some(child) = &mut self.child {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
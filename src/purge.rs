@@ -0,0 +1,67 @@
+//! `purge`, permanently removing old entries from the storage file and its
+//! archives, e.g. for data retention.
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use time::Date;
+
+use crate::{archive, backup, git, lock, read, store, write, Tracker};
+
+/// Removes all entries that started before `before`, after asking. Each
+/// file is backed up first as always, archives left empty are deleted.
+pub fn purge(path: &PathBuf, before: Date) -> Result<()> {
+    let old = |entry: &Tracker| entry.start.date() < before;
+    // Each file with its entries and the ones kept, the storage file last
+    let mut files = Vec::new();
+    for file in archive::archives(path)?
+        .into_iter()
+        .chain(Some(path.clone()))
+    {
+        let entries = read(&file)?;
+        let kept = entries
+            .iter()
+            .filter(|entry| !old(entry))
+            .cloned()
+            .collect::<Vec<_>>();
+        if kept.len() < entries.len() {
+            lock::check(path, &entries, &kept)?;
+            files.push((file, entries.len() - kept.len(), kept));
+        }
+    }
+    let count = files.iter().map(|(_, removed, _)| removed).sum::<usize>();
+    if count == 0 {
+        println!("No entries before {} to purge", before.format("%F"));
+        return Ok(());
+    }
+    if crate::dry_run() {
+        println!(
+            "Would purge {} entries before {}",
+            count,
+            before.format("%F")
+        );
+        return Ok(());
+    }
+    crate::confirm(&format!(
+        "Permanently remove {} entries started before {}?",
+        count,
+        before.format("%F")
+    ))?;
+    for (file, _, kept) in files {
+        if file == *path {
+            write(path, &kept)?;
+        } else if kept.is_empty() {
+            backup::backup(&file)?;
+            fs::remove_file(&file)?;
+            git::stage(&file)?;
+        } else {
+            store(&file, &kept)?;
+            git::stage(&file)?;
+        }
+    }
+    // The archives, if the storage file itself was unchanged
+    git::commit(path, &format!("purge: before {}", before.format("%F")))?;
+    println!("Purged {} entries before {}", count, before.format("%F"));
+    Ok(())
+}
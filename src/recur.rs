@@ -0,0 +1,125 @@
+//! Recurring entries from the `[recur]` config section, e.g.
+//! `standup = "mon-fri 09:30-09:45 Daily standup"`.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Error, Result};
+use time::{Date, Time};
+
+use crate::config::Config;
+use crate::{device, human, read, schedule, write, Meta, Tracker};
+
+#[derive(Debug)]
+struct Recurrence {
+    name: String,
+    days: [bool; 7],
+    start: Time,
+    end: Time,
+    objective: String,
+}
+
+impl std::str::FromStr for Recurrence {
+    type Err = Error;
+
+    /// Days, times and objective, e.g. `mon-fri 09:30-09:45 Daily standup`.
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.trim().splitn(3, ' ');
+        let days = schedule::parse_days(parts.next().unwrap_or_default())?;
+        let times = parts.next().unwrap_or_default();
+        let (start, end) = times
+            .split_once('-')
+            .and_then(|(start, end)| Some((human::clock(start)?, human::clock(end)?)))
+            .ok_or_else(|| {
+                Error::msg(format!(
+                    "Invalid times, expected e.g. 09:30-09:45: {}",
+                    times
+                ))
+            })?;
+        if end <= start {
+            return Err(Error::msg("A recurring entry has to end after it starts"));
+        }
+        Ok(Recurrence {
+            name: String::new(),
+            days,
+            start,
+            end,
+            objective: parts.next().unwrap_or_default().trim().to_string(),
+        })
+    }
+}
+
+fn load(config: &Config) -> Result<Vec<Recurrence>> {
+    config
+        .section("recur")
+        .into_iter()
+        .map(|(name, value)| {
+            let recurrence: Recurrence = value
+                .parse()
+                .with_context(|| format!("Invalid recurring entry {}", name))?;
+            Ok(Recurrence {
+                name: name.to_string(),
+                ..recurrence
+            })
+        })
+        .collect()
+}
+
+/// Adds the recurring entries for every day from `from` to `to`. Entries
+/// added before are skipped, as are ones overlapping other entries unless
+/// forced.
+pub fn materialize(
+    path: &PathBuf,
+    config: &Config,
+    from: Date,
+    to: Date,
+    force: bool,
+) -> Result<()> {
+    let mut recurrences = load(config)?;
+    recurrences.sort_by_key(|recurrence| recurrence.start);
+    if recurrences.is_empty() {
+        return Err(Error::msg(
+            "No recurring entries in the [recur] config section",
+        ));
+    }
+    let mut data = read(path)?;
+    let mut added = 0;
+    let mut date = from;
+    while date <= to {
+        for recurrence in &recurrences {
+            if !recurrence.days[date.weekday().number_days_from_monday() as usize] {
+                continue;
+            }
+            let mut meta = Meta::new();
+            meta.insert("recur".into(), recurrence.name.clone());
+            let entry = Tracker {
+                device: device::name(),
+                meta,
+                ..Tracker::new(
+                    human::local(date, recurrence.start),
+                    Some(human::local(date, recurrence.end)),
+                    recurrence.objective.clone(),
+                )
+            };
+            if data
+                .iter()
+                .any(|other| other.start == entry.start && other.meta == entry.meta)
+            {
+                continue;
+            }
+            if !force && data.iter().any(|other| other.overlaps(&entry)) {
+                println!("Skipped, overlaps another entry: {}", entry);
+                continue;
+            }
+            println!("Added: {}", entry);
+            data.push(entry);
+            added += 1;
+        }
+        date = date.next_day();
+    }
+    if added > 0 {
+        data.sort_by_key(|entry| entry.start);
+        write(path, &data)?;
+    }
+    println!("Added {} recurring entries", added);
+    Ok(())
+}
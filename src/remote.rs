@@ -0,0 +1,173 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::{Context, Error, Result};
+use time::{OffsetDateTime, PrimitiveDateTime};
+
+use crate::config::Config;
+use crate::http::Request;
+use crate::merge::{self, Strategy};
+use crate::{log, read, write};
+
+/// A copy of the storage file on a WebDAV server or in an S3 bucket,
+/// configured in the [remote] config section.
+struct Remote<'a> {
+    url: String,
+    user: Option<&'a str>,
+    password: &'a str,
+    /// The AWS signature provider, only set for S3
+    sigv4: Option<String>,
+}
+
+impl<'a> Remote<'a> {
+    fn from_config(config: &'a Config, path: &Path) -> Result<Self> {
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        let remote = match config.get("remote", "type").unwrap_or("webdav") {
+            "webdav" => Remote {
+                url: format!(
+                    "{}/{}",
+                    config.require("remote", "url")?.trim_end_matches('/'),
+                    name
+                ),
+                user: config.get("remote", "user"),
+                password: config.get("remote", "password").unwrap_or(""),
+                sigv4: None,
+            },
+            "s3" => Remote {
+                url: format!(
+                    "{}/{}/{}",
+                    config.require("remote", "endpoint")?.trim_end_matches('/'),
+                    config.require("remote", "bucket")?,
+                    config.get("remote", "key").unwrap_or(&name)
+                ),
+                user: Some(config.require("remote", "access_key")?),
+                password: config.require("remote", "secret_key")?,
+                sigv4: Some(format!(
+                    "aws:amz:{}:s3",
+                    config.get("remote", "region").unwrap_or("us-east-1")
+                )),
+            },
+            other => return Err(Error::msg(format!("Unknown remote type: {}", other))),
+        };
+        Ok(remote)
+    }
+
+    fn request(&self, method: &'a str) -> Request<'a> {
+        let mut request = Request::new(method, self.url.clone());
+        if let Some(user) = self.user {
+            request = request.basic_auth(user, self.password);
+        }
+        if let Some(provider) = &self.sigv4 {
+            request = request.aws_sigv4(provider.clone());
+        }
+        request
+    }
+
+    /// Downloads the remote file and its modification time, `None` if it
+    /// doesn't exist yet.
+    fn download(&self) -> Result<Option<(Vec<u8>, Option<OffsetDateTime>)>> {
+        let response = self.request("GET").send()?;
+        if response.status == 404 {
+            return Ok(None);
+        }
+        let response = response.error_for_status()?;
+        let modified = response
+            .header("last-modified")
+            .and_then(|date| {
+                PrimitiveDateTime::parse(date.trim_end_matches(" GMT"), "%a, %d %b %Y %T").ok()
+            })
+            .map(PrimitiveDateTime::assume_utc);
+        Ok(Some((response.body, modified)))
+    }
+
+    /// Replaces the remote file with the local one, only announced with
+    /// `--dry-run`.
+    fn upload(&self, path: &Path) -> Result<()> {
+        if crate::dry_run() {
+            println!("Would upload {} to {}", path.display(), self.url);
+            return Ok(());
+        }
+        let content = fs::read(path)?;
+        self.request("PUT")
+            .header("Content-Type", "text/csv")
+            .body(content)
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+fn modified(path: &Path) -> Result<Option<OffsetDateTime>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let modified = fs::metadata(path)?.modified()?;
+    let seconds = modified
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    Ok(Some(OffsetDateTime::from_unix_timestamp(seconds)))
+}
+
+/// Synchronizes the storage file with its remote copy. With the `lww`
+/// strategy the more recently modified file replaces the other, with `merge`
+/// (the default) both are merged, preferring local entries on overlaps.
+pub fn sync(path: &PathBuf, config: &Config) -> Result<()> {
+    let remote = Remote::from_config(config, path)?;
+    let strategy = config.get("remote", "strategy").unwrap_or("merge");
+    let (content, remote_modified) = match remote.download()? {
+        Some(download) => download,
+        None => {
+            remote.upload(path)?;
+            if !crate::dry_run() {
+                println!("Uploaded {}", path.display());
+            }
+            return Ok(());
+        }
+    };
+    let tmp = {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(".remote");
+        path.with_file_name(name)
+    };
+    fs::write(&tmp, content).with_context(|| format!("Could not write {}", tmp.display()))?;
+    let theirs = read(&tmp);
+    fs::remove_file(&tmp)?;
+    let theirs = theirs?;
+
+    match strategy {
+        "lww" => {
+            let local_modified = modified(path)?;
+            log::info(|| format!("Local: {:?}, remote: {:?}", local_modified, remote_modified));
+            if remote_modified > local_modified {
+                write(path, &theirs)?;
+                if !crate::dry_run() {
+                    println!("Downloaded the newer remote file");
+                }
+            } else {
+                remote.upload(path)?;
+                if !crate::dry_run() {
+                    println!("Uploaded the newer local file");
+                }
+            }
+        }
+        "merge" => {
+            let mut data = read(path)?;
+            let summary = merge::merge_entries(&mut data, theirs, Strategy::Ours)?;
+            if summary.added > 0 {
+                write(path, &data)?;
+            }
+            remote.upload(path)?;
+            if !crate::dry_run() {
+                println!("Merged {} remote entries and uploaded", summary.added);
+            }
+        }
+        other => return Err(Error::msg(format!("Unknown sync strategy: {}", other))),
+    }
+    Ok(())
+}
@@ -0,0 +1,573 @@
+//! The reports of `track-work report`, computed over the whole history
+//! including the archives.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+use anyhow::{Context, Error, Result};
+use console::style;
+use time::{Date, Duration, OffsetDateTime};
+
+use crate::config::Config;
+use crate::{archive, chart, format, human, Tracker};
+
+/// The tracked time per day, sessions count for the day they started on.
+fn per_day(entries: &[Tracker]) -> BTreeMap<Date, Duration> {
+    let mut days = BTreeMap::new();
+    for entry in entries {
+        *days
+            .entry(entry.start.date())
+            .or_insert_with(Duration::zero) += entry.end_or_now() - entry.start;
+    }
+    days
+}
+
+/// Cells from no time to the most time, lighter to darker.
+const SHADES: [char; 5] = ['·', '░', '▒', '▓', '█'];
+/// Greens for the shades, as 256 terminal colors.
+const GREENS: [u8; 5] = [240, 22, 28, 34, 40];
+
+/// The shade of a day with `duration` when `full` is the most expected.
+fn shade(duration: Duration, full: Duration) -> usize {
+    if duration <= Duration::zero() {
+        return 0;
+    }
+    let quarters = duration.whole_seconds() * 4 / full.whole_seconds().max(1);
+    (quarters as usize + 1).min(4)
+}
+
+fn cell(level: usize) -> String {
+    style(SHADES[level]).color256(GREENS[level]).to_string()
+}
+
+/// Prints a grid of the days of this month, or with `year` the last 53
+/// weeks, a row per weekday and a column per week, shaded by the time
+/// tracked relative to `target` or the busiest day.
+pub fn heatmap(path: &PathBuf, year: bool, target: Option<Duration>) -> Result<()> {
+    let today = OffsetDateTime::now_local().date();
+    let first = if year {
+        today - Duration::weeks(52)
+    } else {
+        Date::try_from_ymd(today.year(), today.month(), 1)?
+    };
+    let days = per_day(&archive::history(path)?);
+    let full = target
+        .or_else(|| days.range(first..=today).map(|(_, d)| *d).max())
+        .unwrap_or_else(|| Duration::hours(8));
+    // The grid starts on the monday of the first week
+    let start = first - Duration::days(first.weekday().number_days_from_monday().into());
+    let weeks = ((today - start).whole_days() / 7 + 1) as usize;
+    // Month names above the week they start in
+    let mut header = String::new();
+    let mut month = None;
+    for week in 0..weeks {
+        let day = (start + Duration::weeks(week as i64)).max(first);
+        let column = 4 + week * 2;
+        if month != Some(day.month()) && header.len() <= column {
+            month = Some(day.month());
+            header.push_str(&" ".repeat(column - header.len()));
+            header.push_str(&day.format("%b"));
+        }
+    }
+    println!("{}", header.trim_end());
+    for weekday in 0..7 {
+        let mut row = (start + Duration::days(weekday)).format("%a ");
+        for week in 0..weeks {
+            let date = start + Duration::days(weekday + 7 * week as i64);
+            if date < first || date > today {
+                row.push_str("  ");
+            } else {
+                let duration = days.get(&date).copied().unwrap_or_else(Duration::zero);
+                row.push_str(&cell(shade(duration, full)));
+                row.push(' ');
+            }
+        }
+        println!("{}", row.trim_end());
+    }
+    let total = days
+        .range(first..=today)
+        .map(|(_, duration)| *duration)
+        .fold(Duration::zero(), |acc, d| acc + d);
+    println!(
+        "Less {} More, {} days tracked, total {}",
+        (0..SHADES.len()).map(cell).collect::<Vec<_>>().join(""),
+        days.range(first..=today).count(),
+        format::duration(total)
+    );
+    Ok(())
+}
+
+/// The entries started within `from` and `to`, both optional and inclusive.
+fn within(entries: Vec<Tracker>, from: Option<Date>, to: Option<Date>) -> Vec<Tracker> {
+    entries
+        .into_iter()
+        .filter(|entry| {
+            let date = entry.start.date();
+            from.is_none_or(|from| date >= from) && to.is_none_or(|to| date <= to)
+        })
+        .collect()
+}
+
+/// Cuts a session at every full hour, so each part lies within one hour of
+/// one day.
+fn hourly(entry: &Tracker) -> Vec<(OffsetDateTime, Duration)> {
+    let end = entry.end_or_now();
+    let mut parts = Vec::new();
+    let mut at = entry.start;
+    while at < end {
+        let next_hour = at
+            - Duration::minutes(at.minute().into())
+            - Duration::seconds(at.second().into())
+            - Duration::nanoseconds(at.nanosecond().into())
+            + Duration::hour();
+        let until = next_hour.min(end);
+        parts.push((at, until - at));
+        at = until;
+    }
+    parts
+}
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Prints the time per weekday and per hour of the day, when the work
+/// actually happens.
+pub fn distribution(path: &PathBuf, from: Option<Date>, to: Option<Date>) -> Result<()> {
+    const WIDTH: usize = 40;
+    let mut weekdays = [Duration::zero(); 7];
+    let mut hours_of_day = [Duration::zero(); 24];
+    for entry in within(archive::history(path)?, from, to) {
+        for (at, duration) in hourly(&entry) {
+            weekdays[at.weekday().number_days_from_monday() as usize] += duration;
+            hours_of_day[at.hour() as usize] += duration;
+        }
+    }
+    let max = weekdays
+        .iter()
+        .max()
+        .copied()
+        .unwrap_or_else(Duration::zero);
+    println!("Weekday, Duration");
+    for (day, duration) in WEEKDAYS.iter().zip(&weekdays) {
+        let line = format!(
+            "{} {} {}",
+            day,
+            format::duration(*duration),
+            chart::bar(*duration, max, WIDTH, None)
+        );
+        println!("{}", line.trim_end());
+    }
+    let max = hours_of_day
+        .iter()
+        .max()
+        .copied()
+        .unwrap_or_else(Duration::zero);
+    println!("Hour, Duration");
+    for (hour, duration) in hours_of_day.iter().enumerate() {
+        let line = format!(
+            "{:02}  {} {}",
+            hour,
+            format::duration(*duration),
+            chart::bar(*duration, max, WIDTH, None)
+        );
+        println!("{}", line.trim_end());
+    }
+    Ok(())
+}
+
+/// The longest run of consecutive days in `days`, which are sorted.
+fn longest_streak<'a>(days: impl Iterator<Item = &'a Date>) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    let mut previous: Option<Date> = None;
+    for day in days {
+        current = match previous {
+            Some(previous) if previous.next_day() == *day => current + 1,
+            _ => 1,
+        };
+        longest = longest.max(current);
+        previous = Some(*day);
+    }
+    longest
+}
+
+/// Prints summary statistics of the entries started within the period.
+pub fn stats(path: &PathBuf, from: Option<Date>, to: Option<Date>) -> Result<()> {
+    let entries = within(archive::history(path)?, from, to);
+    if entries.is_empty() {
+        println!("No entries");
+        return Ok(());
+    }
+    let days = per_day(&entries);
+    let total = days.values().fold(Duration::zero(), |acc, d| acc + *d);
+    let mut sessions = entries
+        .iter()
+        .map(|entry| entry.end_or_now() - entry.start)
+        .collect::<Vec<_>>();
+    sessions.sort();
+    let median = match sessions.len() {
+        n if n % 2 == 1 => sessions[n / 2],
+        n => (sessions[n / 2 - 1] + sessions[n / 2]) / 2,
+    };
+    let longest = entries
+        .iter()
+        .max_by_key(|entry| entry.end_or_now() - entry.start)
+        .expect("there are entries");
+    let objectives = entries
+        .iter()
+        .map(|entry| entry.objective.as_str())
+        .filter(|objective| !objective.is_empty())
+        .collect::<BTreeSet<_>>();
+    let (first, last) = (days.keys().next(), days.keys().next_back());
+    println!("Total: {}", format::duration(total));
+    println!("Tracked days: {}", days.len());
+    println!(
+        "Average per tracked day: {}",
+        format::duration(total / days.len() as u32)
+    );
+    println!("Sessions: {}", sessions.len());
+    println!("Median session: {}", format::duration(median));
+    println!(
+        "Longest session: {} on {}, {}",
+        format::duration(longest.end_or_now() - longest.start),
+        longest.start.format("%F"),
+        longest.objective
+    );
+    println!("Longest streak: {} days", longest_streak(days.keys()));
+    println!("Objectives: {}", objectives.len());
+    if let (Some(first), Some(last)) = (first, last) {
+        println!("First day: {}", first.format("%F"));
+        println!("Last day: {}", last.format("%F"));
+    }
+    Ok(())
+}
+
+/// A named range of days for `compare`, e.g. `month-1` for last month.
+#[derive(Debug)]
+pub struct Span {
+    name: String,
+    from: Date,
+    to: Date,
+}
+
+/// The first day of the month `months` after the one of `date`, which may
+/// be negative.
+fn month_start(date: Date, months: i32) -> Result<Date> {
+    let index = date.year() * 12 + i32::from(date.month()) - 1 + months;
+    Ok(Date::try_from_ymd(
+        index.div_euclid(12),
+        (index.rem_euclid(12) + 1) as u8,
+        1,
+    )?)
+}
+
+impl std::str::FromStr for Span {
+    type Err = Error;
+
+    /// `month-N`, `week-N` or `year-N` for N of them ago, a month like
+    /// `2024-03` or days like `2024-03-01..2024-03-15`.
+    fn from_str(s: &str) -> Result<Self> {
+        let today = OffsetDateTime::now_local().date();
+        let back = |unit: &str| -> Option<i32> {
+            s.strip_prefix(unit)?
+                .strip_prefix('-')
+                .map_or(Some(0), |n| n.parse().ok())
+        };
+        let (from, to) = if let Some(n) = back("month") {
+            let from = month_start(today, -n)?;
+            (from, month_start(from, 1)?.previous_day())
+        } else if let Some(n) = back("week") {
+            let monday = today
+                - Duration::days(today.weekday().number_days_from_monday().into())
+                - Duration::weeks(n.into());
+            (monday, monday + Duration::days(6))
+        } else if let Some(n) = back("year") {
+            let year = today.year() - n;
+            (
+                Date::try_from_ymd(year, 1, 1)?,
+                Date::try_from_ymd(year, 12, 31)?,
+            )
+        } else if let Some((from, to)) = s.split_once("..") {
+            (human::parse_date(from)?, human::parse_date(to)?)
+        } else {
+            let from = Date::parse(format!("{}-01", s), "%F")
+                .map_err(|_| Error::msg(format!("Invalid period {}, expected e.g. month-1, week-0, 2024-03 or 2024-03-01..2024-03-15", s)))?;
+            (from, month_start(from, 1)?.previous_day())
+        };
+        Ok(Span {
+            name: s.to_string(),
+            from,
+            to,
+        })
+    }
+}
+
+fn per_objective(entries: &[Tracker], span: &Span) -> BTreeMap<String, Duration> {
+    let mut objectives = BTreeMap::new();
+    for entry in entries {
+        let date = entry.start.date();
+        if date >= span.from && date <= span.to {
+            *objectives
+                .entry(entry.objective.clone())
+                .or_insert_with(Duration::zero) += entry.end_or_now() - entry.start;
+        }
+    }
+    objectives
+}
+
+/// The change from `before` to `after` as a signed duration and percentage.
+fn change(before: Duration, after: Duration) -> String {
+    let delta = after - before;
+    let sign = if delta < Duration::zero() { "-" } else { "+" };
+    let percent = if before > Duration::zero() {
+        format!(
+            "{}{:.0}%",
+            sign,
+            (delta.as_seconds_f64() / before.as_seconds_f64() * 100.0).abs()
+        )
+    } else {
+        "new".to_string()
+    };
+    format!("{}{}, {}", sign, format::duration(delta.abs()), percent)
+}
+
+/// Prints the time per objective in both spans and how it changed from the
+/// second to the first.
+pub fn compare(path: &PathBuf, current: &Span, baseline: &Span) -> Result<()> {
+    let entries = archive::history(path)?;
+    let (now, before) = (
+        per_objective(&entries, current),
+        per_objective(&entries, baseline),
+    );
+    println!(
+        "{}: {} to {}, {}: {} to {}",
+        current.name,
+        current.from.format("%F"),
+        current.to.format("%F"),
+        baseline.name,
+        baseline.from.format("%F"),
+        baseline.to.format("%F")
+    );
+    println!("Objective, {}, {}, Change", current.name, baseline.name);
+    let objectives = now.keys().chain(before.keys()).collect::<BTreeSet<_>>();
+    let get = |map: &BTreeMap<String, Duration>, key: &String| {
+        map.get(key).copied().unwrap_or_else(Duration::zero)
+    };
+    for objective in objectives {
+        let (a, b) = (get(&now, objective), get(&before, objective));
+        let name = if objective.is_empty() {
+            "(none)"
+        } else {
+            objective
+        };
+        println!(
+            "{}, {}, {}, {}",
+            name,
+            format::duration(a),
+            format::duration(b),
+            change(b, a)
+        );
+    }
+    let sum =
+        |map: &BTreeMap<String, Duration>| map.values().fold(Duration::zero(), |acc, d| acc + *d);
+    let (a, b) = (sum(&now), sum(&before));
+    println!(
+        "Total, {}, {}, {}",
+        format::duration(a),
+        format::duration(b),
+        change(b, a)
+    );
+    Ok(())
+}
+
+/// Whether `entry` belongs to `project`: by its `project` metadata, or an
+/// objective that is the project or below it, e.g. `acme/backend`.
+fn in_project(entry: &Tracker, project: &str) -> bool {
+    entry.meta.get("project").is_some_and(|p| p == project)
+        || entry
+            .objective
+            .strip_prefix(project)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Prints the used and remaining hours of each budget of the `[budgets]`
+/// config section and, from the run rate of the last `RECENT` days, when
+/// it will be used up.
+pub fn budget(path: &PathBuf, config: &Config) -> Result<()> {
+    const RECENT: i64 = 28;
+    let budgets = config
+        .section("budgets")
+        .into_iter()
+        .map(|(project, budget)| {
+            let budget = human::parse_duration(budget)
+                .with_context(|| format!("Invalid budget for {}", project))?;
+            Ok((project, budget))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if budgets.is_empty() {
+        return Err(Error::msg(
+            "No budgets set, add e.g. acme = \"120h\" to the [budgets] config section",
+        ));
+    }
+    let entries = archive::history(path)?;
+    let today = OffsetDateTime::now_local().date();
+    let recent_start = today - Duration::days(RECENT - 1);
+    println!("Project, Budget, Used, Remaining, Per day, Used up");
+    for (project, budget) in budgets {
+        let mut used = Duration::zero();
+        let mut recent = Duration::zero();
+        for entry in entries.iter().filter(|entry| in_project(entry, project)) {
+            let duration = entry.end_or_now() - entry.start;
+            used += duration;
+            if entry.start.date() >= recent_start {
+                recent += duration;
+            }
+        }
+        let remaining = budget - used;
+        let rate = recent / RECENT as u32;
+        let exhausted = if remaining <= Duration::zero() {
+            "already".to_string()
+        } else if rate <= Duration::zero() {
+            "never at this rate".to_string()
+        } else {
+            let days = (remaining.as_seconds_f64() / rate.as_seconds_f64()).ceil() as i64;
+            (today + Duration::days(days)).format("%F")
+        };
+        let remaining = if remaining < Duration::zero() {
+            format!("-{}", format::duration(remaining.abs()))
+        } else {
+            format::duration(remaining)
+        };
+        println!(
+            "{}, {}, {}, {}, {}, {}",
+            project,
+            format::duration(budget),
+            format::duration(used),
+            remaining,
+            format::duration(rate),
+            exhausted
+        );
+    }
+    Ok(())
+}
+
+/// Prints the time tracked this month against the monthly `target` and the
+/// average needed on each remaining workday, today included, to reach it.
+/// The remaining workdays are counted from `workdays`, starting with
+/// monday, unless given as `days`.
+pub fn forecast(
+    path: &PathBuf,
+    target: Duration,
+    workdays: [bool; 7],
+    days: Option<u32>,
+) -> Result<()> {
+    let today = OffsetDateTime::now_local().date();
+    let from = month_start(today, 0)?;
+    let to = month_start(today, 1)?.previous_day();
+    let tracked = within(archive::history(path)?, Some(from), Some(to))
+        .iter()
+        .fold(Duration::zero(), |acc, entry| {
+            acc + (entry.end_or_now() - entry.start)
+        });
+    let days = days.unwrap_or_else(|| {
+        let mut count = 0;
+        let mut date = today;
+        while date <= to {
+            if workdays[date.weekday().number_days_from_monday() as usize] {
+                count += 1;
+            }
+            date = date.next_day();
+        }
+        count
+    });
+    let remaining = target - tracked;
+    println!("Target:    {}", format::duration(target));
+    println!("Tracked:   {}", format::duration(tracked));
+    if remaining <= Duration::zero() {
+        println!(
+            "Reached, {} over the target",
+            format::duration(remaining.abs())
+        );
+        return Ok(());
+    }
+    println!(
+        "Remaining: {} in {} workdays",
+        format::duration(remaining),
+        days
+    );
+    if days == 0 {
+        println!("No workdays left this month");
+    } else {
+        println!(
+            "Needed:    {} per workday",
+            format::duration(remaining / days)
+        );
+    }
+    Ok(())
+}
+
+/// The badges of the `[badges]` config section, a name per streak length
+/// in workdays, sorted by that length.
+fn badges(config: &Config) -> Result<Vec<(usize, String)>> {
+    let mut badges = config
+        .section("badges")
+        .into_iter()
+        .map(|(name, days)| {
+            let days = days
+                .parse()
+                .map_err(|_| Error::msg(format!("Invalid streak length for badge {}", name)))?;
+            Ok((days, name.to_string()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if badges.is_empty() {
+        badges = vec![
+            (5, "week".to_string()),
+            (20, "month".to_string()),
+            (60, "quarter".to_string()),
+            (220, "year".to_string()),
+        ];
+    }
+    badges.sort();
+    Ok(badges)
+}
+
+/// Prints the current and the longest run of consecutive workdays with
+/// tracked time, other days neither break nor extend a streak. Today only
+/// counts once something is tracked, so the current streak isn't broken
+/// before the day is over.
+pub fn streaks(path: &PathBuf, config: &Config, workdays: [bool; 7]) -> Result<()> {
+    let badges = badges(config)?;
+    let days = per_day(&archive::history(path)?);
+    let is_workday = |date: Date| workdays[date.weekday().number_days_from_monday() as usize];
+    let today = OffsetDateTime::now_local().date();
+    let (mut current, mut longest) = (0, 0);
+    if let Some(first) = days.keys().next() {
+        let mut date = *first;
+        while date <= today {
+            if is_workday(date) {
+                if days.contains_key(&date) {
+                    current += 1;
+                } else if date != today {
+                    current = 0;
+                }
+                longest = longest.max(current);
+            }
+            date = date.next_day();
+        }
+    }
+    println!("Current streak: {} workdays", current);
+    println!("Longest streak: {} workdays", longest);
+    for (length, name) in &badges {
+        if longest >= *length {
+            println!("{} {} ({} workdays)", style("★").yellow(), name, length);
+        } else {
+            println!(
+                "{} {} ({} workdays, {} to go)",
+                style("☆").dim(),
+                name,
+                length,
+                length - current
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,298 @@
+//! Regular working hours from the `[schedule]` config section. The daemon
+//! reminds about them or starts and stops tracking at their boundaries.
+
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+
+use anyhow::{Context, Error, Result};
+use time::{Date, Duration, OffsetDateTime, Time};
+
+use crate::config::Config;
+use crate::{human, last_of, mqtt, notify, read, start, stop, systemd, Meta};
+
+/// How often the daemon looks at the clock.
+const INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+/// How often the daemon looks for SIGTERM while waiting.
+const STEP: std::time::Duration = std::time::Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Notify if tracking doesn't match the working hours
+    Remind,
+    /// Start and stop tracking at the boundaries
+    Auto,
+}
+
+#[derive(Debug)]
+pub struct Schedule {
+    /// Working days, starting with monday
+    days: [bool; 7],
+    start: Time,
+    end: Time,
+    action: Action,
+    /// The objective of automatically started sessions
+    objective: String,
+    /// How often the daemon repeats the reminder while nothing is tracked
+    /// during the working hours
+    nag: Option<Duration>,
+}
+
+/// Days like `mon-fri`, `mon,wed,fri` or `mon-thu,sat`.
+pub fn parse_days(s: &str) -> Result<[bool; 7]> {
+    let day = |word: &str| {
+        human::weekday(word.trim())
+            .map(|day| day.number_days_from_monday() as usize)
+            .ok_or_else(|| Error::msg(format!("Invalid weekday: {}", word)))
+    };
+    let mut days = [false; 7];
+    for part in s.split(',') {
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (day(first)?, day(last)?);
+                // Ranges may wrap around the week, e.g. sat-mon
+                let mut current = first;
+                loop {
+                    days[current] = true;
+                    if current == last {
+                        break;
+                    }
+                    current = (current + 1) % 7;
+                }
+            }
+            None => days[day(part)?] = true,
+        }
+    }
+    Ok(days)
+}
+
+/// The working days of the `[schedule]` config section, monday to friday
+/// by default.
+pub fn workdays(config: &Config) -> Result<[bool; 7]> {
+    parse_days(config.get("schedule", "days").unwrap_or("mon-fri")).context("Invalid schedule days")
+}
+
+impl Schedule {
+    /// The schedule if the config has working hours.
+    pub fn load(config: &Config) -> Result<Option<Schedule>> {
+        let time = |key| -> Result<Option<Time>> {
+            config
+                .get("schedule", key)
+                .map(|value| {
+                    human::clock(value)
+                        .ok_or_else(|| Error::msg(format!("Invalid schedule {}: {}", key, value)))
+                })
+                .transpose()
+        };
+        let (start, end) = match (time("start")?, time("end")?) {
+            (Some(start), Some(end)) => (start, end),
+            (None, None) => return Ok(None),
+            _ => return Err(Error::msg("The schedule needs both a start and an end")),
+        };
+        if end <= start {
+            return Err(Error::msg("The schedule has to end after it starts"));
+        }
+        let days = workdays(config)?;
+        let action = match config.get("schedule", "action") {
+            None | Some("remind") => Action::Remind,
+            Some("auto") => Action::Auto,
+            Some(other) => {
+                return Err(Error::msg(format!(
+                    "Invalid schedule action {}, expected remind or auto",
+                    other
+                )))
+            }
+        };
+        let nag = match config.get("schedule", "remind_minutes") {
+            Some(minutes) => match minutes.parse::<i64>() {
+                Ok(0) => None,
+                Ok(minutes) if minutes > 0 => Some(Duration::minutes(minutes)),
+                _ => {
+                    return Err(Error::msg(format!(
+                        "Invalid schedule remind_minutes: {}",
+                        minutes
+                    )))
+                }
+            },
+            None => None,
+        };
+        Ok(Some(Schedule {
+            days,
+            start,
+            end,
+            action,
+            objective: config.get("schedule", "objective").unwrap_or("").into(),
+            nag,
+        }))
+    }
+
+    fn works_on(&self, date: Date) -> bool {
+        self.days[date.weekday().number_days_from_monday() as usize]
+    }
+
+    /// Whether `at` is within the working hours.
+    pub fn is_work_time(&self, at: OffsetDateTime) -> bool {
+        self.works_on(at.date()) && self.start <= at.time() && at.time() < self.end
+    }
+}
+
+fn is_running(path: &PathBuf, timer: &str) -> Result<bool> {
+    let data = read(path)?;
+    Ok(last_of(&data, timer).is_some_and(|index| data[index].end.is_none()))
+}
+
+/// Notifies if nothing is tracked although it's working time, returns
+/// whether it did.
+pub fn remind(path: &PathBuf, timer: &str, schedule: &Schedule) -> Result<bool> {
+    let now = OffsetDateTime::now_local();
+    if !schedule.is_work_time(now) || is_running(path, timer)? {
+        return Ok(false);
+    }
+    println!("Nothing is tracked during the working hours");
+    notify::send(
+        "Not tracking",
+        "It's working time but no session is running, start one with `track-work now`",
+    );
+    Ok(true)
+}
+
+/// Starts tracking or reminds to do so at the start of the working hours.
+fn on_start(path: &PathBuf, timer: &str, schedule: &Schedule, at: OffsetDateTime) -> Result<()> {
+    if is_running(path, timer)? {
+        return Ok(());
+    }
+    match schedule.action {
+        Action::Remind => notify::send(
+            "Work hours started",
+            "No session is running, start one with `track-work now`",
+        ),
+        Action::Auto => {
+            start(
+                path,
+                timer,
+                schedule.objective.clone(),
+                Meta::new(),
+                Some(at),
+                false,
+            )?;
+            println!("Started tracking at {}", at.format("%F %R"));
+            mqtt::event(path, timer, "started");
+            notify::send("Tracking started", &schedule.objective);
+        }
+    }
+    Ok(())
+}
+
+/// Stops tracking or reminds to do so at the end of the working hours.
+fn on_end(path: &PathBuf, timer: &str, schedule: &Schedule, at: OffsetDateTime) -> Result<()> {
+    let data = read(path)?;
+    let entry = match last_of(&data, timer).map(|index| &data[index]) {
+        Some(entry) if entry.end.is_none() => entry,
+        _ => return Ok(()),
+    };
+    match schedule.action {
+        Action::Remind => notify::send(
+            "Work hours ended",
+            &format!(
+                "A session is still running since {}",
+                entry.start.format("%R")
+            ),
+        ),
+        // A session started after the end of the working hours is kept
+        Action::Auto if entry.start < at => {
+            stop(path, timer, Some(entry.objective.clone()), Some(at), false)?;
+            println!("Stopped tracking at {}", at.format("%F %R"));
+            mqtt::event(path, timer, "stopped");
+            notify::send("Tracking stopped", &entry.objective);
+        }
+        Action::Auto => {}
+    }
+    Ok(())
+}
+
+/// Stops the running session on SIGTERM, unless `on_terminate` keeps it.
+fn on_terminate(path: &PathBuf, timer: &str) -> Result<()> {
+    systemd::notify("STOPPING=1");
+    if crate::KEEP_ON_TERMINATE.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+    let data = read(path)?;
+    if let Some(entry) = last_of(&data, timer)
+        .map(|index| &data[index])
+        .filter(|entry| entry.end.is_none())
+    {
+        stop(path, timer, Some(entry.objective.clone()), None, false)?;
+        println!("Stopped tracking {}", entry.objective);
+        mqtt::event(path, timer, "stopped");
+    }
+    Ok(())
+}
+
+/// Runs until killed, acting on the boundaries of the working hours. Errors
+/// are printed instead of ending the daemon. With `systemd` it notifies the
+/// service manager and ends on SIGTERM, stopping the running session.
+pub fn daemon(path: &PathBuf, timer: &str, schedule: &Schedule, systemd: bool) -> Result<()> {
+    let status = format!(
+        "Following the schedule {}-{}",
+        schedule.start.format("%R"),
+        schedule.end.format("%R")
+    );
+    println!("{}", status);
+    let mut last = OffsetDateTime::now_local();
+    let mut reminded = last;
+    // Started during the working hours, act as if the start just passed
+    if schedule.is_work_time(last) {
+        if let Err(e) = on_start(path, timer, schedule, last) {
+            eprintln!("Error: {:#}", e);
+        }
+    }
+    let watchdog = systemd::watchdog().filter(|_| systemd);
+    if systemd {
+        crate::on_terminate();
+        systemd::notify(&format!("READY=1\nSTATUS={}", status));
+    }
+    let step = watchdog.map_or(STEP, |interval| interval.min(STEP));
+    let mut pinged = Instant::now();
+    loop {
+        let slept = Instant::now();
+        while slept.elapsed() < INTERVAL && !crate::TERMINATED.load(Ordering::SeqCst) {
+            std::thread::sleep(step);
+            if watchdog.is_some_and(|interval| pinged.elapsed() >= interval) {
+                systemd::notify("WATCHDOG=1");
+                pinged = Instant::now();
+            }
+        }
+        if crate::TERMINATED.load(Ordering::SeqCst) {
+            return on_terminate(path, timer);
+        }
+        mqtt::tick(path);
+        let now = OffsetDateTime::now_local();
+        // Usually just today, but the machine may have been suspended
+        let mut date = last.date();
+        while date <= now.date() {
+            if schedule.works_on(date) {
+                let start = human::local(date, schedule.start);
+                let end = human::local(date, schedule.end);
+                if last < start && start <= now {
+                    if let Err(e) = on_start(path, timer, schedule, start) {
+                        eprintln!("Error: {:#}", e);
+                    }
+                }
+                if last < end && end <= now {
+                    if let Err(e) = on_end(path, timer, schedule, end) {
+                        eprintln!("Error: {:#}", e);
+                    }
+                }
+            }
+            date = date.next_day();
+        }
+        if schedule.nag.is_some_and(|nag| now - reminded >= nag) {
+            match remind(path, timer, schedule) {
+                Ok(true) => reminded = now,
+                Ok(false) => {}
+                Err(e) => eprintln!("Error: {:#}", e),
+            }
+        }
+        last = now;
+    }
+}
@@ -0,0 +1,210 @@
+//! Versioning of the storage file format. Files start with a
+//! `# track-work schema <version>` line, rows of older versions are upgraded
+//! when read and `migrate` rewrites the file in the current format.
+
+use std::path::PathBuf;
+
+use anyhow::{Error, Result};
+use csv::StringRecord;
+use time::{Format, OffsetDateTime};
+
+use crate::{git, read, store};
+
+/// The schema version this build reads and writes.
+/// 1: Start, End, Objective
+/// 2: added Device
+/// 3: added Notes
+/// 4: added Meta
+/// 5: added Timer
+/// 6: RFC 3339 times like 2022-03-01T09:00:00+01:00 instead of %F %T %z
+pub const VERSION: u32 = 6;
+
+/// The time format of schema 5 and before.
+pub const LEGACY_TIME_FORMAT: &str = "%F %T %z";
+
+const MARKER: &str = "# track-work schema ";
+
+/// The first line of every storage file.
+pub fn marker() -> String {
+    format!("{}{}\n", MARKER, VERSION)
+}
+
+/// The version given by the marker line at the start of `head`, if any.
+pub fn marked(head: &[u8]) -> Option<u32> {
+    let line = head.split(|b| *b == b'\n').next()?;
+    std::str::from_utf8(line)
+        .ok()?
+        .trim_end()
+        .strip_prefix(MARKER)?
+        .parse()
+        .ok()
+}
+
+/// The schema version of a file, from its marker or, for files written
+/// before versioning, from its header.
+pub fn version(marked: Option<u32>, headers: &StringRecord) -> Result<u32> {
+    let version = match marked {
+        Some(version) => version,
+        None if headers.get(3) == Some("Device") => 2,
+        None => 1,
+    };
+    if version > VERSION {
+        return Err(Error::msg(format!(
+            "The storage file has schema version {}, this version of track-work only supports up to {}",
+            version, VERSION
+        )));
+    }
+    Ok(version)
+}
+
+/// The number of columns of schema `version`.
+fn columns(version: u32) -> usize {
+    version.min(5) as usize + 2
+}
+
+/// A start or end as stored.
+pub fn format_time(at: OffsetDateTime) -> String {
+    at.format(Format::Rfc3339)
+}
+
+/// Parses a stored start or end, in RFC 3339 or the format of older
+/// schema versions, so rows are upgraded when read.
+pub fn parse_time(s: &str) -> Option<OffsetDateTime> {
+    OffsetDateTime::parse(s, Format::Rfc3339)
+        .or_else(|_| OffsetDateTime::parse(s, LEGACY_TIME_FORMAT))
+        .ok()
+}
+
+/// The names of columns beyond the ones of schema `version`, e.g. added by
+/// other tools. They are kept when the file is rewritten.
+pub fn unknown_columns(version: u32, headers: &StringRecord) -> Vec<String> {
+    headers
+        .iter()
+        .skip(columns(version))
+        .map(String::from)
+        .collect()
+}
+
+/// Converts a row of schema `version` into the current layout, followed by
+/// the values of unknown columns.
+pub fn upgrade(version: u32, record: StringRecord) -> StringRecord {
+    let mut upgraded = record
+        .iter()
+        .take(columns(version))
+        .collect::<StringRecord>();
+    if version < 2 {
+        upgraded.truncate(3);
+        upgraded.push_field("");
+    }
+    if version < 3 {
+        upgraded.truncate(4);
+        upgraded.push_field("");
+    }
+    if version < 4 {
+        upgraded.truncate(5);
+        upgraded.push_field("");
+    }
+    if version < 5 {
+        upgraded.truncate(6);
+        upgraded.push_field("");
+    }
+    upgraded.extend(record.iter().skip(columns(version)));
+    upgraded.set_position(record.position().cloned());
+    upgraded
+}
+
+/// Rewrites the storage file in the current schema version.
+pub fn migrate(path: &PathBuf) -> Result<()> {
+    let data = read(path)?;
+    if crate::dry_run() {
+        println!(
+            "Would migrate {} entries to schema version {}",
+            data.len(),
+            VERSION
+        );
+        return Ok(());
+    }
+    crate::confirm(&format!(
+        "Rewrite {} entries in schema version {}?",
+        data.len(),
+        VERSION
+    ))?;
+    store(path, &data)?;
+    git::commit(path, &format!("migrate: schema {}", VERSION))?;
+    println!(
+        "Migrated {} entries to schema version {}",
+        data.len(),
+        VERSION
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn times_in_both_formats() {
+        let legacy = parse_time("2022-03-01 09:00:00 +0100").unwrap();
+        let rfc3339 = parse_time("2022-03-01T09:00:00+01:00").unwrap();
+        assert_eq!(legacy, rfc3339);
+        assert_eq!(format_time(legacy), "2022-03-01T09:00:00+01:00");
+        assert!(parse_time("2022-03-01 09:00").is_none());
+    }
+
+    #[test]
+    fn old_rows_are_written_back_in_rfc3339() {
+        let dir = std::env::temp_dir().join(format!("track-work-schema-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("work.csv");
+        fs::write(
+            &path,
+            "# track-work schema 5\n\
+             Start,End,Objective,Device,Notes,Meta,Timer,Ticket\n\
+             2022-03-01 09:00:00 +0100,2022-03-01 10:30:00 +0100,review,laptop,,,,T-1\n\
+             2022-03-01 11:00:00 +0100,,write,,,,focus,\n",
+        )
+        .unwrap();
+        let data = read(&path).unwrap();
+        store(&path, &data).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        let again = read(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            written,
+            "# track-work schema 6\n\
+             Start,End,Objective,Device,Notes,Meta,Timer,Ticket\n\
+             2022-03-01T09:00:00+01:00,2022-03-01T10:30:00+01:00,review,laptop,,,,T-1\n\
+             2022-03-01T11:00:00+01:00,,write,,,,focus,\n"
+        );
+        assert_eq!(again.len(), 2);
+        for (old, new) in data.iter().zip(&again) {
+            assert_eq!(old.to_record(), new.to_record());
+            assert_eq!(old.extra, new.extra);
+        }
+    }
+
+    #[test]
+    fn unversioned_rows_are_upgraded() {
+        let headers = StringRecord::from(vec!["Start", "End", "Objective", "Device"]);
+        let old = version(None, &headers).unwrap();
+        assert_eq!(old, 2);
+        let row = StringRecord::from(vec!["2022-03-01 09:00:00 +0100", "", "review", "laptop"]);
+        let upgraded = upgrade(old, row);
+        assert_eq!(
+            upgraded.iter().collect::<Vec<_>>(),
+            [
+                "2022-03-01 09:00:00 +0100",
+                "",
+                "review",
+                "laptop",
+                "",
+                "",
+                ""
+            ]
+        );
+        assert!(version(Some(VERSION + 1), &headers).is_err());
+    }
+}
@@ -0,0 +1,64 @@
+//! Finds entries by their objective or notes across the whole history,
+//! including the yearly archives.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
+use time::Duration;
+
+use crate::{archive, Tracker};
+
+/// What to look for, matched case-insensitively.
+pub enum Pattern {
+    Plain(String),
+    Regex(Regex),
+}
+
+impl Pattern {
+    pub fn new(pattern: &str, regex: bool) -> Result<Pattern> {
+        Ok(if regex {
+            Pattern::Regex(
+                RegexBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .with_context(|| format!("Invalid regex: {}", pattern))?,
+            )
+        } else {
+            Pattern::Plain(pattern.to_lowercase())
+        })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        match self {
+            Pattern::Plain(pattern) => text.to_lowercase().contains(pattern),
+            Pattern::Regex(regex) => regex.is_match(text),
+        }
+    }
+}
+
+/// Prints the entries whose objective or notes match, with their total
+/// duration.
+pub fn search(path: &PathBuf, pattern: &Pattern) -> Result<()> {
+    let mut entries = archive::history(path)?;
+    entries.retain(|entry: &Tracker| {
+        pattern.is_match(&entry.objective) || entry.notes.lines().any(|line| pattern.is_match(line))
+    });
+    entries.sort_by_key(|entry| entry.start);
+    println!("Date, Start, End, Duration, Objective");
+    let mut total = Duration::zero();
+    for entry in &entries {
+        println!("{}", entry);
+        for line in entry.notes.lines().filter(|line| pattern.is_match(line)) {
+            println!("    {}", line);
+        }
+        total += entry.end_or_now() - entry.start;
+    }
+    println!(
+        "Total: {:02}:{:02} in {} entries",
+        total.whole_hours(),
+        total.whole_minutes() % 60,
+        entries.len()
+    );
+    Ok(())
+}
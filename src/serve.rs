@@ -0,0 +1,85 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{Error, Result};
+
+use crate::{ics, log, read, systemd};
+
+/// How long a client may take to send its request or read the response,
+/// requests are handled one at a time.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &str) -> Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+fn handle(path: &PathBuf, mut stream: TcpStream, notes: bool) -> Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut request_line = String::new();
+    let mut reader = BufReader::new(stream.try_clone()?);
+    reader.read_line(&mut request_line)?;
+    // Drain the headers, the body of a GET request is irrelevant.
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+    log::info(|| request_line.trim_end().to_string());
+    let mut parts = request_line.split_whitespace();
+    match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) if target == "/" || target.starts_with("/work.ics") => {
+            let data = read(path)?;
+            respond(
+                &mut stream,
+                "200 OK",
+                "text/calendar; charset=utf-8",
+                &ics::calendar(data.iter(), notes),
+            )
+        }
+        (Some("GET"), _) => respond(&mut stream, "404 Not Found", "text/plain", "Not found\n"),
+        _ => respond(
+            &mut stream,
+            "405 Method Not Allowed",
+            "text/plain",
+            "Method not allowed\n",
+        ),
+    }
+}
+
+/// Serves the tracked sessions as a read-only iCalendar feed at `/work.ics`,
+/// with their notes only if `notes` is set.
+pub fn serve(path: &PathBuf, bind: &str, ics: bool, notes: bool) -> Result<()> {
+    if !ics {
+        return Err(Error::msg("Nothing to serve, see serve --help"));
+    }
+    let listener = match systemd::listener() {
+        Some(listener) => listener,
+        None => TcpListener::bind(bind)?,
+    };
+    println!(
+        "Serving calendar feed at http://{}/work.ics",
+        listener.local_addr()?
+    );
+    systemd::notify("READY=1");
+    for stream in listener.incoming() {
+        match stream
+            .map_err(Error::from)
+            .and_then(|s| handle(path, s, notes))
+        {
+            Ok(()) => {}
+            Err(e) => eprintln!("Error handling request: {:#}", e),
+        }
+    }
+    Ok(())
+}
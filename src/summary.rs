@@ -0,0 +1,145 @@
+//! The summary printed after `now`, `stop`, `continue` and `switch`, by
+//! default a line like `Session: 01:30, today: 05:10 of 08:00, week: 12:30`.
+//! Its parts are chosen by `show` of the `[summary]` config section.
+
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::sync::OnceLock;
+
+use anyhow::{Error, Result};
+use time::{Date, Duration, OffsetDateTime};
+
+use crate::config::Config;
+use crate::{format, last_of, read, theme, Report, Tracker};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Part {
+    /// The last session of the timer
+    Session,
+    /// Worked today, against the daily target
+    Today,
+    /// Worked since Monday, against the weekly target
+    Week,
+    /// The days of the current month like `info`
+    Month,
+}
+
+impl std::str::FromStr for Part {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "session" => Ok(Part::Session),
+            "today" => Ok(Part::Today),
+            "week" => Ok(Part::Week),
+            "month" => Ok(Part::Month),
+            _ => Err(Error::msg(format!(
+                "Invalid summary part {}, expected session, today, week or month",
+                s
+            ))),
+        }
+    }
+}
+
+struct Settings {
+    parts: Vec<Part>,
+    daily: Option<Duration>,
+    weekly: Option<Duration>,
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+const DEFAULT: &str = "session,today,week";
+
+fn parse(show: &str) -> Result<Vec<Part>> {
+    show.split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+/// Reads the parts to show, an empty `show` turns the summary off.
+pub fn configure(config: &Config) -> Result<()> {
+    let parts = parse(config.get("summary", "show").unwrap_or(DEFAULT))?;
+    let _ = SETTINGS.set(Settings {
+        parts,
+        daily: crate::target(config, "daily")?,
+        weekly: crate::target(config, "weekly")?,
+    });
+    Ok(())
+}
+
+/// The time worked since `since`, running sessions until now. Entries that
+/// end before they start are left out.
+fn worked_since(data: &[Tracker], since: Date) -> Duration {
+    data.iter()
+        .filter(|entry| entry.start.date() >= since)
+        .map(|entry| entry.end_or_now() - entry.start)
+        .filter(|duration| !duration.is_negative())
+        .fold(Duration::zero(), |total, duration| total + duration)
+}
+
+/// `worked`, followed by the target if there is one.
+fn against(worked: Duration, target: Option<Duration>) -> String {
+    match target {
+        Some(target) if worked >= target => theme::reached(&format!(
+            "{} of {}",
+            format::duration(worked),
+            format::duration(target)
+        )),
+        Some(target) => format!(
+            "{} of {}",
+            format::duration(worked),
+            format::duration(target)
+        ),
+        None => format::duration(worked),
+    }
+}
+
+/// Prints the summary after a change to the sessions of `timer`, unless
+/// `--quiet` or `--dry-run` is given.
+pub fn print(path: &PathBuf, timer: &str) -> Result<()> {
+    if crate::QUIET.load(Ordering::SeqCst) || crate::dry_run() {
+        return Ok(());
+    }
+    let settings = SETTINGS.get_or_init(|| Settings {
+        parts: parse(DEFAULT).expect("the default parts are valid"),
+        daily: None,
+        weekly: None,
+    });
+    let data = read(path)?;
+    let today = OffsetDateTime::now_local().date();
+    let monday = today - Duration::days(today.weekday().number_days_from_monday().into());
+    let mut line = Vec::new();
+    for part in &settings.parts {
+        match part {
+            Part::Session => {
+                if let Some(entry) = last_of(&data, timer).map(|index| &data[index]) {
+                    let duration = format::duration(entry.end_or_now() - entry.start);
+                    line.push(match entry.end {
+                        Some(_) => format!("session: {}", duration),
+                        None => format!("session: {} running", duration),
+                    });
+                }
+            }
+            Part::Today => line.push(format!(
+                "today: {}",
+                against(worked_since(&data, today), settings.daily)
+            )),
+            Part::Week => line.push(format!(
+                "week: {}",
+                against(worked_since(&data, monday), settings.weekly)
+            )),
+            Part::Month => {}
+        }
+    }
+    if let Some(first) = line.first_mut() {
+        *first = first[..1].to_uppercase() + &first[1..];
+        println!("{}", line.join(", "));
+    }
+    if settings.parts.contains(&Part::Month) {
+        crate::info(path, &None, &Report::default())?;
+    }
+    Ok(())
+}
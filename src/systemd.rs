@@ -0,0 +1,218 @@
+//! Running under systemd: readiness and watchdog notifications for
+//! `daemon --systemd`, socket activation of `serve` and `install-service`,
+//! writing the user units.
+
+use std::fs;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Error, Result};
+
+#[cfg(unix)]
+use crate::log;
+
+/// Marks units written by `install-service`, others are never overwritten.
+const MARKER: &str = "# Installed by track-work";
+
+/// Sends `state` like `READY=1` to the service manager, if started by one
+/// with `Type=notify`. Failures are only logged.
+#[cfg(unix)]
+pub fn notify(state: &str) {
+    use std::os::unix::net::UnixDatagram;
+
+    let socket = match std::env::var("NOTIFY_SOCKET") {
+        Ok(socket) if !socket.is_empty() => socket,
+        _ => return,
+    };
+    log::debug(|| format!("Notifying systemd: {}", state));
+    let result = UnixDatagram::unbound().and_then(|datagram| match socket.strip_prefix('@') {
+        #[cfg(target_os = "linux")]
+        Some(name) => {
+            use std::os::linux::net::SocketAddrExt;
+            let address = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+            datagram.send_to_addr(state.as_bytes(), &address)
+        }
+        _ => datagram.send_to(state.as_bytes(), &socket),
+    });
+    if let Err(e) = result {
+        log::info(|| format!("Could not notify systemd at {}: {}", socket, e));
+    }
+}
+
+#[cfg(not(unix))]
+pub fn notify(_: &str) {}
+
+/// How often the watchdog wants `WATCHDOG=1`, half of the configured
+/// `WatchdogSec=` to be on the safe side.
+pub fn watchdog() -> Option<Duration> {
+    let usec = std::env::var("WATCHDOG_USEC").ok()?.parse::<u64>().ok()?;
+    if let Ok(pid) = std::env::var("WATCHDOG_PID") {
+        if pid.parse::<u32>().ok()? != std::process::id() {
+            return None;
+        }
+    }
+    Some(Duration::from_micros(usec / 2)).filter(|interval| !interval.is_zero())
+}
+
+/// The first socket passed by systemd socket activation, if any.
+#[cfg(unix)]
+pub fn listener() -> Option<TcpListener> {
+    use std::os::unix::io::FromRawFd;
+
+    /// The first passed descriptor, `SD_LISTEN_FDS_START`
+    const FIRST: i32 = 3;
+    let pid = std::env::var("LISTEN_PID").ok()?.parse::<u32>().ok()?;
+    let fds = std::env::var("LISTEN_FDS").ok()?.parse::<i32>().ok()?;
+    if pid != std::process::id() || fds < 1 {
+        return None;
+    }
+    // Not meant for child processes like git
+    std::env::remove_var("LISTEN_PID");
+    std::env::remove_var("LISTEN_FDS");
+    std::env::remove_var("LISTEN_FDNAMES");
+    if fds > 1 {
+        log::info(|| format!("Using the first of {} passed sockets", fds));
+    }
+    // SAFETY: systemd passes the descriptors starting at 3 to this process
+    // only, as checked by LISTEN_PID, and nothing else owns them.
+    Some(unsafe { TcpListener::from_raw_fd(FIRST) })
+}
+
+#[cfg(not(unix))]
+pub fn listener() -> Option<TcpListener> {
+    None
+}
+
+/// Quotes `s` for the command line of a unit.
+fn quote(s: &str) -> String {
+    format!(
+        "\"{}\"",
+        s.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('%', "%%")
+    )
+}
+
+/// `$XDG_CONFIG_HOME/systemd/user` falling back to `~/.config/systemd/user`.
+fn units_dir() -> Result<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .ok_or_else(|| Error::msg("Neither XDG_CONFIG_HOME nor HOME is set"))?;
+    Ok(base.join("systemd").join("user"))
+}
+
+/// The units by name: the daemon and, with `ics`, the calendar feed
+/// activated by a socket listening on that address.
+fn units(program: &str, ics: Option<&str>) -> Vec<(&'static str, String)> {
+    let mut units = vec![(
+        "track-work.service",
+        format!(
+            "{}\n\
+             [Unit]\n\
+             Description=track-work schedule daemon\n\
+             \n\
+             [Service]\n\
+             Type=notify\n\
+             ExecStart={} daemon --systemd\n\
+             Restart=on-failure\n\
+             \n\
+             [Install]\n\
+             WantedBy=default.target\n",
+            MARKER, program
+        ),
+    )];
+    if let Some(bind) = ics {
+        units.push((
+            "track-work-ics.socket",
+            format!(
+                "{}\n\
+                 [Unit]\n\
+                 Description=track-work calendar feed socket\n\
+                 \n\
+                 [Socket]\n\
+                 ListenStream={}\n\
+                 \n\
+                 [Install]\n\
+                 WantedBy=sockets.target\n",
+                MARKER, bind
+            ),
+        ));
+        units.push((
+            "track-work-ics.service",
+            format!(
+                "{}\n\
+                 [Unit]\n\
+                 Description=track-work calendar feed\n\
+                 Requires=track-work-ics.socket\n\
+                 \n\
+                 [Service]\n\
+                 Type=notify\n\
+                 ExecStart={} serve --ics\n",
+                MARKER, program
+            ),
+        ));
+    }
+    units
+}
+
+/// Writes the user units running this executable with the given storage
+/// file, config and timer.
+pub fn install(
+    path: &Path,
+    config_path: &Path,
+    profile: Option<&str>,
+    timer: &str,
+    ics: Option<&str>,
+) -> Result<()> {
+    let dir = units_dir()?;
+    let exe = std::env::current_exe().context("Could not find the track-work executable")?;
+    let absolute = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut program = format!(
+        "{} --file {}",
+        quote(&exe.to_string_lossy()),
+        quote(&absolute(path).to_string_lossy())
+    );
+    if config_path.exists() {
+        program += &format!(
+            " --config {}",
+            quote(&absolute(config_path).to_string_lossy())
+        );
+    }
+    if let Some(profile) = profile {
+        program += &format!(" --profile {}", quote(profile));
+    }
+    if !timer.is_empty() {
+        program += &format!(" --timer {}", quote(timer));
+    }
+    let units = units(&program, ics);
+    for (name, _) in &units {
+        let unit = dir.join(name);
+        if let Ok(existing) = fs::read_to_string(&unit) {
+            if !existing.contains(MARKER) && !crate::force() {
+                return Err(Error::msg(format!(
+                    "{} already exists, pass --force to replace it",
+                    unit.display()
+                )));
+            }
+        }
+    }
+    if crate::dry_run() {
+        for (name, _) in &units {
+            println!("Would write {}", dir.join(name).display());
+        }
+        return Ok(());
+    }
+    fs::create_dir_all(&dir).with_context(|| format!("Could not create {}", dir.display()))?;
+    for (name, content) in &units {
+        let unit = dir.join(name);
+        fs::write(&unit, content).with_context(|| format!("Could not write {}", unit.display()))?;
+        println!("Installed {}", unit.display());
+    }
+    println!("Enable with: systemctl --user daemon-reload && systemctl --user enable --now track-work.service");
+    if ics.is_some() {
+        println!("and: systemctl --user enable --now track-work-ics.socket");
+    }
+    Ok(())
+}
@@ -0,0 +1,96 @@
+//! Tables with padded, aligned columns for the terminal.
+
+use console::{measure_text_width, pad_str, truncate_str, Alignment, Term};
+
+enum Row {
+    Cells(Vec<String>),
+    /// Text below the previous row, e.g. its notes
+    Line(String),
+}
+
+/// A table whose last column is cut to the terminal width, so each row
+/// fits on one line.
+pub struct Table {
+    header: Vec<String>,
+    /// Columns to align right, like durations
+    right: Vec<usize>,
+    rows: Vec<Row>,
+}
+
+impl Table {
+    pub fn new(header: &[&str]) -> Table {
+        Table {
+            header: header.iter().map(|h| h.to_string()).collect(),
+            right: Vec::new(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Aligns `column` to the right.
+    pub fn align_right(mut self, column: usize) -> Table {
+        self.right.push(column);
+        self
+    }
+
+    pub fn row(&mut self, cells: Vec<String>) {
+        self.rows.push(Row::Cells(cells));
+    }
+
+    pub fn line(&mut self, text: String) {
+        self.rows.push(Row::Line(text));
+    }
+
+    pub fn print(&self) {
+        let mut widths = self
+            .header
+            .iter()
+            .map(|h| measure_text_width(h))
+            .collect::<Vec<_>>();
+        for row in &self.rows {
+            if let Row::Cells(cells) = row {
+                for (width, cell) in widths.iter_mut().zip(cells) {
+                    *width = (*width).max(measure_text_width(cell));
+                }
+            }
+        }
+        // Only the last column is cut, and only on a terminal
+        let columns = Term::stdout()
+            .size_checked()
+            .map(|(_, columns)| columns as usize);
+        if let (Some(columns), Some(last)) = (columns, widths.len().checked_sub(1)) {
+            let before = widths[..last].iter().map(|w| w + 2).sum::<usize>();
+            widths[last] = widths[last].min(columns.saturating_sub(before).max(10));
+        }
+        let format = |cells: &[String]| {
+            let line = cells
+                .iter()
+                .zip(&widths)
+                .enumerate()
+                .map(|(column, (cell, width))| {
+                    let align = if self.right.contains(&column) {
+                        Alignment::Right
+                    } else {
+                        Alignment::Left
+                    };
+                    if measure_text_width(cell) > *width {
+                        truncate_str(cell, *width, "…").into_owned()
+                    } else {
+                        pad_str(cell, *width, align, None).into_owned()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("  ");
+            line.trim_end().to_string()
+        };
+        println!("{}", format(&self.header));
+        for row in &self.rows {
+            match row {
+                Row::Cells(cells) => println!("{}", format(cells)),
+                Row::Line(text) => match columns {
+                    Some(columns) => println!("{}", truncate_str(text, columns, "…")),
+                    None => println!("{}", text),
+                },
+            }
+        }
+    }
+}
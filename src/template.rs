@@ -0,0 +1,57 @@
+//! Objective templates from the `[templates]` config section, e.g.
+//! `meeting = "Meeting: {topic} ({attendees})"`.
+
+use anyhow::{Error, Result};
+
+use crate::config::Config;
+
+/// A `--var` value as key=value.
+pub fn parse_var(s: &str) -> Result<(String, String)> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(Error::msg(format!(
+            "Invalid variable, expected key=value: {}",
+            s
+        ))),
+    }
+}
+
+/// The objective of template `name` with its placeholders filled in from
+/// `vars`, all of which have to be used.
+pub fn render(config: &Config, name: &str, vars: &[(String, String)]) -> Result<String> {
+    let template = config.get("templates", name).ok_or_else(|| {
+        Error::msg(format!(
+            "No template {} in the [templates] config section",
+            name
+        ))
+    })?;
+    let mut used = vec![false; vars.len()];
+    let mut objective = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        objective += &rest[..open];
+        let close = rest[open..]
+            .find('}')
+            .ok_or_else(|| Error::msg(format!("Unclosed placeholder in template {}", name)))?;
+        let key = &rest[open + 1..open + close];
+        let index = vars.iter().position(|(var, _)| var == key).ok_or_else(|| {
+            Error::msg(format!(
+                "Template {} needs a value for {}, pass --var {}=...",
+                name, key, key
+            ))
+        })?;
+        used[index] = true;
+        objective += &vars[index].1;
+        rest = &rest[open + close + 1..];
+    }
+    objective += rest;
+    if let Some(index) = used.iter().position(|used| !used) {
+        return Err(Error::msg(format!(
+            "Template {} has no placeholder {}",
+            name, vars[index].0
+        )));
+    }
+    Ok(objective)
+}
@@ -0,0 +1,107 @@
+//! Colored output, configured by `--color`, `NO_COLOR` and the `[theme]`
+//! config section.
+
+use std::sync::OnceLock;
+
+use anyhow::{Error, Result};
+use console::Style;
+
+use crate::config::Config;
+
+/// When to color the output.
+#[derive(Debug, Clone, Copy)]
+pub enum ColorMode {
+    /// On a terminal unless `NO_COLOR` is set
+    Auto,
+    Always,
+    Never,
+}
+
+impl std::str::FromStr for ColorMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "auto" => Ok(ColorMode::Auto),
+            "always" => Ok(ColorMode::Always),
+            "never" => Ok(ColorMode::Never),
+            _ => Err(Error::msg(format!(
+                "Invalid color mode {}, expected auto, always or never",
+                s
+            ))),
+        }
+    }
+}
+
+struct Theme {
+    today: Style,
+    /// Days that reached the daily target
+    reached: Style,
+    /// Days below the daily target
+    missed: Style,
+    objective: Style,
+    /// Sessions that are still running
+    running: Style,
+    /// Entries that end before they start
+    invalid: Style,
+}
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Enables or disables colors and loads the styles of the `[theme]` config
+/// section, dotted like `red.bold`.
+pub fn configure(config: &Config, mode: ColorMode) {
+    match mode {
+        ColorMode::Always => console::set_colors_enabled(true),
+        ColorMode::Never => console::set_colors_enabled(false),
+        ColorMode::Auto => {
+            if std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+                console::set_colors_enabled(false);
+            }
+        }
+    }
+    let style = |key, default| Style::from_dotted_str(config.get("theme", key).unwrap_or(default));
+    THEME.get_or_init(|| Theme {
+        today: style("today", "bold"),
+        reached: style("reached", "green"),
+        missed: style("missed", "red"),
+        objective: style("objective", "dim"),
+        running: style("running", "yellow"),
+        invalid: style("invalid", "red.bold"),
+    });
+}
+
+fn theme() -> &'static Theme {
+    THEME.get_or_init(|| Theme {
+        today: Style::new(),
+        reached: Style::new(),
+        missed: Style::new(),
+        objective: Style::new(),
+        running: Style::new(),
+        invalid: Style::new(),
+    })
+}
+
+pub fn today(text: &str) -> String {
+    theme().today.apply_to(text).to_string()
+}
+
+pub fn reached(text: &str) -> String {
+    theme().reached.apply_to(text).to_string()
+}
+
+pub fn missed(text: &str) -> String {
+    theme().missed.apply_to(text).to_string()
+}
+
+pub fn objective(text: &str) -> String {
+    theme().objective.apply_to(text).to_string()
+}
+
+pub fn running(text: &str) -> String {
+    theme().running.apply_to(text).to_string()
+}
+
+pub fn invalid(text: &str) -> String {
+    theme().invalid.apply_to(text).to_string()
+}